@@ -21,6 +21,7 @@ struct OrderManager {
     max_orders_per_symbol: usize,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct OrderInfo {
     order_id: String,
@@ -34,6 +35,7 @@ struct OrderInfo {
     take_profit: Option<f64>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct PositionLimit {
     symbol: String,
@@ -52,7 +54,10 @@ impl OrderManager {
         }
     }
 
-    /// Place an order with automatic stop loss and take profit
+    /// Place an order with automatic stop loss and take profit.
+    ///
+    /// With native Lighter keys, `LighterFfiTradingClient::place_bracket`
+    /// links the children on the exchange and cancels the survivor on fill.
     async fn place_order_with_sl_tp(
         &mut self,
         symbol: &str,
@@ -63,7 +68,7 @@ impl OrderManager {
         take_profit_pct: f64,
     ) -> Result<String, Box<dyn std::error::Error>> {
         // Check position limits
-        if !self.check_position_limit(symbol, quantity, side.clone()) {
+        if !self.check_position_limit(symbol, quantity, side) {
             return Err("Position limit exceeded".into());
         }
 
@@ -99,7 +104,7 @@ impl OrderManager {
             .orders()
             .create_order(
                 symbol,
-                side.clone(),
+                side,
                 OrderType::Limit,
                 &quantity.to_string(),
                 Some(&price.to_string()),
@@ -149,7 +154,8 @@ impl OrderManager {
         Ok(order_id)
     }
 
-    /// Implement a grid trading strategy
+    /// Implement a grid trading strategy. See `lighter_rust::strategy::GridStrategy`
+    /// for a version that re-places filled levels and survives restarts.
     async fn setup_grid_orders(
         &mut self,
         symbol: &str,
@@ -286,7 +292,7 @@ impl OrderManager {
                             }
                             self.active_orders.remove(&order_id);
                         }
-                        OrderStatus::Cancelled | OrderStatus::Rejected => {
+                        OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired => {
                             println!("  Order ended: {:?}", order.status);
                             self.active_orders.remove(&order_id);
                         }
@@ -401,14 +407,15 @@ impl OrderManager {
     }
 
     /// Cancel all orders for a symbol
+    #[allow(dead_code)]
     async fn cancel_all_symbol_orders(
         &mut self,
         symbol: &str,
     ) -> Result<u32, Box<dyn std::error::Error>> {
         println!("Cancelling all orders for {}", symbol);
 
-        // TODO: Implement cancel_all_orders in OrderApi
-        // let count = self.client.orders().cancel_all_orders(Some(symbol)).await?;
+        // The REST OrderApi has no cancel-all; with native Lighter keys use
+        // LighterFfiTradingClient::cancel_all_orders(Some(symbol)) instead.
         let count = 0u32; // Placeholder
 
         // Remove from local tracking
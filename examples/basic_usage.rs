@@ -145,7 +145,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!(
                     "Page {}/{}, Total: {}",
                     pagination.page,
-                    (pagination.total + pagination.limit as u64 - 1) / pagination.limit as u64,
+                    pagination.total.div_ceil(pagination.limit as u64),
                     pagination.total
                 );
             }
@@ -1,4 +1,3 @@
-use lighter_rust::api::CandlestickInterval;
 use lighter_rust::{init_logging, Config, LighterClient, OrderType, Side};
 use std::collections::VecDeque;
 use tokio::time::{sleep, Duration};
@@ -124,7 +123,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // Print account summary every 5 iterations
-        if price_history.len() % 5 == 0 {
+        if price_history.len().is_multiple_of(5) {
             print_account_summary(&client).await;
         }
 
@@ -0,0 +1,822 @@
+use crate::api::transaction_api::{LighterTransactionApi, TxResponse};
+use crate::auth::AuthTokenProvider;
+use crate::client::ApiClient;
+use crate::config::Config;
+use crate::error::{LighterError, Result};
+use crate::ffi_client::{fetch_account_snapshot, AccountSnapshot};
+use crate::models::common::parse_timestamp;
+use crate::nonce::NonceManager;
+use crate::signers::FFISigner;
+use crate::trading::{build_signing_url, scale_usdc};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Lighter account type of a master account.
+const MASTER_ACCOUNT_TYPE: i32 = 0;
+/// Lighter account type of a sub-account.
+const SUB_ACCOUNT_TYPE: i32 = 1;
+
+/// An account owned by an L1 address, as listed by
+/// [`AccountManager::list_accounts`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubAccount {
+    #[serde(alias = "index")]
+    pub account_index: i64,
+    #[serde(default)]
+    pub l1_address: String,
+    /// `0` for the master account, `1` for sub-accounts.
+    #[serde(default)]
+    pub account_type: i32,
+    #[serde(default)]
+    pub collateral: String,
+    #[serde(default)]
+    pub total_order_count: i64,
+    #[serde(default)]
+    pub status: i32,
+}
+
+impl SubAccount {
+    pub fn is_master(&self) -> bool {
+        self.account_type == MASTER_ACCOUNT_TYPE
+    }
+
+    pub fn is_sub_account(&self) -> bool {
+        self.account_type == SUB_ACCOUNT_TYPE
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AccountsByL1Response {
+    #[serde(default)]
+    sub_accounts: Vec<SubAccount>,
+}
+
+/// Recipient of a transfer: a Lighter account index, or an L1 address
+/// standing for the master account it owns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Receiver {
+    Account(i64),
+    L1Address(String),
+}
+
+impl From<i64> for Receiver {
+    fn from(account_index: i64) -> Self {
+        Self::Account(account_index)
+    }
+}
+
+impl FromStr for Receiver {
+    type Err = LighterError;
+
+    /// Parse `0x`-prefixed addresses as L1 addresses and plain numbers as
+    /// account indices.
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if value.starts_with("0x") || value.starts_with("0X") {
+            return validate_l1_address(value).map(Self::L1Address);
+        }
+        value
+            .parse::<i64>()
+            .ok()
+            .filter(|index| *index >= 0)
+            .map(Self::Account)
+            .ok_or_else(|| {
+                LighterError::AccountState(format!(
+                    "receiver {value:?} is neither an account index nor an L1 address"
+                ))
+            })
+    }
+}
+
+impl fmt::Display for Receiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Account(index) => write!(f, "account {index}"),
+            Self::L1Address(address) => f.write_str(address),
+        }
+    }
+}
+
+/// Lifecycle of a Lighter L2 transaction, as reported by `/tx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    Pending,
+    Queued,
+    Committed,
+    Executed,
+    Failed,
+    Rejected,
+}
+
+impl TxStatus {
+    pub fn from_lighter_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Pending),
+            1 => Some(Self::Queued),
+            2 => Some(Self::Committed),
+            3 => Some(Self::Executed),
+            4 => Some(Self::Failed),
+            5 => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Self::Failed | Self::Rejected)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawTx {
+    #[serde(default)]
+    status: i32,
+}
+
+/// A submitted transfer between Lighter accounts.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub to_account_index: i64,
+    pub amount: Decimal,
+    pub response: TxResponse,
+}
+
+impl Transfer {
+    pub fn tx_hash(&self) -> Option<&str> {
+        self.response.tx_hash.as_deref()
+    }
+}
+
+/// Where a withdrawal stands on its way to L1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WithdrawalStatus {
+    /// The L2 transaction has not executed yet.
+    Pending,
+    /// Executed on Lighter; funds are on their way to L1.
+    Processing,
+    /// Ready to be claimed on L1.
+    Claimable,
+    /// Settled on L1.
+    Completed {
+        l1_tx_hash: Option<String>,
+    },
+    Failed(String),
+}
+
+impl WithdrawalStatus {
+    /// Whether nothing more happens without action on L1.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::Claimable | Self::Completed { .. } | Self::Failed(_)
+        )
+    }
+}
+
+/// A withdrawal followed with [`AccountManager::refresh_withdrawal`].
+#[derive(Debug, Clone)]
+pub struct Withdrawal {
+    pub l1_address: String,
+    pub amount: Decimal,
+    pub tx_hash: Option<String>,
+    pub submitted_at: DateTime<Utc>,
+    pub status: WithdrawalStatus,
+    /// Key of the `/withdraw/history` entry this withdrawal was matched to.
+    pub history_id: Option<String>,
+}
+
+/// Entry of `/withdraw/history`.
+#[derive(Debug, Clone, Deserialize)]
+struct WithdrawalRecord {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(default)]
+    tx_hash: String,
+    #[serde(default)]
+    amount: String,
+    #[serde(default)]
+    timestamp: i64,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    l1_tx_hash: String,
+}
+
+impl WithdrawalRecord {
+    /// Identifies the entry across polls: its id, else its L2 tx hash, else
+    /// its time and amount.
+    fn key(&self) -> String {
+        match &self.id {
+            Some(serde_json::Value::String(id)) if !id.is_empty() => id.clone(),
+            Some(id @ serde_json::Value::Number(_)) => id.to_string(),
+            _ if !self.tx_hash.is_empty() => self.tx_hash.clone(),
+            _ => format!("{}@{}", self.amount, self.timestamp),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WithdrawalHistoryResponse {
+    #[serde(default)]
+    withdraws: Vec<WithdrawalRecord>,
+}
+
+/// History timestamps may trail the submission slightly.
+const HISTORY_CLOCK_SKEW: Duration = Duration::minutes(1);
+
+/// Creates sub-accounts, lists the accounts of an L1 address and moves
+/// collateral between them.
+///
+/// Transactions are signed by the account the manager is built for, so
+/// collateral always leaves that account. To move funds from a sub-account
+/// back to the master, build a manager with the sub-account's API key.
+pub struct AccountManager {
+    transaction_api: LighterTransactionApi,
+    api_client: ApiClient,
+    nonce_manager: NonceManager,
+    account_index: i32,
+    /// History entries already matched to a withdrawal, so two withdrawals
+    /// of the same amount are not both resolved from one entry.
+    claimed_withdrawals: Mutex<HashSet<String>>,
+}
+
+impl AccountManager {
+    pub async fn new(
+        config: Config,
+        private_key: &str,
+        account_index: i32,
+        api_key_index: i32,
+    ) -> Result<Self> {
+        let api_client = ApiClient::new(config.clone())?;
+        let initial_nonce = api_client
+            .fetch_next_nonce(account_index, api_key_index)
+            .await?;
+        let signing_url = build_signing_url(&config.base_url)?;
+        let signer = Arc::new(FFISigner::new(
+            &signing_url,
+            private_key,
+            api_key_index,
+            account_index,
+        )?);
+        let auth = Arc::new(AuthTokenProvider::new(signer.clone()));
+        let api_client = api_client.with_auth_token_provider(auth);
+        let transaction_api = LighterTransactionApi::with_shared_signer(api_client.clone(), signer);
+
+        Ok(Self {
+            transaction_api,
+            api_client,
+            nonce_manager: NonceManager::with_seed(initial_nonce),
+            account_index,
+            claimed_withdrawals: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Manager whose signer cannot sign, for tests of the checks made
+    /// before a transaction is signed.
+    #[cfg(all(test, unix))]
+    fn unsigned(config: Config, account_index: i32) -> Result<Self> {
+        let api_client = ApiClient::new(config)?;
+        let signer = Arc::new(FFISigner::unlinked(0, account_index));
+        Ok(Self {
+            transaction_api: LighterTransactionApi::with_shared_signer(api_client.clone(), signer),
+            api_client,
+            nonce_manager: NonceManager::with_seed(0),
+            account_index,
+            claimed_withdrawals: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn account_index(&self) -> i32 {
+        self.account_index
+    }
+
+    /// Snapshot of any account, e.g. a sub-account after funding it.
+    pub async fn account(&self, account_index: i64) -> Result<AccountSnapshot> {
+        fetch_account_snapshot(&self.api_client, account_index).await
+    }
+
+    /// Every account owned by `l1_address`, master first as returned by the
+    /// exchange.
+    pub async fn list_accounts(&self, l1_address: &str) -> Result<Vec<SubAccount>> {
+        fetch_accounts_by_l1(&self.api_client, l1_address).await
+    }
+
+    /// Sub-accounts sharing this account's L1 address.
+    pub async fn sub_accounts(&self) -> Result<Vec<SubAccount>> {
+        let own = self.account(i64::from(self.account_index)).await?;
+        let accounts = self.list_accounts(&own.l1_address).await?;
+        Ok(accounts
+            .into_iter()
+            .filter(SubAccount::is_sub_account)
+            .collect())
+    }
+
+    /// Create a sub-account under this account's L1 address. Must be signed
+    /// by the master account; the new account index shows up in
+    /// [`Self::sub_accounts`] once the transaction is executed.
+    pub async fn create_sub_account(&self) -> Result<TxResponse> {
+        let nonce = self.nonce_manager.generate()? as i64;
+        self.transaction_api.create_sub_account(nonce).await
+    }
+
+    /// Move `amount` USDC from this account to `to`, which must be an
+    /// existing account other than this one. An L1 address resolves to the
+    /// master account it owns.
+    pub async fn transfer(&self, to: impl Into<Receiver>, amount: Decimal) -> Result<Transfer> {
+        let scaled = scale_usdc(amount)?;
+        let to_account_index = self.resolve(to.into()).await?;
+        if to_account_index == i64::from(self.account_index) {
+            return Err(LighterError::AccountState(format!(
+                "cannot transfer from account {to_account_index} to itself"
+            )));
+        }
+        let nonce = self.nonce_manager.generate()? as i64;
+        let response = self
+            .transaction_api
+            .transfer(to_account_index, scaled, nonce)
+            .await?;
+        Ok(Transfer {
+            to_account_index,
+            amount,
+            response,
+        })
+    }
+
+    /// Withdraw `amount` USDC to L1. Lighter only pays out to the account's
+    /// own L1 address, so `l1_address` must match it; this guards against
+    /// sending a withdrawal meant for another account.
+    pub async fn withdraw(&self, l1_address: &str, amount: Decimal) -> Result<Withdrawal> {
+        let l1_address = validate_l1_address(l1_address)?;
+        let scaled = scale_usdc(amount)?;
+        let own = self.account(i64::from(self.account_index)).await?;
+        if !own.l1_address.eq_ignore_ascii_case(&l1_address) {
+            return Err(LighterError::AccountState(format!(
+                "account {} withdraws to {}, not {l1_address}",
+                self.account_index, own.l1_address
+            )));
+        }
+
+        let nonce = self.nonce_manager.generate()? as i64;
+        let submitted_at = Utc::now();
+        let response = self
+            .transaction_api
+            .withdraw(&l1_address, scaled, nonce)
+            .await?;
+        Ok(Withdrawal {
+            l1_address,
+            amount,
+            tx_hash: response.tx_hash,
+            submitted_at,
+            status: WithdrawalStatus::Pending,
+            history_id: None,
+        })
+    }
+
+    /// Status of an L2 transaction. `None` until the exchange knows it.
+    pub async fn tx_status(&self, tx_hash: &str) -> Result<Option<TxStatus>> {
+        let endpoint = format!("/tx?by=hash&value={tx_hash}");
+        let raw: RawTx = match self.api_client.get(&endpoint).await {
+            Ok(raw) => raw,
+            Err(LighterError::Api { status: 404, .. }) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(TxStatus::from_lighter_code(raw.status))
+    }
+
+    /// Update `withdrawal` from its L2 transaction and, once executed, the
+    /// account's withdrawal history.
+    pub async fn refresh_withdrawal(&self, withdrawal: &mut Withdrawal) -> Result<()> {
+        if withdrawal.status.is_final() && withdrawal.status != WithdrawalStatus::Claimable {
+            return Ok(());
+        }
+        if withdrawal.status == WithdrawalStatus::Pending {
+            if let Some(tx_hash) = &withdrawal.tx_hash {
+                match self.tx_status(tx_hash).await? {
+                    Some(status) if status.is_failure() => {
+                        withdrawal.status =
+                            WithdrawalStatus::Failed(format!("L2 transaction {status:?}"));
+                        return Ok(());
+                    }
+                    Some(TxStatus::Executed) => {}
+                    _ => return Ok(()),
+                }
+            }
+        }
+
+        let endpoint = format!("/withdraw/history?account_index={}", self.account_index);
+        let history: WithdrawalHistoryResponse = self.api_client.get(&endpoint).await?;
+        let record = {
+            let mut claimed = self
+                .claimed_withdrawals
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let record = match_withdrawal(withdrawal, &history.withdraws, &claimed);
+            if let (None, Some(record)) = (&withdrawal.history_id, record) {
+                let key = record.key();
+                claimed.insert(key.clone());
+                withdrawal.history_id = Some(key);
+            }
+            record
+        };
+        withdrawal.status = match record {
+            Some(record) => record_status(record),
+            None => WithdrawalStatus::Processing,
+        };
+        Ok(())
+    }
+
+    /// Poll until `withdrawal` completes, becomes claimable on L1 or fails,
+    /// giving up after `timeout`. A failed withdrawal is returned as an error.
+    pub async fn wait_for_withdrawal(
+        &self,
+        withdrawal: &mut Withdrawal,
+        poll_interval: std::time::Duration,
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            self.refresh_withdrawal(withdrawal).await?;
+            match &withdrawal.status {
+                WithdrawalStatus::Failed(reason) => {
+                    return Err(LighterError::AccountState(format!(
+                        "withdrawal of {} USDC failed: {reason}",
+                        withdrawal.amount
+                    )))
+                }
+                status if status.is_final() => return Ok(()),
+                _ => {}
+            }
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Err(LighterError::Unknown(format!(
+                    "withdrawal of {} USDC still {:?} after {timeout:?}",
+                    withdrawal.amount, withdrawal.status
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    async fn resolve(&self, receiver: Receiver) -> Result<i64> {
+        match receiver {
+            Receiver::Account(index) => match self.account(index).await {
+                Ok(_) => Ok(index),
+                Err(LighterError::Api { status: 404, .. }) => Err(LighterError::AccountState(
+                    format!("receiver account {index} does not exist"),
+                )),
+                Err(err) => Err(err),
+            },
+            Receiver::L1Address(address) => {
+                let address = validate_l1_address(&address)?;
+                self.list_accounts(&address)
+                    .await?
+                    .into_iter()
+                    .find(SubAccount::is_master)
+                    .map(|account| account.account_index)
+                    .ok_or_else(|| {
+                        LighterError::AccountState(format!("no Lighter account owned by {address}"))
+                    })
+            }
+        }
+    }
+}
+
+async fn fetch_accounts_by_l1(api_client: &ApiClient, l1_address: &str) -> Result<Vec<SubAccount>> {
+    let endpoint = format!("/accountsByL1Address?l1_address={l1_address}");
+    let response: AccountsByL1Response = api_client.get(&endpoint).await?;
+    Ok(response.sub_accounts)
+}
+
+fn validate_l1_address(address: &str) -> Result<String> {
+    let address = address.trim();
+    let digits = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"));
+    let valid = digits
+        .is_some_and(|digits| digits.len() == 40 && digits.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(LighterError::AccountState(format!(
+            "{address:?} is not a valid L1 address"
+        )));
+    }
+    Ok(address.to_string())
+}
+
+/// The history entry of `withdrawal`: the one it was matched to before, else
+/// the entry carrying its L2 tx hash. Entries without a hash fall back to the
+/// earliest one for the same amount recorded since the withdrawal was
+/// submitted that no other withdrawal has `claimed`.
+fn match_withdrawal<'a>(
+    withdrawal: &Withdrawal,
+    records: &'a [WithdrawalRecord],
+    claimed: &HashSet<String>,
+) -> Option<&'a WithdrawalRecord> {
+    if let Some(history_id) = &withdrawal.history_id {
+        return records.iter().find(|record| &record.key() == history_id);
+    }
+    if let Some(tx_hash) = &withdrawal.tx_hash {
+        let by_hash = records
+            .iter()
+            .find(|record| record.tx_hash.eq_ignore_ascii_case(tx_hash));
+        if by_hash.is_some() {
+            return by_hash;
+        }
+    }
+    let since = withdrawal.submitted_at - HISTORY_CLOCK_SKEW;
+    records
+        .iter()
+        .filter(|record| record.tx_hash.is_empty() && !claimed.contains(&record.key()))
+        .filter(|record| Decimal::from_str(&record.amount).ok() == Some(withdrawal.amount))
+        .filter_map(|record| Some((parse_timestamp(record.timestamp)?, record)))
+        .filter(|(at, _)| *at >= since)
+        .min_by_key(|(at, _)| *at)
+        .map(|(_, record)| record)
+}
+
+fn record_status(record: &WithdrawalRecord) -> WithdrawalStatus {
+    let l1_tx_hash = (!record.l1_tx_hash.is_empty()).then(|| record.l1_tx_hash.clone());
+    match record.status.to_ascii_lowercase().as_str() {
+        "completed" | "complete" | "finalized" => WithdrawalStatus::Completed { l1_tx_hash },
+        "claimable" => WithdrawalStatus::Claimable,
+        "failed" | "refunded" | "rejected" => WithdrawalStatus::Failed(record.status.clone()),
+        _ if l1_tx_hash.is_some() => WithdrawalStatus::Completed { l1_tx_hash },
+        _ => WithdrawalStatus::Processing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_accounts_by_l1_address() {
+        let raw = r#"{
+            "code": 200,
+            "l1_address": "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0",
+            "sub_accounts": [
+                {
+                    "code": 0,
+                    "account_type": 0,
+                    "index": 70407,
+                    "l1_address": "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0",
+                    "cancel_all_time": 0,
+                    "total_order_count": 3,
+                    "pending_order_count": 0,
+                    "status": 1,
+                    "collateral": "20721.549363"
+                },
+                {
+                    "account_type": 1,
+                    "index": 281474976624800,
+                    "l1_address": "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0",
+                    "status": 1,
+                    "collateral": "0.000000"
+                }
+            ]
+        }"#;
+        let response: AccountsByL1Response = serde_json::from_str(raw).expect("valid json");
+        assert_eq!(response.sub_accounts.len(), 2);
+        assert!(response.sub_accounts[0].is_master());
+        assert_eq!(response.sub_accounts[0].account_index, 70407);
+        assert!(response.sub_accounts[1].is_sub_account());
+        assert_eq!(response.sub_accounts[1].account_index, 281474976624800);
+    }
+
+    #[test]
+    fn parses_and_validates_receivers() {
+        assert_eq!(
+            "281474976624800".parse::<Receiver>().unwrap(),
+            Receiver::Account(281474976624800)
+        );
+        assert_eq!(
+            "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0"
+                .parse::<Receiver>()
+                .unwrap(),
+            Receiver::L1Address("0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0".to_string())
+        );
+        assert!("0x166ed9f7".parse::<Receiver>().is_err());
+        assert!("-3".parse::<Receiver>().is_err());
+        assert!("1e6".parse::<Receiver>().is_err());
+    }
+
+    #[test]
+    fn matches_withdrawal_history() {
+        let submitted_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let withdrawal = Withdrawal {
+            l1_address: "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0".to_string(),
+            amount: Decimal::from(25),
+            tx_hash: Some("0b1c5f".to_string()),
+            submitted_at,
+            status: WithdrawalStatus::Processing,
+            history_id: None,
+        };
+        let raw = r#"{
+            "code": 200,
+            "withdraws": [
+                {"amount": "25.000000", "timestamp": 1699990000000, "status": "completed", "l1_tx_hash": "0xold"},
+                {"amount": "10.000000", "timestamp": 1700000100000, "status": "claimable"},
+                {"amount": "25.000000", "timestamp": 1700000100000, "status": "pending"},
+                {"amount": "25.000000", "timestamp": 1700000900000, "status": "claimable"}
+            ]
+        }"#;
+        let mut history: WithdrawalHistoryResponse = serde_json::from_str(raw).unwrap();
+        let claimed = HashSet::new();
+        let record = match_withdrawal(&withdrawal, &history.withdraws, &claimed).unwrap();
+        assert_eq!(record_status(record), WithdrawalStatus::Processing);
+
+        history.withdraws[2].status = "completed".to_string();
+        history.withdraws[2].l1_tx_hash = "0xabc".to_string();
+        let record = match_withdrawal(&withdrawal, &history.withdraws, &claimed).unwrap();
+        assert_eq!(
+            record_status(record),
+            WithdrawalStatus::Completed {
+                l1_tx_hash: Some("0xabc".to_string())
+            }
+        );
+        assert_eq!(TxStatus::from_lighter_code(3), Some(TxStatus::Executed));
+        assert!(TxStatus::Rejected.is_failure());
+    }
+
+    #[test]
+    fn matches_withdrawal_by_hash_and_skips_claimed_entries() {
+        let submitted_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let withdrawal = |tx_hash: &str| Withdrawal {
+            l1_address: "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0".to_string(),
+            amount: Decimal::from(25),
+            tx_hash: Some(tx_hash.to_string()),
+            submitted_at,
+            status: WithdrawalStatus::Processing,
+            history_id: None,
+        };
+        let raw = r#"{
+            "code": 200,
+            "withdraws": [
+                {"id": 7, "amount": "25.000000", "timestamp": 1700000100000, "status": "claimable"},
+                {"id": 8, "amount": "25.000000", "timestamp": 1700000200000, "status": "pending"},
+                {"id": 9, "tx_hash": "0b1c5f", "amount": "25.000000", "timestamp": 1700000300000, "status": "completed"}
+            ]
+        }"#;
+        let history: WithdrawalHistoryResponse = serde_json::from_str(raw).unwrap();
+
+        // The entry carrying the withdrawal's own hash wins over earlier ones.
+        let mut claimed = HashSet::new();
+        let record = match_withdrawal(&withdrawal("0B1C5F"), &history.withdraws, &claimed);
+        assert_eq!(record.unwrap().key(), "9");
+
+        // Without a hash in the history, an entry claimed by one withdrawal
+        // is not handed to another of the same amount.
+        let first = match_withdrawal(&withdrawal("aa"), &history.withdraws, &claimed).unwrap();
+        assert_eq!(first.key(), "7");
+        claimed.insert(first.key());
+        let second = match_withdrawal(&withdrawal("bb"), &history.withdraws, &claimed).unwrap();
+        assert_eq!(second.key(), "8");
+
+        // Once matched, a withdrawal keeps resolving to its own entry.
+        let mut pinned = withdrawal("aa");
+        pinned.history_id = Some("7".to_string());
+        let record = match_withdrawal(&pinned, &history.withdraws, &claimed).unwrap();
+        assert_eq!(record_status(record), WithdrawalStatus::Claimable);
+    }
+
+    #[test]
+    fn rejects_malformed_l1_addresses() {
+        assert!(validate_l1_address("0X166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0").is_ok());
+        // Multibyte input of the right byte length must not panic.
+        assert!(validate_l1_address("é166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0").is_err());
+        assert!(validate_l1_address("0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8bé").is_err());
+        assert!(validate_l1_address("0x").is_err());
+    }
+
+    #[test]
+    fn maps_history_statuses() {
+        let record = |status: &str, l1_tx_hash: &str| WithdrawalRecord {
+            id: None,
+            tx_hash: String::new(),
+            amount: "25.000000".to_string(),
+            timestamp: 1_700_000_000_000,
+            status: status.to_string(),
+            l1_tx_hash: l1_tx_hash.to_string(),
+        };
+        assert_eq!(
+            record_status(&record("Completed", "")),
+            WithdrawalStatus::Completed { l1_tx_hash: None }
+        );
+        assert_eq!(
+            record_status(&record("claimable", "")),
+            WithdrawalStatus::Claimable
+        );
+        assert_eq!(
+            record_status(&record("refunded", "")),
+            WithdrawalStatus::Failed("refunded".to_string())
+        );
+        // An L1 hash means the payout happened, whatever the status says.
+        assert_eq!(
+            record_status(&record("pending", "0xabc")),
+            WithdrawalStatus::Completed {
+                l1_tx_hash: Some("0xabc".to_string())
+            }
+        );
+        assert_eq!(
+            record_status(&record("pending", "")),
+            WithdrawalStatus::Processing
+        );
+    }
+
+    #[cfg(unix)]
+    const OWN_ADDRESS: &str = "0x166ed9f7A56053c7c4E77CB0C91a9E46bbC5e8b0";
+
+    /// Manager for account 70407, owned by `OWN_ADDRESS`, against a mock API
+    /// that fails the test if anything is sent to `sendTx`.
+    #[cfg(unix)]
+    async fn guarded_manager(server: &mut mockito::ServerGuard) -> (AccountManager, mockito::Mock) {
+        let account = serde_json::json!({
+            "code": 200,
+            "accounts": [{ "account_index": 70407, "l1_address": OWN_ADDRESS }]
+        });
+        server
+            .mock("GET", "/api/v1/account")
+            .match_query(mockito::Matcher::UrlEncoded("value".into(), "70407".into()))
+            .with_status(200)
+            .with_body(account.to_string())
+            .create_async()
+            .await;
+        let send = server
+            .mock("POST", "/api/v1/sendTx")
+            .expect(0)
+            .create_async()
+            .await;
+        let config = Config::new().with_base_url(server.url()).unwrap();
+        (AccountManager::unsigned(config, 70407).unwrap(), send)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn withdraws_only_to_own_l1_address() {
+        let mut server = mockito::Server::new_async().await;
+        let (manager, send) = guarded_manager(&mut server).await;
+
+        let err = manager
+            .withdraw(
+                "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb1",
+                Decimal::from(25),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LighterError::AccountState(_)));
+        send.assert_async().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn rejects_transfers_to_itself() {
+        let mut server = mockito::Server::new_async().await;
+        let (manager, send) = guarded_manager(&mut server).await;
+        let _accounts = server
+            .mock("GET", "/api/v1/accountsByL1Address")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "code": 200,
+                    "sub_accounts": [{ "index": 70407, "account_type": 0 }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        for receiver in [
+            Receiver::Account(70407),
+            Receiver::L1Address(OWN_ADDRESS.to_string()),
+        ] {
+            let err = manager
+                .transfer(receiver, Decimal::from(25))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, LighterError::AccountState(_)));
+        }
+        send.assert_async().await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn unknown_transactions_have_no_status() {
+        let mut server = mockito::Server::new_async().await;
+        let (manager, _send) = guarded_manager(&mut server).await;
+        let _tx = server
+            .mock("GET", "/api/v1/tx")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("by".into(), "hash".into()),
+                mockito::Matcher::UrlEncoded("value".into(), "0b1c5f".into()),
+            ]))
+            .with_status(404)
+            .with_body(r#"{"code": 404, "message": "tx not found"}"#)
+            .create_async()
+            .await;
+
+        assert_eq!(manager.tx_status("0b1c5f").await.unwrap(), None);
+    }
+}
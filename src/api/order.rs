@@ -201,10 +201,10 @@ impl OrderApi {
         let response: ApiResponse<serde_json::Value> =
             self.signer_client.delete_signed(&endpoint).await?;
 
-        if response.error.is_some() {
+        if let Some(message) = response.error {
             return Err(crate::error::LighterError::Api {
                 status: 500,
-                message: response.error.unwrap(),
+                message,
             });
         }
 
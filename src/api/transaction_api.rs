@@ -1,8 +1,9 @@
 use crate::client::ApiClient;
 use crate::error::{LighterError, Result};
-use crate::signers::FFISigner;
+use crate::signers::{FFISigner, GroupedOrderLeg, SignedCreateOrder};
 use serde::{Deserialize, Serialize};
 use serde_json;
+use std::sync::Arc;
 use tracing::debug;
 
 #[derive(Debug, Clone, Serialize)]
@@ -11,22 +12,81 @@ struct SendTxRequest {
     tx_info: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct SendTxBatchRequest {
+    tx_types: String,
+    tx_infos: String,
+}
+
+/// Largest number of transactions Lighter accepts in one batch.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+const TX_TYPE_CREATE_SUB_ACCOUNT: i32 = 9;
 const TX_TYPE_CREATE_ORDER: i32 = 14;
 const TX_TYPE_CANCEL_ORDER: i32 = 15;
 const TX_TYPE_CANCEL_ALL_ORDERS: i32 = 16;
+const TX_TYPE_MODIFY_ORDER: i32 = 17;
 const TX_TYPE_TRANSFER: i32 = 12;
 const TX_TYPE_WITHDRAW: i32 = 13;
+const TX_TYPE_UPDATE_LEVERAGE: i32 = 20;
+const TX_TYPE_CREATE_GROUPED_ORDERS: i32 = 28;
+const TX_TYPE_UPDATE_MARGIN: i32 = 29;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxResponse {
     pub code: i32,
+    #[serde(default)]
     pub tx_hash: Option<String>,
+    #[serde(default)]
     pub message: Option<String>,
+    /// Exchange estimate of when the tx will be executed, in unix millis.
+    #[serde(default)]
+    pub predicted_execution_time_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchTxResponse {
+    pub code: i32,
+    #[serde(default)]
+    pub tx_hash: Vec<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub predicted_execution_time_ms: Option<i64>,
+}
+
+/// Unsigned transaction that can be submitted as part of a batch.
+#[derive(Debug, Clone)]
+pub enum BatchTx {
+    CreateOrder {
+        market_index: i32,
+        client_order_index: i64,
+        base_amount: i64,
+        price: i32,
+        is_ask: bool,
+        order_type: crate::models::common::OrderType,
+        time_in_force: crate::models::order::TimeInForce,
+        reduce_only: bool,
+        trigger_price: i32,
+        order_expiry: i64,
+    },
+    CancelOrder {
+        market_index: i32,
+        client_cancel_index: i64,
+        order_id: String,
+    },
+    ModifyOrder {
+        market_index: i32,
+        order_index: i64,
+        base_amount: i64,
+        price: i64,
+        trigger_price: i64,
+    },
 }
 
 pub struct LighterTransactionApi {
     client: ApiClient,
-    signer: FFISigner,
+    signer: Arc<FFISigner>,
 }
 
 impl LighterTransactionApi {
@@ -38,13 +98,21 @@ impl LighterTransactionApi {
         account_index: i32,
     ) -> Result<Self> {
         let signer = FFISigner::new(url, private_key, api_key_index, account_index)?;
-        Ok(Self { client, signer })
+        Ok(Self::with_signer(client, signer))
     }
 
     pub fn with_signer(client: ApiClient, signer: FFISigner) -> Self {
+        Self::with_shared_signer(client, Arc::new(signer))
+    }
+
+    pub fn with_shared_signer(client: ApiClient, signer: Arc<FFISigner>) -> Self {
         Self { client, signer }
     }
 
+    pub fn signer(&self) -> &Arc<FFISigner> {
+        &self.signer
+    }
+
     async fn send_tx(&self, tx_type: i32, tx_info: String) -> Result<TxResponse> {
         let payload = SendTxRequest { tx_type, tx_info };
 
@@ -65,6 +133,110 @@ impl LighterTransactionApi {
         Ok(response)
     }
 
+    async fn send_tx_batch(
+        &self,
+        tx_types: Vec<i32>,
+        tx_infos: Vec<String>,
+    ) -> Result<BatchTxResponse> {
+        let payload = SendTxBatchRequest {
+            tx_types: serde_json::to_string(&tx_types)?,
+            tx_infos: serde_json::to_string(&tx_infos)?,
+        };
+
+        debug!(target: "lighter::http", count = tx_types.len(), "Sending Lighter HTTP sendTxBatch request");
+
+        let response: BatchTxResponse = self.client.post("/sendTxBatch", Some(payload)).await?;
+
+        if response.code != 200 {
+            return Err(LighterError::Api {
+                status: response.code as u16,
+                message: response
+                    .message
+                    .unwrap_or("Transaction batch failed".to_string()),
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Sign `txs` with consecutive nonces starting at `first_nonce` and submit
+    /// them in a single request.
+    pub async fn submit_batch(&self, txs: &[BatchTx], first_nonce: i64) -> Result<BatchTxResponse> {
+        if txs.is_empty() || txs.len() > MAX_BATCH_SIZE {
+            return Err(LighterError::OrderValidation(format!(
+                "transaction batch must contain between 1 and {MAX_BATCH_SIZE} txs, got {}",
+                txs.len()
+            )));
+        }
+
+        let mut tx_types = Vec::with_capacity(txs.len());
+        let mut tx_infos = Vec::with_capacity(txs.len());
+        for (offset, tx) in txs.iter().enumerate() {
+            let (tx_type, tx_info) = self.sign_batch_tx(tx, first_nonce + offset as i64)?;
+            tx_types.push(tx_type);
+            tx_infos.push(tx_info);
+        }
+
+        self.send_tx_batch(tx_types, tx_infos).await
+    }
+
+    fn sign_batch_tx(&self, tx: &BatchTx, nonce: i64) -> Result<(i32, String)> {
+        match tx {
+            BatchTx::CreateOrder {
+                market_index,
+                client_order_index,
+                base_amount,
+                price,
+                is_ask,
+                order_type,
+                time_in_force,
+                reduce_only,
+                trigger_price,
+                order_expiry,
+            } => self
+                .signer
+                .sign_create_order(
+                    *market_index,
+                    *client_order_index,
+                    *base_amount,
+                    *price,
+                    *is_ask,
+                    *order_type,
+                    *time_in_force,
+                    *reduce_only,
+                    *trigger_price,
+                    *order_expiry,
+                    nonce,
+                )
+                .map(|info| (TX_TYPE_CREATE_ORDER, info)),
+            BatchTx::CancelOrder {
+                market_index,
+                client_cancel_index,
+                order_id,
+            } => self
+                .signer
+                .sign_cancel_order(*market_index, *client_cancel_index, order_id, nonce)
+                .map(|info| (TX_TYPE_CANCEL_ORDER, info)),
+            BatchTx::ModifyOrder {
+                market_index,
+                order_index,
+                base_amount,
+                price,
+                trigger_price,
+            } => self
+                .signer
+                .sign_modify_order(
+                    *market_index,
+                    *order_index,
+                    *base_amount,
+                    *price,
+                    *trigger_price,
+                    nonce,
+                )
+                .map(|info| (TX_TYPE_MODIFY_ORDER, info)),
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn create_order(
         &self,
@@ -79,7 +251,7 @@ impl LighterTransactionApi {
         trigger_price: i32,
         order_expiry: i64,
         nonce: i64,
-    ) -> Result<(serde_json::Value, TxResponse)> {
+    ) -> Result<(SignedCreateOrder, TxResponse)> {
         let tx_info = self.signer.sign_create_order(
             market_index,
             client_order_index,
@@ -94,12 +266,26 @@ impl LighterTransactionApi {
             nonce,
         )?;
 
-        let order_data: serde_json::Value = serde_json::from_str(&tx_info)?;
+        let order_data: SignedCreateOrder = serde_json::from_str(&tx_info)?;
         let response = self.send_tx(TX_TYPE_CREATE_ORDER, tx_info).await?;
 
         Ok((order_data, response))
     }
 
+    /// Submit linked orders as one grouped-order transaction.
+    pub async fn create_grouped_orders(
+        &self,
+        grouping: crate::models::order::OrderGrouping,
+        legs: &[GroupedOrderLeg],
+        nonce: i64,
+    ) -> Result<TxResponse> {
+        let tx_info = self
+            .signer
+            .sign_create_grouped_orders(grouping, legs, nonce)?;
+
+        self.send_tx(TX_TYPE_CREATE_GROUPED_ORDERS, tx_info).await
+    }
+
     pub async fn cancel_order(
         &self,
         market_index: i32,
@@ -117,26 +303,137 @@ impl LighterTransactionApi {
         self.send_tx(TX_TYPE_CANCEL_ORDER, tx_info).await
     }
 
-    pub async fn cancel_all_orders(
+    pub async fn modify_order(
         &self,
         market_index: i32,
-        client_cancel_index: i64,
+        order_index: i64,
+        base_amount: i64,
+        price: i64,
+        trigger_price: i64,
         nonce: i64,
     ) -> Result<TxResponse> {
-        let tx_info =
-            self.signer
-                .sign_cancel_all_orders(market_index, client_cancel_index, nonce)?;
+        let tx_info = self.signer.sign_modify_order(
+            market_index,
+            order_index,
+            base_amount,
+            price,
+            trigger_price,
+            nonce,
+        )?;
+
+        self.send_tx(TX_TYPE_MODIFY_ORDER, tx_info).await
+    }
+
+    pub async fn cancel_all_orders(
+        &self,
+        mode: crate::models::order::CancelAllMode,
+        nonce: i64,
+    ) -> Result<TxResponse> {
+        let tx_info = self.signer.sign_cancel_all_orders(mode, nonce)?;
 
         self.send_tx(TX_TYPE_CANCEL_ALL_ORDERS, tx_info).await
     }
 
-    pub async fn transfer(&self, receiver: &str, amount: i64, nonce: i64) -> Result<TxResponse> {
-        let tx_info = self.signer.sign_transfer(receiver, amount, nonce)?;
+    /// Transfer USDC (scaled by 1e6) to the account at `to_account_index`.
+    pub async fn transfer(
+        &self,
+        to_account_index: i64,
+        usdc_amount: i64,
+        nonce: i64,
+    ) -> Result<TxResponse> {
+        let tx_info = self
+            .signer
+            .sign_transfer(to_account_index, usdc_amount, nonce)?;
         self.send_tx(TX_TYPE_TRANSFER, tx_info).await
     }
 
+    pub async fn create_sub_account(&self, nonce: i64) -> Result<TxResponse> {
+        let tx_info = self.signer.sign_create_sub_account(nonce)?;
+        self.send_tx(TX_TYPE_CREATE_SUB_ACCOUNT, tx_info).await
+    }
+
+    /// Withdraw USDC (scaled by 1e6) to the L1 address `receiver`. See
+    /// [`crate::AccountManager::withdraw`] for a validated, tracked version.
     pub async fn withdraw(&self, receiver: &str, amount: i64, nonce: i64) -> Result<TxResponse> {
         let tx_info = self.signer.sign_withdraw(receiver, amount, nonce)?;
         self.send_tx(TX_TYPE_WITHDRAW, tx_info).await
     }
+
+    /// Set a market's leverage, as an initial margin fraction in basis
+    /// points, together with its margin mode.
+    pub async fn update_leverage(
+        &self,
+        market_index: i32,
+        initial_margin_fraction: i32,
+        margin_mode: crate::models::account::MarginType,
+        nonce: i64,
+    ) -> Result<TxResponse> {
+        let tx_info = self.signer.sign_update_leverage(
+            market_index,
+            initial_margin_fraction,
+            margin_mode.lighter_code(),
+            nonce,
+        )?;
+        self.send_tx(TX_TYPE_UPDATE_LEVERAGE, tx_info).await
+    }
+
+    /// Add USDC (scaled by 1e6) to, or remove it from, an isolated position.
+    pub async fn update_margin(
+        &self,
+        market_index: i32,
+        usdc_amount: i64,
+        add: bool,
+        nonce: i64,
+    ) -> Result<TxResponse> {
+        let tx_info =
+            self.signer
+                .sign_update_margin(market_index, usdc_amount, i32::from(add), nonce)?;
+        self.send_tx(TX_TYPE_UPDATE_MARGIN, tx_info).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::OrderType;
+    use crate::models::order::TimeInForce;
+
+    #[test]
+    fn parses_tx_response() {
+        let raw = r#"{"code":200,"message":"{\"ratelimit\": \"didn't use volume quota\"}","tx_hash":"0b1c5f","predicted_execution_time_ms":1751465474850}"#;
+        let response: TxResponse = serde_json::from_str(raw).expect("valid json");
+        assert_eq!(response.tx_hash.as_deref(), Some("0b1c5f"));
+        assert_eq!(response.predicted_execution_time_ms, Some(1751465474850));
+    }
+
+    #[test]
+    fn parses_signed_create_order() {
+        let raw = r#"{
+            "AccountIndex": 70407,
+            "ApiKeyIndex": 2,
+            "MarketIndex": 0,
+            "ClientOrderIndex": 17514654748,
+            "BaseAmount": 1000,
+            "Price": 312450,
+            "IsAsk": 1,
+            "Type": 0,
+            "TimeInForce": 1,
+            "ReduceOnly": 0,
+            "TriggerPrice": 0,
+            "OrderExpiry": 1753884674850,
+            "ExpiredAt": 1751466074850,
+            "Nonce": 42,
+            "Sig": "c2lnbmF0dXJl"
+        }"#;
+        let order: SignedCreateOrder = serde_json::from_str(raw).expect("valid json");
+        assert_eq!(order.client_order_index, 17514654748);
+        assert!(order.is_ask);
+        assert!(!order.reduce_only);
+        assert_eq!(order.order_type(), Some(OrderType::Limit));
+        assert_eq!(order.time_in_force(), Some(TimeInForce::Gtc));
+        assert_eq!(order.signature.as_deref(), Some("c2lnbmF0dXJl"));
+
+        let value = serde_json::to_value(&order).expect("serialize");
+        assert_eq!(value["IsAsk"], 1);
+    }
 }
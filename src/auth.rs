@@ -0,0 +1,168 @@
+use crate::error::{LighterError, Result};
+use crate::signers::FFISigner;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(10 * 60);
+const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+type TokenMinter = dyn Fn(i64) -> Result<String> + Send + Sync;
+
+/// Caches Lighter auth tokens and mints a new one shortly before the cached
+/// token expires, so private read endpoints do not pay for a signature on
+/// every request.
+pub struct AuthTokenProvider {
+    minter: Arc<TokenMinter>,
+    ttl: Duration,
+    refresh_margin: Duration,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    token: String,
+    expires_at: i64,
+}
+
+impl AuthTokenProvider {
+    /// Create a provider that mints tokens through the FFI signer.
+    pub fn new(signer: Arc<FFISigner>) -> Self {
+        Self::with_minter(move |deadline| signer.create_auth_token_with_expiry(Some(deadline)))
+    }
+
+    /// Create a provider backed by an arbitrary minting function. The function
+    /// receives the token deadline as unix seconds.
+    pub fn with_minter<F>(minter: F) -> Self
+    where
+        F: Fn(i64) -> Result<String> + Send + Sync + 'static,
+    {
+        Self {
+            minter: Arc::new(minter),
+            ttl: DEFAULT_TOKEN_TTL,
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Lifetime requested for each freshly minted token.
+    pub fn with_ttl(mut self, ttl: Duration) -> Result<Self> {
+        if ttl <= self.refresh_margin {
+            return Err(LighterError::Config(format!(
+                "auth token ttl {:?} must exceed the refresh margin {:?}",
+                ttl, self.refresh_margin
+            )));
+        }
+        self.ttl = ttl;
+        Ok(self)
+    }
+
+    /// How long before expiry the cached token is replaced.
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Result<Self> {
+        if refresh_margin >= self.ttl {
+            return Err(LighterError::Config(format!(
+                "auth token refresh margin {:?} must be shorter than the ttl {:?}",
+                refresh_margin, self.ttl
+            )));
+        }
+        self.refresh_margin = refresh_margin;
+        Ok(self)
+    }
+
+    /// Return the cached token, minting a new one if it is missing or about
+    /// to expire.
+    pub fn token(&self) -> Result<String> {
+        self.token_at(unix_now()?)
+    }
+
+    /// Drop the cached token so the next call mints a fresh one.
+    pub fn invalidate(&self) {
+        if let Ok(mut guard) = self.cached.lock() {
+            *guard = None;
+        }
+    }
+
+    fn token_at(&self, now: i64) -> Result<String> {
+        let mut guard = self
+            .cached
+            .lock()
+            .map_err(|_| LighterError::Auth("auth token cache poisoned".to_string()))?;
+
+        if let Some(cached) = guard.as_ref() {
+            if now + (self.refresh_margin.as_secs() as i64) < cached.expires_at {
+                return Ok(cached.token.clone());
+            }
+        }
+
+        let expires_at = now + self.ttl.as_secs() as i64;
+        let token = (self.minter)(expires_at)?;
+        *guard = Some(CachedToken {
+            token: token.clone(),
+            expires_at,
+        });
+        Ok(token)
+    }
+}
+
+impl fmt::Debug for AuthTokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthTokenProvider")
+            .field("ttl", &self.ttl)
+            .field("refresh_margin", &self.refresh_margin)
+            .finish_non_exhaustive()
+    }
+}
+
+fn unix_now() -> Result<i64> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| LighterError::Auth(err.to_string()))?;
+    Ok(now.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counting_provider() -> (AuthTokenProvider, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let provider = AuthTokenProvider::with_minter(move |deadline| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("token-{n}-{deadline}"))
+        });
+        (provider, calls)
+    }
+
+    #[test]
+    fn reuses_token_until_refresh_window() {
+        let (provider, calls) = counting_provider();
+
+        let first = provider.token_at(1_000).unwrap();
+        let second = provider.token_at(1_000 + 500).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 600s ttl with a 60s margin: refresh kicks in at 540s.
+        let third = provider.token_at(1_000 + 540).unwrap();
+        assert_ne!(first, third);
+        assert_eq!(third, "token-1-2140");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn invalidate_forces_new_token() {
+        let (provider, calls) = counting_provider();
+        provider.token_at(1_000).unwrap();
+        provider.invalidate();
+        provider.token_at(1_001).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn rejects_ttl_shorter_than_margin() {
+        let (provider, _) = counting_provider();
+        assert!(provider.with_ttl(Duration::from_secs(30)).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::error::Result;
+use crate::metadata::MarketInfo;
+use crate::paper::{PaperConfig, PaperExchange};
+use crate::strategy::{Strategy, StrategyRuntime};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+mod replay;
+mod report;
+
+pub use replay::{candle_events, load_recorded_stream, CandleReplay, RecordedMessage, ReplayEvent};
+pub use report::{BacktestReport, BacktestSummary, EquityPoint};
+
+/// Client order indices start here so runs are reproducible.
+const FIRST_CLIENT_INDEX: i64 = 1;
+
+/// Replays recorded market data through a [`Strategy`] against a
+/// [`PaperExchange`], entirely offline and on a simulated clock.
+///
+/// Events must be in time order. Timers fire at their scheduled simulated
+/// time, before the first event at or after it.
+pub struct Backtester<S> {
+    runtime: StrategyRuntime<S, PaperExchange>,
+    exchange: Arc<PaperExchange>,
+    initial_equity: Decimal,
+    sample_interval: Duration,
+}
+
+impl<S: Strategy> Backtester<S> {
+    pub fn new(strategy: S, config: PaperConfig, markets: Vec<MarketInfo>) -> Self {
+        let initial_equity = config.initial_collateral;
+        let exchange = Arc::new(PaperExchange::new(config));
+        let mut runtime = StrategyRuntime::new(strategy, exchange.clone())
+            .with_first_client_index(FIRST_CLIENT_INDEX);
+        for market in markets {
+            runtime = runtime.with_market(market.symbol.clone());
+            exchange.add_market(market);
+        }
+        Self {
+            runtime,
+            exchange,
+            initial_equity,
+            sample_interval: Duration::minutes(1),
+        }
+    }
+
+    /// Least spacing of equity curve samples, which are taken as events
+    /// arrive. Sharpe is computed on the curve resampled to this interval.
+    pub fn with_sample_interval(mut self, sample_interval: Duration) -> Self {
+        self.sample_interval = sample_interval;
+        self
+    }
+
+    pub fn exchange(&self) -> &PaperExchange {
+        &self.exchange
+    }
+
+    pub fn strategy(&self) -> &S {
+        self.runtime.strategy()
+    }
+
+    /// Run the strategy over `events` until they run out or the strategy
+    /// stops, then call its `on_stop` hook and build the report.
+    pub async fn run(
+        &mut self,
+        events: impl IntoIterator<Item = ReplayEvent>,
+    ) -> Result<BacktestReport> {
+        let mut events = events.into_iter().peekable();
+        let Some(start) = events.peek().and_then(ReplayEvent::at) else {
+            return Ok(BacktestReport::new(
+                self.initial_equity,
+                Vec::new(),
+                Vec::new(),
+                self.sample_interval,
+            ));
+        };
+
+        self.runtime.set_time(start);
+        self.runtime.start().await?;
+        let mut curve = Vec::new();
+        let mut now = start;
+        self.sample(&mut curve, now);
+
+        for event in events {
+            now = event.at().unwrap_or(now).max(now);
+            while let Some(due) = self.runtime.next_timer().filter(|due| *due <= now) {
+                self.runtime.set_time(due);
+                self.runtime.fire_timers(due).await?;
+                if self.runtime.is_stopped() {
+                    break;
+                }
+            }
+            if self.runtime.is_stopped() {
+                break;
+            }
+
+            self.runtime.set_time(now);
+            match event {
+                ReplayEvent::Market(event) => self.runtime.handle(event).await?,
+                ReplayEvent::Ws { message, .. } => self.runtime.handle_ws_message(&message).await?,
+            }
+            if curve
+                .last()
+                .is_none_or(|last: &EquityPoint| now - last.timestamp >= self.sample_interval)
+            {
+                self.sample(&mut curve, now);
+            }
+            if self.runtime.is_stopped() {
+                break;
+            }
+        }
+
+        self.runtime.stop().await?;
+        if curve.last().is_some_and(|last| last.timestamp < now) {
+            self.sample(&mut curve, now);
+        }
+        Ok(BacktestReport::new(
+            self.initial_equity,
+            curve,
+            self.exchange.fills(),
+            self.sample_interval,
+        ))
+    }
+
+    fn sample(&self, curve: &mut Vec<EquityPoint>, timestamp: DateTime<Utc>) {
+        curve.push(EquityPoint {
+            timestamp,
+            equity: self.exchange.equity(),
+            collateral: self.exchange.collateral(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::candlestick::Candlestick;
+    use crate::strategy::{BookUpdate, StrategyContext, StrategyOrder};
+
+    fn eth() -> MarketInfo {
+        MarketInfo {
+            market_id: 0,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(2),
+            supported_price_decimals: Some(2),
+            ..Default::default()
+        }
+    }
+
+    fn candle(minute: i64, open: &str, high: &str, low: &str, close: &str) -> Candlestick {
+        let open_time = DateTime::from_timestamp(1_700_000_000 + minute * 60, 0).unwrap();
+        Candlestick {
+            symbol: "ETH".to_string(),
+            interval: "1m".to_string(),
+            open_time,
+            close_time: open_time + Duration::seconds(59),
+            open: open.to_string(),
+            high: high.to_string(),
+            low: low.to_string(),
+            close: close.to_string(),
+            volume: "10".to_string(),
+            quote_volume: "0".to_string(),
+            trade_count: 0,
+        }
+    }
+
+    /// Buys on the first book and sells once the mid has risen 2%.
+    #[derive(Default)]
+    struct Momentum {
+        entry: Option<Decimal>,
+        exited: bool,
+        timer_fires: Vec<DateTime<Utc>>,
+    }
+
+    impl Strategy for Momentum {
+        fn on_start(&mut self, ctx: &mut StrategyContext) {
+            ctx.set_timer("tick", Duration::seconds(90));
+        }
+
+        fn on_book(&mut self, ctx: &mut StrategyContext, book: &BookUpdate) {
+            let mid = book.mid_price().unwrap();
+            match self.entry {
+                None => {
+                    self.entry = Some(mid);
+                    ctx.submit(StrategyOrder::market("ETH", true, Decimal::ONE, 100))
+                        .unwrap();
+                }
+                Some(entry) if !self.exited && mid >= entry * Decimal::new(102, 2) => {
+                    self.exited = true;
+                    ctx.submit(StrategyOrder::market("ETH", false, Decimal::ONE, 100))
+                        .unwrap();
+                }
+                _ => {}
+            }
+        }
+
+        fn on_timer(&mut self, ctx: &mut StrategyContext, _timer: &str) {
+            self.timer_fires.push(ctx.now());
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_candles_and_reports() {
+        let candles = [
+            candle(0, "100", "101", "99", "100"),
+            candle(1, "100", "103", "100", "103"),
+            candle(2, "103", "104", "95", "96"),
+        ];
+        let events = candle_events(&candles, &eth(), CandleReplay::default()).unwrap();
+        let mut backtest = Backtester::new(
+            Momentum::default(),
+            PaperConfig::new(Decimal::from(1_000)),
+            vec![eth()],
+        );
+        let report = backtest.run(events).await.unwrap();
+
+        assert!(backtest.strategy().exited);
+        assert_eq!(report.summary.trades, 2);
+        // Bought at 100.05 and sold at 102.95 through the synthetic spread.
+        assert_eq!(report.summary.pnl, Decimal::new(290, 2));
+        assert!(report.summary.turnover > Decimal::from(200));
+        assert!(report.summary.max_drawdown >= Decimal::ZERO);
+        assert_eq!(backtest.strategy().timer_fires.len(), 1);
+        assert_eq!(
+            backtest.strategy().timer_fires[0],
+            candles[0].open_time + Duration::seconds(90)
+        );
+
+        let dir = std::env::temp_dir().join(format!("lighter-backtest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        report.write_json(dir.join("report.json")).unwrap();
+        report.write_trades_csv(dir.join("trades.csv")).unwrap();
+        let trades = std::fs::read_to_string(dir.join("trades.csv")).unwrap();
+        assert_eq!(trades.lines().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_ws_messages() {
+        let dir = std::env::temp_dir().join(format!("lighter-recording-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stream.jsonl");
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut file = std::fs::File::create(&path).unwrap();
+        for (offset, bid) in [(0, "99.00"), (60, "102.50")] {
+            RecordedMessage::new(
+                start + Duration::seconds(offset),
+                serde_json::json!({
+                    "type": "update/order_book",
+                    "channel": "order_book:0",
+                    "order_book": {
+                        "bids": [{ "price": bid, "size": "5" }],
+                        "asks": [{ "price": "103.00", "size": "5" }]
+                    }
+                }),
+            )
+            .write_to(&mut file)
+            .unwrap();
+        }
+        drop(file);
+
+        let events = load_recorded_stream(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let mut backtest = Backtester::new(
+            Momentum::default(),
+            PaperConfig::new(Decimal::from(1_000)),
+            vec![eth()],
+        );
+        let report = backtest.run(events).await.unwrap();
+        assert_eq!(report.summary.trades, 1);
+        assert_eq!(report.trades[0].price, Decimal::from(103));
+        assert_eq!(report.summary.start, Some(start));
+        assert_eq!(report.equity_curve.len(), 2);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::api::candlestick::Candlestick;
+use crate::error::{LighterError, Result};
+use crate::metadata::MarketInfo;
+use crate::models::common::{OrderBook, PriceLevel, Side};
+use crate::strategy::{BookUpdate, MarketTrade, StrategyEvent};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+const BPS_DENOMINATOR: i64 = 10_000;
+
+/// One step of recorded market data.
+#[derive(Debug, Clone)]
+pub enum ReplayEvent {
+    /// A book or trade event; its own timestamp drives the clock.
+    Market(StrategyEvent),
+    /// A raw WS message as received at `at`, parsed like live data.
+    Ws { at: DateTime<Utc>, message: Value },
+}
+
+impl ReplayEvent {
+    pub fn at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Market(StrategyEvent::Book(update)) => Some(update.book.timestamp),
+            Self::Market(StrategyEvent::Trade(trade)) => Some(trade.timestamp),
+            Self::Market(_) => None,
+            Self::Ws { at, .. } => Some(*at),
+        }
+    }
+}
+
+/// Line of a recorded WS stream: a message and when it was received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Receive time in unix milliseconds.
+    pub timestamp: i64,
+    pub message: Value,
+}
+
+impl RecordedMessage {
+    pub fn new(at: DateTime<Utc>, message: Value) -> Self {
+        Self {
+            timestamp: at.timestamp_millis(),
+            message,
+        }
+    }
+
+    /// Append this message to a JSON lines recording.
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        serde_json::to_writer(&mut *writer, self)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Read a JSON lines recording of [`RecordedMessage`]s.
+pub fn load_recorded_stream(path: impl AsRef<Path>) -> Result<Vec<ReplayEvent>> {
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: RecordedMessage = serde_json::from_str(&line)?;
+        let at = DateTime::from_timestamp_millis(record.timestamp).ok_or_else(|| {
+            LighterError::Unknown(format!("invalid recording timestamp {}", record.timestamp))
+        })?;
+        events.push(ReplayEvent::Ws {
+            at,
+            message: record.message,
+        });
+    }
+    Ok(events)
+}
+
+/// How candles are turned into synthetic books and trades.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandleReplay {
+    /// Distance of the synthetic bid and ask from the candle price.
+    pub half_spread_bps: u32,
+    /// Size shown at each synthetic touch.
+    pub depth: Decimal,
+}
+
+impl Default for CandleReplay {
+    fn default() -> Self {
+        Self {
+            half_spread_bps: 5,
+            depth: Decimal::from(1_000_000),
+        }
+    }
+}
+
+/// Expand candles, as returned by
+/// [`crate::api::candlestick::CandlestickApi::get_candlesticks`], into
+/// events: a book at the open, trades at the extremes in the likely order,
+/// and a book at the close.
+pub fn candle_events(
+    candles: &[Candlestick],
+    market: &MarketInfo,
+    replay: CandleReplay,
+) -> Result<Vec<ReplayEvent>> {
+    let mut events = Vec::with_capacity(candles.len() * 4);
+    for candle in candles {
+        let parse = |field: &str, value: &str| {
+            Decimal::from_str(value).map_err(|_| {
+                LighterError::Unknown(format!(
+                    "unparseable candle {field} {value} at {}",
+                    candle.open_time
+                ))
+            })
+        };
+        let open = parse("open", &candle.open)?;
+        let high = parse("high", &candle.high)?;
+        let low = parse("low", &candle.low)?;
+        let close = parse("close", &candle.close)?;
+        let volume = parse("volume", &candle.volume)?;
+
+        let middle = candle.open_time + (candle.close_time - candle.open_time) / 2;
+        // A rising candle more likely visited its low first.
+        let extremes = if close >= open {
+            [(low, Side::Sell), (high, Side::Buy)]
+        } else {
+            [(high, Side::Buy), (low, Side::Sell)]
+        };
+
+        events.push(synthetic_book(market, open, candle.open_time, replay));
+        for (index, (price, taker_side)) in extremes.into_iter().enumerate() {
+            events.push(ReplayEvent::Market(StrategyEvent::Trade(MarketTrade {
+                symbol: market.symbol.clone(),
+                market_id: market.market_id,
+                trade_id: 0,
+                price,
+                size: volume / Decimal::TWO,
+                taker_side,
+                timestamp: if index == 0 {
+                    middle
+                } else {
+                    middle + (candle.close_time - middle) / 2
+                },
+            })));
+        }
+        events.push(synthetic_book(market, close, candle.close_time, replay));
+    }
+    Ok(events)
+}
+
+fn synthetic_book(
+    market: &MarketInfo,
+    price: Decimal,
+    at: DateTime<Utc>,
+    replay: CandleReplay,
+) -> ReplayEvent {
+    let offset = price * Decimal::from(replay.half_spread_bps) / Decimal::from(BPS_DENOMINATOR);
+    let decimals = market.supported_price_decimals.unwrap_or(0);
+    let level = |price: Decimal| PriceLevel {
+        price: price.round_dp(decimals).to_string(),
+        quantity: replay.depth.to_string(),
+    };
+    ReplayEvent::Market(StrategyEvent::Book(BookUpdate {
+        symbol: market.symbol.clone(),
+        market_id: market.market_id,
+        book: OrderBook {
+            bids: vec![level(price - offset)],
+            asks: vec![level(price + offset)],
+            timestamp: at,
+        },
+    }))
+}
@@ -1,22 +1,35 @@
+use crate::auth::AuthTokenProvider;
 use crate::config::Config;
 use crate::error::{LighterError, Result};
 use reqwest::{
-    Client,
-    Method,
-    Response,
     header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+    Client, Method, Response,
 };
 use serde::Serialize;
 use serde::{de::DeserializeOwned, Deserialize};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{debug, error, warn};
 use url::Url;
 
+/// Endpoints that only answer with an auth token for the queried account.
+const AUTHENTICATED_ENDPOINTS: &[&str] = &[
+    "accountActiveOrders",
+    "accountInactiveOrders",
+    "pnl",
+    "liquidations",
+    "withdraw/history",
+];
+
+/// Public endpoints that need an auth token only when filtered to an account.
+const ACCOUNT_SCOPED_ENDPOINTS: &[&str] = &["trades"];
+
 #[derive(Debug, Clone)]
 pub struct ApiClient {
     client: Client,
     config: Config,
+    auth: Option<Arc<AuthTokenProvider>>,
 }
 
 impl ApiClient {
@@ -32,7 +45,22 @@ impl ApiClient {
             .build()
             .map_err(|e| LighterError::Http(Box::new(e)))?;
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            auth: None,
+        })
+    }
+
+    /// Attach an auth token provider. Requests to authenticated endpoints will
+    /// carry a cached token in the `Authorization` header.
+    pub fn with_auth_token_provider(mut self, provider: Arc<AuthTokenProvider>) -> Self {
+        self.auth = Some(provider);
+        self
+    }
+
+    pub fn auth_token_provider(&self) -> Option<&Arc<AuthTokenProvider>> {
+        self.auth.as_ref()
     }
 
     pub async fn get<T>(&self, endpoint: &str) -> Result<T>
@@ -46,7 +74,8 @@ impl ApiClient {
     where
         T: DeserializeOwned,
     {
-        self.request(Method::GET, endpoint, None::<()>, Some(headers)).await
+        self.request(Method::GET, endpoint, None::<()>, Some(headers))
+            .await
     }
 
     pub async fn post<T, B>(&self, endpoint: &str, body: Option<B>) -> Result<T>
@@ -69,7 +98,8 @@ impl ApiClient {
     where
         T: DeserializeOwned,
     {
-        self.request(Method::DELETE, endpoint, None::<()>, None).await
+        self.request(Method::DELETE, endpoint, None::<()>, None)
+            .await
     }
 
     pub async fn fetch_next_nonce(&self, account_index: i32, api_key_index: i32) -> Result<u64> {
@@ -99,17 +129,24 @@ impl ApiClient {
         let mut retries = 0;
         let max_retries = self.config.max_retries;
 
+        let custom_auth = headers
+            .map(|pairs| {
+                pairs
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case(AUTHORIZATION.as_str()))
+            })
+            .unwrap_or(false);
+        let auth_token = match &self.auth {
+            Some(provider) if !custom_auth && requires_auth(endpoint) => Some(provider.token()?),
+            _ => None,
+        };
+
         loop {
             let mut request_builder = self.client.request(method.clone(), url.clone());
 
-            if let Some(api_key) = &self.config.api_key {
-                let custom_auth = headers
-                    .map(|pairs| {
-                        pairs
-                            .iter()
-                            .any(|(name, _)| name.eq_ignore_ascii_case(AUTHORIZATION.as_str()))
-                    })
-                    .unwrap_or(false);
+            if let Some(token) = &auth_token {
+                request_builder = request_builder.header(AUTHORIZATION, token.as_str());
+            } else if let Some(api_key) = &self.config.api_key {
                 if !custom_auth {
                     request_builder =
                         request_builder.header(AUTHORIZATION, format!("Bearer {}", api_key));
@@ -156,7 +193,13 @@ impl ApiClient {
                         continue;
                     }
 
-                    return self.handle_response(response).await;
+                    let result = self.handle_response(response).await;
+                    if let (Err(LighterError::Auth(_)), Some(provider)) = (&result, &self.auth) {
+                        if auth_token.is_some() {
+                            provider.invalidate();
+                        }
+                    }
+                    return result;
                 }
                 Err(e) if retries < max_retries => {
                     retries += 1;
@@ -236,6 +279,19 @@ impl ApiClient {
     }
 }
 
+fn requires_auth(endpoint: &str) -> bool {
+    let endpoint = endpoint.trim_start_matches('/');
+    let endpoint = endpoint.split('#').next().unwrap_or_default();
+    let (path, query) = endpoint.split_once('?').unwrap_or((endpoint, ""));
+    if AUTHENTICATED_ENDPOINTS.contains(&path) {
+        return true;
+    }
+    ACCOUNT_SCOPED_ENDPOINTS.contains(&path)
+        && query
+            .split('&')
+            .any(|pair| pair.split('=').next() == Some("account_index"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,4 +307,18 @@ mod tests {
 
         assert_eq!(url.as_str(), "https://example.com/api/v3/account");
     }
+
+    #[test]
+    fn detects_authenticated_endpoints() {
+        assert!(requires_auth(
+            "/accountActiveOrders?account_index=1&market_id=0"
+        ));
+        assert!(requires_auth("pnl?by=index&value=1"));
+        assert!(requires_auth("/withdraw/history?account_index=1"));
+        assert!(requires_auth("/trades?account_index=1&sort_by=timestamp"));
+        assert!(!requires_auth("/trades?symbol=BTC"));
+        assert!(!requires_auth("/trades"));
+        assert!(!requires_auth("/orderBooks"));
+        assert!(!requires_auth("/account?by=index&value=1"));
+    }
 }
@@ -38,6 +38,12 @@ pub enum LighterError {
     #[error("Order validation failed: {0}")]
     OrderValidation(String),
 
+    #[error("Risk limit breached: {0}")]
+    RiskLimit(crate::trading::RiskViolation),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
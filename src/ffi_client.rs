@@ -1,17 +1,22 @@
+use crate::auth::AuthTokenProvider;
 use crate::client::ApiClient;
 use crate::config::Config;
 use crate::error::{LighterError, Result};
+use crate::models::account::MarginType;
+use crate::models::common::{OrderBook, PriceLevel};
 use crate::signers::FFISigner;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use std::str::FromStr;
+use std::sync::Arc;
 
 /// Lightweight client that mirrors the Python SDK behaviour by driving the
 /// FFI signer directly. This avoids the Ethereum-style key requirements of the
 /// default `LighterClient` while still exposing read-only account helpers.
 pub struct LighterFfiClient {
     api_client: ApiClient,
-    #[allow(dead_code)]
-    signer: FFISigner,
+    auth: Arc<AuthTokenProvider>,
     account_index: i32,
 }
 
@@ -37,16 +42,27 @@ impl LighterFfiClient {
             url
         };
 
-        let api_client = ApiClient::new(config.clone())?;
-        let signer = FFISigner::new(&base_url_str, private_key, api_key_index, account_index)?;
+        let signer = Arc::new(FFISigner::new(
+            &base_url_str,
+            private_key,
+            api_key_index,
+            account_index,
+        )?);
+        let auth = Arc::new(AuthTokenProvider::new(signer));
+        let api_client = ApiClient::new(config.clone())?.with_auth_token_provider(auth.clone());
 
         Ok(Self {
             api_client,
-            signer,
+            auth,
             account_index,
         })
     }
 
+    /// Cached auth token provider used for private endpoints.
+    pub fn auth_token_provider(&self) -> &Arc<AuthTokenProvider> {
+        &self.auth
+    }
+
     /// Fetch the full account payload.
     pub async fn get_account(&self) -> Result<AccountSnapshot> {
         self.fetch_account().await
@@ -64,22 +80,103 @@ impl LighterFfiClient {
         Ok(account.positions)
     }
 
+    /// Fetch the resting orders at the top of a market's book.
+    pub async fn get_order_book(&self, market_id: i32, limit: u32) -> Result<OrderBookOrders> {
+        fetch_order_book_orders(&self.api_client, market_id, limit).await
+    }
+
+    /// Fetch resting orders for a market. Requires an auth token, which is
+    /// attached automatically.
+    pub async fn get_active_orders(&self, market_id: i32) -> Result<Vec<AccountOrder>> {
+        fetch_active_orders(&self.api_client, self.account_index, market_id).await
+    }
+
+    /// Fetch recently closed orders, optionally limited to a single market.
+    pub async fn get_inactive_orders(
+        &self,
+        market_id: Option<i32>,
+        limit: u32,
+    ) -> Result<Vec<AccountOrder>> {
+        fetch_inactive_orders(&self.api_client, self.account_index, market_id, limit).await
+    }
+
     async fn fetch_account(&self) -> Result<AccountSnapshot> {
-        let endpoint = format!("/account?by=index&value={}", self.account_index);
-        let value: serde_json::Value = self.api_client.get(&endpoint).await?;
+        fetch_account_snapshot(&self.api_client, self.account_index).await
+    }
+}
 
-        let response: RawAccountResponse = serde_json::from_value(value)?;
-        let account = response
-            .accounts
-            .into_iter()
-            .find(|entry| entry.account_index == self.account_index as i64)
-            .ok_or_else(|| LighterError::Api {
-                status: 404,
-                message: "Account not found".to_string(),
-            })?;
+pub(crate) async fn fetch_account_snapshot(
+    api_client: &ApiClient,
+    account_index: impl Into<i64>,
+) -> Result<AccountSnapshot> {
+    let account_index: i64 = account_index.into();
+    let endpoint = format!("/account?by=index&value={account_index}");
+    let value: serde_json::Value = api_client.get(&endpoint).await?;
+
+    let response: RawAccountResponse = serde_json::from_value(value)?;
+    let account = response
+        .accounts
+        .into_iter()
+        .find(|entry| entry.account_index == account_index)
+        .ok_or_else(|| LighterError::Api {
+            status: 404,
+            message: "Account not found".to_string(),
+        })?;
 
-        Ok(account.into())
+    Ok(account.into())
+}
+
+pub(crate) async fn fetch_order_book_orders(
+    api_client: &ApiClient,
+    market_id: i32,
+    limit: u32,
+) -> Result<OrderBookOrders> {
+    let endpoint = format!("/orderBookOrders?market_id={market_id}&limit={limit}");
+    api_client.get(&endpoint).await
+}
+
+pub(crate) async fn fetch_active_orders(
+    api_client: &ApiClient,
+    account_index: i32,
+    market_id: i32,
+) -> Result<Vec<AccountOrder>> {
+    let endpoint =
+        format!("/accountActiveOrders?account_index={account_index}&market_id={market_id}");
+    let response: RawOrdersResponse = api_client.get(&endpoint).await?;
+    Ok(response.orders)
+}
+
+pub(crate) async fn fetch_inactive_orders(
+    api_client: &ApiClient,
+    account_index: i32,
+    market_id: Option<i32>,
+    limit: u32,
+) -> Result<Vec<AccountOrder>> {
+    fetch_inactive_orders_page(api_client, account_index, market_id, limit, None)
+        .await
+        .map(|(orders, _)| orders)
+}
+
+/// One page of closed orders, newest first, with the cursor of the next
+/// page if there is one.
+pub(crate) async fn fetch_inactive_orders_page(
+    api_client: &ApiClient,
+    account_index: i32,
+    market_id: Option<i32>,
+    limit: u32,
+    cursor: Option<&str>,
+) -> Result<(Vec<AccountOrder>, Option<String>)> {
+    let mut endpoint =
+        format!("/accountInactiveOrders?account_index={account_index}&limit={limit}");
+    if let Some(market_id) = market_id {
+        endpoint.push_str(&format!("&market_id={market_id}"));
+    }
+    if let Some(cursor) = cursor {
+        endpoint.push_str(&format!("&cursor={cursor}"));
     }
+    let response: RawOrdersResponse = api_client.get(&endpoint).await?;
+    let next_cursor = response.next_cursor.filter(|cursor| !cursor.is_empty());
+    Ok((response.orders, next_cursor))
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -90,6 +187,117 @@ struct RawAccountResponse {
     _code: i32,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct RawOrdersResponse {
+    #[serde(default)]
+    orders: Vec<AccountOrder>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+/// Order as reported by the account order endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountOrder {
+    pub order_index: i64,
+    #[serde(default)]
+    pub client_order_index: i64,
+    #[serde(default)]
+    pub market_index: i32,
+    #[serde(default)]
+    pub initial_base_amount: String,
+    #[serde(default)]
+    pub remaining_base_amount: String,
+    #[serde(default)]
+    pub filled_base_amount: String,
+    #[serde(default)]
+    pub filled_quote_amount: String,
+    #[serde(default)]
+    pub price: String,
+    #[serde(default)]
+    pub is_ask: bool,
+    #[serde(default, rename = "type")]
+    pub order_type: String,
+    #[serde(default)]
+    pub time_in_force: String,
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub trigger_price: String,
+    #[serde(default)]
+    pub order_expiry: i64,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub nonce: i64,
+    #[serde(default)]
+    pub timestamp: i64,
+}
+
+/// Resting orders on each side of a market, best price first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderBookOrders {
+    #[serde(default)]
+    pub asks: Vec<BookOrder>,
+    #[serde(default)]
+    pub bids: Vec<BookOrder>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookOrder {
+    #[serde(default)]
+    pub order_index: i64,
+    #[serde(default)]
+    pub price: String,
+    #[serde(default)]
+    pub remaining_base_amount: String,
+}
+
+impl BookOrder {
+    pub fn price(&self) -> Option<Decimal> {
+        Decimal::from_str(&self.price).ok()
+    }
+
+    pub fn remaining_base_amount(&self) -> Option<Decimal> {
+        Decimal::from_str(&self.remaining_base_amount).ok()
+    }
+}
+
+impl OrderBookOrders {
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.first().and_then(BookOrder::price)
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.first().and_then(BookOrder::price)
+    }
+
+    /// Midpoint of the best bid and ask, if both sides are populated.
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_bid()? + self.best_ask()?) / Decimal::TWO)
+    }
+}
+
+/// Price levels of a streamed book, such as [`crate::strategy::BookUpdate::book`],
+/// as aggregated resting orders.
+impl From<&OrderBook> for OrderBookOrders {
+    fn from(book: &OrderBook) -> Self {
+        let orders = |levels: &[PriceLevel]| {
+            levels
+                .iter()
+                .map(|level| BookOrder {
+                    order_index: 0,
+                    price: level.price.clone(),
+                    remaining_base_amount: level.quantity.clone(),
+                })
+                .collect()
+        };
+        Self {
+            asks: orders(&book.asks),
+            bids: orders(&book.bids),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct RawAccountEntry {
     account_index: i64,
@@ -104,6 +312,8 @@ struct RawAccountEntry {
     #[serde(default)]
     l1_address: String,
     #[serde(default)]
+    total_order_count: i64,
+    #[serde(default)]
     positions: Vec<RawAccountPosition>,
     #[serde(default)]
     balances: Vec<RawAccountBalance>,
@@ -123,9 +333,13 @@ struct RawAccountBalance {
 
 #[derive(Debug, Clone, Deserialize)]
 struct RawAccountPosition {
+    #[serde(default)]
+    market_id: i32,
     #[serde(default)]
     symbol: String,
     #[serde(default)]
+    sign: i32,
+    #[serde(default)]
     position: String,
     #[serde(default)]
     avg_entry_price: String,
@@ -139,6 +353,10 @@ struct RawAccountPosition {
     margin_mode: i32,
     #[serde(default)]
     initial_margin_fraction: String,
+    #[serde(default)]
+    allocated_margin: String,
+    #[serde(default)]
+    open_order_count: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -149,6 +367,8 @@ pub struct AccountSnapshot {
     pub collateral: String,
     pub cross_asset_value: String,
     pub total_asset_value: String,
+    /// Resting orders across all markets.
+    pub total_order_count: i64,
     pub balances: Vec<AccountBalance>,
     pub positions: Vec<AccountPosition>,
 }
@@ -163,14 +383,34 @@ pub struct AccountBalance {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct AccountPosition {
+    pub market_id: i32,
     pub symbol: String,
+    /// `1` for long, `-1` for short; `size` is always unsigned.
+    pub sign: i32,
     pub size: String,
     pub average_entry_price: String,
     pub notional_value: String,
     pub unrealized_pnl: String,
     pub realized_pnl: String,
+    /// `0` for cross margin, `1` for isolated; see [`Self::margin_type`].
     pub margin_mode: i32,
+    /// In percent; `33.33` is 3x leverage.
     pub initial_margin_fraction: String,
+    /// Collateral set aside for an isolated position.
+    pub allocated_margin: String,
+    pub open_order_count: i64,
+}
+
+impl AccountPosition {
+    /// Position size, negative when short.
+    pub fn signed_size(&self) -> Option<Decimal> {
+        let size = Decimal::from_str(&self.size).ok()?;
+        Some(if self.sign < 0 { -size } else { size })
+    }
+
+    pub fn margin_type(&self) -> MarginType {
+        MarginType::from_lighter_code(self.margin_mode).unwrap_or(MarginType::Cross)
+    }
 }
 
 impl From<RawAccountEntry> for AccountSnapshot {
@@ -199,7 +439,9 @@ impl From<RawAccountEntry> for AccountSnapshot {
             .positions
             .into_iter()
             .map(|position| AccountPosition {
+                market_id: position.market_id,
                 symbol: position.symbol,
+                sign: position.sign,
                 size: position.position,
                 average_entry_price: position.avg_entry_price,
                 notional_value: position.position_value,
@@ -207,6 +449,8 @@ impl From<RawAccountEntry> for AccountSnapshot {
                 realized_pnl: position.realized_pnl,
                 margin_mode: position.margin_mode,
                 initial_margin_fraction: position.initial_margin_fraction,
+                allocated_margin: position.allocated_margin,
+                open_order_count: position.open_order_count,
             })
             .collect();
 
@@ -217,6 +461,7 @@ impl From<RawAccountEntry> for AccountSnapshot {
             collateral: value.collateral,
             cross_asset_value: value.cross_asset_value,
             total_asset_value: value.total_asset_value,
+            total_order_count: value.total_order_count,
             balances,
             positions,
         }
@@ -245,7 +490,9 @@ mod tests {
                     ],
                     "positions": [
                         {
+                            "market_id": 94,
                             "symbol": "MEGA",
+                            "sign": -1,
                             "position": "17963.0",
                             "avg_entry_price": "0.48774",
                             "position_value": "8815.162620",
@@ -267,5 +514,57 @@ mod tests {
         assert_eq!(snapshot.balances.len(), 1);
         assert_eq!(snapshot.positions.len(), 1);
         assert_eq!(snapshot.positions[0].symbol, "MEGA");
+        assert_eq!(
+            snapshot.positions[0].signed_size(),
+            Some(Decimal::new(-179630, 1))
+        );
+    }
+
+    #[test]
+    fn parses_order_book_orders() {
+        let raw = r#"{
+            "code": 200,
+            "total_asks": 1,
+            "asks": [{"order_index": 7, "price": "3001.00", "remaining_base_amount": "0.5000"}],
+            "total_bids": 1,
+            "bids": [{"order_index": 8, "price": "2999.00", "remaining_base_amount": "1.2000"}]
+        }"#;
+        let book: OrderBookOrders = serde_json::from_str(raw).expect("valid json");
+        assert_eq!(book.mid_price(), Some(Decimal::from(3000)));
+        assert_eq!(
+            book.bids[0].remaining_base_amount(),
+            Some(Decimal::new(12, 1))
+        );
+    }
+
+    #[test]
+    fn parses_account_orders() {
+        let raw = r#"
+        {
+            "code": 200,
+            "orders": [
+                {
+                    "order_index": 281474976710657,
+                    "client_order_index": 42,
+                    "market_index": 0,
+                    "initial_base_amount": "0.100",
+                    "remaining_base_amount": "0.050",
+                    "filled_base_amount": "0.050",
+                    "price": "3000.00",
+                    "is_ask": false,
+                    "type": "limit",
+                    "time_in_force": "good-till-time",
+                    "status": "open"
+                }
+            ]
+        }
+        "#;
+
+        let response: RawOrdersResponse = serde_json::from_str(raw).expect("valid json");
+        assert_eq!(response.orders.len(), 1);
+        let order = &response.orders[0];
+        assert_eq!(order.client_order_index, 42);
+        assert_eq!(order.order_type, "limit");
+        assert!(!order.is_ask);
     }
 }
@@ -30,39 +30,52 @@
 //! }
 //! ```
 
+pub mod account_manager;
 pub mod api;
+pub mod auth;
+pub mod backtest;
 pub mod client;
 pub mod config;
 pub mod error;
 pub mod ffi_client;
 pub mod logging;
+pub mod margin;
 pub mod metadata;
 pub mod models;
 pub mod nonce;
+pub mod paper;
 pub mod signers;
+pub mod strategy;
 pub mod trading;
 
 // Re-export specific items to avoid ambiguous glob re-exports
+pub use account_manager::{
+    AccountManager, Receiver, SubAccount, Transfer, TxStatus, Withdrawal, WithdrawalStatus,
+};
 pub use api::{
     account::AccountApi,
     candlestick::{CandlestickApi, CandlestickInterval},
     order::OrderApi,
     transaction::TransactionApi,
 };
+pub use auth::AuthTokenProvider;
 pub use client::{api_client::ApiClient, signer_client::SignerClient, ws_client::WebSocketClient};
 pub use config::Config;
 pub use error::{LighterError, Result};
 pub use logging::{init_logging, init_logging_with_filter};
 // Re-export models modules individually
-pub use ffi_client::LighterFfiClient;
+pub use ffi_client::{LighterFfiClient, OrderBookOrders};
 pub use models::account::Account;
 pub use models::common::*;
-pub use models::order::{CreateOrderRequest, Order, TimeInForce};
+pub use models::order::{CancelAllMode, CreateOrderRequest, Order, OrderGrouping, TimeInForce};
 pub use signers::{
     account_tier_signature_message, ethereum::*, ffi::*, order_signature_message,
     sign_account_tier_payload, sign_order_payload,
 };
-pub use trading::{LighterFfiTradingClient, SubmittedOrder};
+pub use trading::{
+    ClientOrderId, CloseStyle, LighterFfiTradingClient, OrderExpiry, OrderOptions, OrderState,
+    OrderTracker, RiskLimits, RiskViolation, RoundingPolicy, SubmittedOrder,
+};
 
 /// Main client for interacting with the Lighter API
 #[derive(Debug)]
@@ -0,0 +1,407 @@
+use crate::error::{LighterError, Result};
+use crate::ffi_client::{AccountPosition, AccountSnapshot};
+use crate::metadata::MarketInfo;
+use crate::models::account::MarginType;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Margin state of one open position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionMargin {
+    pub market_id: i32,
+    pub symbol: String,
+    pub margin_type: MarginType,
+    /// Signed size, negative when short.
+    pub size: Decimal,
+    pub entry_price: Decimal,
+    pub mark_price: Decimal,
+    pub notional: Decimal,
+    pub unrealized_pnl: Decimal,
+    /// Fraction of notional required to hold the position, as set by its
+    /// leverage.
+    pub initial_margin_fraction: Decimal,
+    pub initial_margin: Decimal,
+    pub maintenance_margin: Decimal,
+    /// Collateral set aside for the position; zero under cross margin.
+    pub allocated_margin: Decimal,
+    /// Mark price at which the position would be liquidated, with every
+    /// other position held at its current mark. `None` when no positive
+    /// price liquidates it.
+    pub liquidation_price: Option<Decimal>,
+}
+
+impl PositionMargin {
+    /// Leverage the position is opened at.
+    pub fn leverage(&self) -> Option<Decimal> {
+        (!self.initial_margin_fraction.is_zero())
+            .then(|| Decimal::ONE / self.initial_margin_fraction)
+    }
+}
+
+/// Margin state of an account, from an [`AccountSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountMargin {
+    /// Cross collateral, excluding margin allocated to isolated positions.
+    pub collateral: Decimal,
+    /// Cross collateral plus the unrealised PnL of cross positions.
+    pub cross_equity: Decimal,
+    pub cross_initial_margin: Decimal,
+    pub cross_maintenance_margin: Decimal,
+    /// Cross equity not tied up as initial margin, available to open
+    /// positions. Open orders are not reserved against it.
+    pub free_collateral: Decimal,
+    /// Cross equity plus the equity of isolated positions.
+    pub total_equity: Decimal,
+    pub total_notional: Decimal,
+    pub positions: Vec<PositionMargin>,
+}
+
+impl AccountMargin {
+    /// Total notional over total equity; `None` without positive equity.
+    pub fn effective_leverage(&self) -> Option<Decimal> {
+        (self.total_equity > Decimal::ZERO).then(|| self.total_notional / self.total_equity)
+    }
+
+    /// Whether cross equity has fallen to the maintenance requirement.
+    pub fn is_liquidatable(&self) -> bool {
+        !self.cross_maintenance_margin.is_zero()
+            && self.cross_equity <= self.cross_maintenance_margin
+    }
+
+    pub fn position(&self, market_id: i32) -> Option<&PositionMargin> {
+        self.positions
+            .iter()
+            .find(|position| position.market_id == market_id)
+    }
+}
+
+/// Computes margin requirements, free collateral, leverage and liquidation
+/// prices from an account snapshot and per-market margin fractions, as
+/// returned by [`crate::metadata::MarketMetadata::fetch_market_details`].
+///
+/// Positions are marked at [`Self::set_mark`] prices, falling back to the
+/// exchange-reported position value.
+#[derive(Debug, Clone, Default)]
+pub struct MarginCalculator {
+    markets: HashMap<i32, MarketInfo>,
+    marks: HashMap<i32, Decimal>,
+}
+
+impl MarginCalculator {
+    pub fn new(markets: impl IntoIterator<Item = MarketInfo>) -> Self {
+        Self {
+            markets: markets
+                .into_iter()
+                .map(|info| (info.market_id, info))
+                .collect(),
+            marks: HashMap::new(),
+        }
+    }
+
+    pub fn with_mark(mut self, market_id: i32, price: Decimal) -> Self {
+        self.set_mark(market_id, price);
+        self
+    }
+
+    pub fn set_mark(&mut self, market_id: i32, price: Decimal) {
+        self.marks.insert(market_id, price);
+    }
+
+    /// Margin state of `account`. Fails if a position's market is unknown
+    /// or has no maintenance margin fraction.
+    pub fn account(&self, account: &AccountSnapshot) -> Result<AccountMargin> {
+        let collateral = parse_or_zero(&account.collateral);
+        let mut positions = Vec::with_capacity(account.positions.len());
+        for position in &account.positions {
+            if let Some(margin) = self.position(position)? {
+                positions.push(margin);
+            }
+        }
+
+        let cross = || {
+            positions
+                .iter()
+                .filter(|position| position.margin_type == MarginType::Cross)
+        };
+        let cross_equity = collateral + cross().map(|p| p.unrealized_pnl).sum::<Decimal>();
+        let cross_initial_margin: Decimal = cross().map(|p| p.initial_margin).sum();
+        let cross_maintenance_margin: Decimal = cross().map(|p| p.maintenance_margin).sum();
+        let isolated_equity: Decimal = positions
+            .iter()
+            .filter(|position| position.margin_type == MarginType::Isolated)
+            .map(|position| position.allocated_margin + position.unrealized_pnl)
+            .sum();
+
+        for position in &mut positions {
+            let buffer = match position.margin_type {
+                MarginType::Isolated => position.allocated_margin,
+                MarginType::Cross => {
+                    cross_equity
+                        - position.unrealized_pnl
+                        - (cross_maintenance_margin - position.maintenance_margin)
+                }
+            };
+            let maintenance_fraction = if position.notional.is_zero() {
+                Decimal::ZERO
+            } else {
+                position.maintenance_margin / position.notional
+            };
+            position.liquidation_price = liquidation_price(
+                position.size,
+                position.entry_price,
+                buffer,
+                maintenance_fraction,
+            );
+        }
+
+        Ok(AccountMargin {
+            collateral,
+            cross_equity,
+            cross_initial_margin,
+            cross_maintenance_margin,
+            free_collateral: (cross_equity - cross_initial_margin).max(Decimal::ZERO),
+            total_equity: cross_equity + isolated_equity,
+            total_notional: positions.iter().map(|position| position.notional).sum(),
+            positions,
+        })
+    }
+
+    /// Largest size that can be ordered in `market_id` at `price` before
+    /// signing, rounded down to the market's size step. Reducing an
+    /// existing position needs no margin; under cross margin, closing it
+    /// also releases its margin for the flipped side. Fees are ignored.
+    pub fn max_order_size(
+        &self,
+        account: &AccountSnapshot,
+        market_id: i32,
+        is_buy: bool,
+        price: Decimal,
+    ) -> Result<Decimal> {
+        if price <= Decimal::ZERO {
+            return Err(LighterError::OrderValidation(format!(
+                "price {price} must be positive"
+            )));
+        }
+        let info = self.market(market_id)?;
+        let margin = self.account(account)?;
+        let existing = margin.position(market_id);
+
+        let fraction = existing
+            .map(|position| position.initial_margin_fraction)
+            .filter(|fraction| !fraction.is_zero())
+            .or_else(|| info.initial_margin_fraction())
+            .filter(|fraction| !fraction.is_zero())
+            .ok_or_else(|| {
+                LighterError::AccountState(format!(
+                    "no initial margin fraction known for market {market_id}"
+                ))
+            })?;
+
+        let size = existing.map_or(Decimal::ZERO, |position| position.size);
+        let reducible = if is_buy { -size } else { size }.max(Decimal::ZERO);
+        let released = match existing.map(|position| position.margin_type) {
+            Some(MarginType::Cross) => reducible,
+            _ => Decimal::ZERO,
+        };
+        let opening = margin.free_collateral / (fraction * price) + released;
+        let step = info.size_step();
+        Ok(((reducible + opening) / step).floor() * step)
+    }
+
+    fn market(&self, market_id: i32) -> Result<&MarketInfo> {
+        self.markets.get(&market_id).ok_or_else(|| {
+            LighterError::AccountState(format!("no metadata for market {market_id}"))
+        })
+    }
+
+    fn position(&self, position: &AccountPosition) -> Result<Option<PositionMargin>> {
+        let size = position.signed_size().unwrap_or_default();
+        if size.is_zero() {
+            return Ok(None);
+        }
+        let info = self.market(position.market_id)?;
+        let maintenance_fraction = info.maintenance_margin_fraction().ok_or_else(|| {
+            LighterError::AccountState(format!(
+                "no maintenance margin fraction for market {}",
+                position.market_id
+            ))
+        })?;
+        let initial_margin_fraction = Decimal::from_str(&position.initial_margin_fraction)
+            .ok()
+            .filter(|percent| !percent.is_zero())
+            .map(|percent| percent / Decimal::ONE_HUNDRED)
+            .or_else(|| info.initial_margin_fraction())
+            .unwrap_or(maintenance_fraction);
+
+        let entry_price = parse_or_zero(&position.average_entry_price);
+        let mark_price = self
+            .marks
+            .get(&position.market_id)
+            .copied()
+            .or_else(|| {
+                Decimal::from_str(&position.notional_value)
+                    .ok()
+                    .filter(|notional| !notional.is_zero())
+                    .map(|notional| notional / size.abs())
+            })
+            .unwrap_or(entry_price);
+        let notional = size.abs() * mark_price;
+        let margin_type = position.margin_type();
+
+        Ok(Some(PositionMargin {
+            market_id: position.market_id,
+            symbol: position.symbol.clone(),
+            margin_type,
+            size,
+            entry_price,
+            mark_price,
+            notional,
+            unrealized_pnl: size * (mark_price - entry_price),
+            initial_margin_fraction,
+            initial_margin: notional * initial_margin_fraction,
+            maintenance_margin: notional * maintenance_fraction,
+            allocated_margin: match margin_type {
+                MarginType::Isolated => parse_or_zero(&position.allocated_margin),
+                MarginType::Cross => Decimal::ZERO,
+            },
+            liquidation_price: None,
+        }))
+    }
+}
+
+/// Price `p` where `buffer + size * (p - entry)` falls to
+/// `maintenance_fraction * |size| * p`.
+fn liquidation_price(
+    size: Decimal,
+    entry: Decimal,
+    buffer: Decimal,
+    maintenance_fraction: Decimal,
+) -> Option<Decimal> {
+    let denominator = size - maintenance_fraction * size.abs();
+    if denominator.is_zero() {
+        return None;
+    }
+    let price = (size * entry - buffer) / denominator;
+    (price > Decimal::ZERO).then_some(price)
+}
+
+fn parse_or_zero(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn btc() -> MarketInfo {
+        MarketInfo {
+            market_id: 1,
+            symbol: "BTC".to_string(),
+            supported_size_decimals: Some(3),
+            default_initial_margin_fraction: Some(1_000),
+            min_initial_margin_fraction: Some(200),
+            maintenance_margin_fraction: Some(500),
+            ..Default::default()
+        }
+    }
+
+    fn position(sign: i32, margin_mode: i32, allocated: &str) -> AccountPosition {
+        AccountPosition {
+            market_id: 1,
+            symbol: "BTC".to_string(),
+            sign,
+            size: "1".to_string(),
+            average_entry_price: "100".to_string(),
+            notional_value: "100".to_string(),
+            unrealized_pnl: String::new(),
+            realized_pnl: String::new(),
+            margin_mode,
+            initial_margin_fraction: "10".to_string(),
+            allocated_margin: allocated.to_string(),
+            open_order_count: 0,
+        }
+    }
+
+    fn account(collateral: &str, positions: Vec<AccountPosition>) -> AccountSnapshot {
+        AccountSnapshot {
+            account_index: 1,
+            l1_address: String::new(),
+            available_balance: String::new(),
+            collateral: collateral.to_string(),
+            cross_asset_value: String::new(),
+            total_asset_value: String::new(),
+            total_order_count: 0,
+            balances: Vec::new(),
+            positions,
+        }
+    }
+
+    #[test]
+    fn cross_long_margin_and_liquidation() {
+        let calculator = MarginCalculator::new([btc()]).with_mark(1, Decimal::from(110));
+        let margin = calculator
+            .account(&account("50", vec![position(1, 0, "")]))
+            .unwrap();
+
+        let btc = margin.position(1).unwrap();
+        assert_eq!(btc.unrealized_pnl, Decimal::from(10));
+        assert_eq!(btc.initial_margin, Decimal::from(11));
+        assert_eq!(btc.maintenance_margin, Decimal::new(55, 1));
+        assert_eq!(btc.leverage(), Some(Decimal::from(10)));
+        assert_eq!(margin.cross_equity, Decimal::from(60));
+        assert_eq!(margin.free_collateral, Decimal::from(49));
+        // 50 + (p - 100) = 0.05 p
+        assert_eq!(
+            btc.liquidation_price.unwrap().round_dp(4),
+            Decimal::new(526316, 4)
+        );
+        assert!(!margin.is_liquidatable());
+        assert_eq!(
+            margin.effective_leverage().unwrap().round_dp(4),
+            Decimal::new(18333, 4)
+        );
+    }
+
+    #[test]
+    fn isolated_short_liquidates_on_its_allocation() {
+        let calculator = MarginCalculator::new([btc()]);
+        let margin = calculator
+            .account(&account("1000", vec![position(-1, 1, "20")]))
+            .unwrap();
+
+        let btc = margin.position(1).unwrap();
+        assert_eq!(btc.margin_type, MarginType::Isolated);
+        assert_eq!(btc.mark_price, Decimal::from(100));
+        assert_eq!(margin.cross_initial_margin, Decimal::ZERO);
+        assert_eq!(margin.free_collateral, Decimal::from(1_000));
+        // 20 - (p - 100) = 0.05 p
+        assert_eq!(
+            btc.liquidation_price.unwrap().round_dp(4),
+            Decimal::new(1142857, 4)
+        );
+    }
+
+    #[test]
+    fn max_order_size_counts_reducible_and_released_margin() {
+        let calculator = MarginCalculator::new([btc()]);
+        let snapshot = account("50", vec![position(1, 0, "")]);
+
+        // 40 free at 10% of 100 per unit.
+        let buy = calculator
+            .max_order_size(&snapshot, 1, true, Decimal::from(100))
+            .unwrap();
+        assert_eq!(buy, Decimal::from(4));
+
+        // Close 1, release its 10 of margin, then open 5 short.
+        let sell = calculator
+            .max_order_size(&snapshot, 1, false, Decimal::from(100))
+            .unwrap();
+        assert_eq!(sell, Decimal::from(6));
+
+        assert!(matches!(
+            calculator.max_order_size(&snapshot, 9, true, Decimal::from(100)),
+            Err(LighterError::AccountState(_))
+        ));
+    }
+}
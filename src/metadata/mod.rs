@@ -1,6 +1,7 @@
 use crate::client::ApiClient;
 use crate::config::Config;
 use crate::error::{LighterError, Result};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -20,6 +21,14 @@ impl MarketMetadata {
         Ok(response.order_books)
     }
 
+    /// Like [`Self::fetch_markets`], but from `orderBookDetails`, which also
+    /// carries the markets' margin fractions.
+    pub async fn fetch_market_details(&self) -> Result<Vec<MarketInfo>> {
+        let response: OrderBookDetailsResponse = self.api_client.get("orderBookDetails").await?;
+
+        Ok(response.order_book_details)
+    }
+
     pub async fn build_index_map(&self) -> Result<HashMap<String, i32>> {
         let markets = self.fetch_markets().await?;
         let mut map = HashMap::with_capacity(markets.len());
@@ -47,7 +56,12 @@ struct OrderBooksResponse {
     pub order_books: Vec<MarketInfo>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+struct OrderBookDetailsResponse {
+    pub order_book_details: Vec<MarketInfo>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct MarketInfo {
     pub market_id: i32,
     pub symbol: String,
@@ -57,6 +71,60 @@ pub struct MarketInfo {
     pub supported_price_decimals: Option<u32>,
     #[serde(default)]
     pub supported_quote_decimals: Option<u32>,
+    /// Smallest order size, in base units.
+    #[serde(default)]
+    pub min_base_amount: Option<Decimal>,
+    /// Smallest order notional, in quote units.
+    #[serde(default)]
+    pub min_quote_amount: Option<Decimal>,
+    #[serde(default)]
+    pub maker_fee: Option<Decimal>,
+    #[serde(default)]
+    pub taker_fee: Option<Decimal>,
+    /// Margin fractions in basis points (`500` is 5%), only reported by
+    /// [`MarketMetadata::fetch_market_details`].
+    #[serde(default)]
+    pub default_initial_margin_fraction: Option<u32>,
+    #[serde(default)]
+    pub min_initial_margin_fraction: Option<u32>,
+    #[serde(default)]
+    pub maintenance_margin_fraction: Option<u32>,
+    #[serde(default)]
+    pub closeout_margin_fraction: Option<u32>,
+}
+
+impl MarketInfo {
+    /// Smallest size increment accepted by the market.
+    pub fn size_step(&self) -> Decimal {
+        Decimal::new(1, self.supported_size_decimals.unwrap_or(0).min(28))
+    }
+
+    /// Smallest price increment accepted by the market.
+    pub fn price_tick(&self) -> Decimal {
+        Decimal::new(1, self.supported_price_decimals.unwrap_or(0).min(28))
+    }
+
+    /// Initial margin of a new position as a fraction of its notional.
+    pub fn initial_margin_fraction(&self) -> Option<Decimal> {
+        self.default_initial_margin_fraction.map(basis_points)
+    }
+
+    /// Maintenance margin as a fraction of notional; below it a position
+    /// is liquidated.
+    pub fn maintenance_margin_fraction(&self) -> Option<Decimal> {
+        self.maintenance_margin_fraction.map(basis_points)
+    }
+
+    /// Highest leverage the market allows.
+    pub fn max_leverage(&self) -> Option<Decimal> {
+        self.min_initial_margin_fraction
+            .filter(|fraction| *fraction > 0)
+            .map(|fraction| Decimal::ONE / basis_points(fraction))
+    }
+}
+
+fn basis_points(value: u32) -> Decimal {
+    Decimal::new(i64::from(value), 4)
 }
 
 #[cfg(test)]
@@ -75,4 +143,44 @@ mod tests {
         assert_eq!(serialized["symbol"], "MEGA");
         assert_eq!(serialized["supported_size_decimals"], 3);
     }
+
+    #[test]
+    fn market_info_parses_limits_and_fees() {
+        let payload = r#"{
+            "market_id": 0,
+            "symbol": "ETH",
+            "taker_fee": "0.0300",
+            "maker_fee": "0.0000",
+            "min_base_amount": "0.0050",
+            "min_quote_amount": "10.000000",
+            "supported_size_decimals": 4,
+            "supported_price_decimals": 2,
+            "supported_quote_decimals": 6
+        }"#;
+        let market: MarketInfo = serde_json::from_str(payload).expect("valid json");
+        assert_eq!(market.min_base_amount, Some(Decimal::new(50, 4)));
+        assert_eq!(market.min_quote_amount, Some(Decimal::from(10)));
+        assert_eq!(market.taker_fee, Some(Decimal::new(300, 4)));
+        assert_eq!(market.size_step(), Decimal::new(1, 4));
+        assert_eq!(market.price_tick(), Decimal::new(1, 2));
+    }
+
+    #[test]
+    fn market_info_parses_margin_fractions() {
+        let payload = r#"{
+            "market_id": 1,
+            "symbol": "BTC",
+            "default_initial_margin_fraction": 500,
+            "min_initial_margin_fraction": 200,
+            "maintenance_margin_fraction": 120,
+            "closeout_margin_fraction": 80
+        }"#;
+        let market: MarketInfo = serde_json::from_str(payload).expect("valid json");
+        assert_eq!(market.initial_margin_fraction(), Some(Decimal::new(5, 2)));
+        assert_eq!(
+            market.maintenance_margin_fraction(),
+            Some(Decimal::new(12, 3))
+        );
+        assert_eq!(market.max_leverage(), Some(Decimal::from(50)));
+    }
 }
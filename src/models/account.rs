@@ -43,6 +43,26 @@ pub enum MarginType {
     Isolated,
 }
 
+impl MarginType {
+    /// Margin mode code used in Lighter's account data and update-leverage
+    /// transaction.
+    pub fn lighter_code(&self) -> i32 {
+        match self {
+            Self::Cross => 0,
+            Self::Isolated => 1,
+        }
+    }
+
+    /// Inverse of [`MarginType::lighter_code`].
+    pub fn from_lighter_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Cross),
+            1 => Some(Self::Isolated),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountTierSwitchRequest {
     pub target_tier: AccountTier,
@@ -1,5 +1,8 @@
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
@@ -28,6 +31,34 @@ pub enum OrderType {
     Twap,
 }
 
+impl OrderType {
+    /// Order type code used in Lighter's signed order payload.
+    pub fn lighter_code(&self) -> i32 {
+        match self {
+            Self::Limit => 0,
+            Self::Market => 1,
+            Self::StopLoss => 2,
+            Self::StopLossLimit => 3,
+            Self::TakeProfit => 4,
+            Self::TakeProfitLimit => 5,
+            Self::Twap => 6,
+        }
+    }
+
+    pub fn from_lighter_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Limit),
+            1 => Some(Self::Market),
+            2 => Some(Self::StopLoss),
+            3 => Some(Self::StopLossLimit),
+            4 => Some(Self::TakeProfit),
+            5 => Some(Self::TakeProfitLimit),
+            6 => Some(Self::Twap),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderStatus {
@@ -37,6 +68,7 @@ pub enum OrderStatus {
     Filled,
     Cancelled,
     Rejected,
+    Expired,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -66,3 +98,46 @@ pub struct OrderBook {
     pub asks: Vec<PriceLevel>,
     pub timestamp: DateTime<Utc>,
 }
+
+/// Lighter timestamps are in milliseconds, older payloads and some channels
+/// in seconds.
+pub(crate) fn parse_timestamp(raw: i64) -> Option<DateTime<Utc>> {
+    if raw > 10_000_000_000 {
+        DateTime::from_timestamp_millis(raw)
+    } else {
+        DateTime::from_timestamp(raw, 0)
+    }
+}
+
+/// Decimal field of a JSON payload, sent either as a string or a number.
+pub(crate) fn decimal_field(value: &Value, key: &str) -> Option<Decimal> {
+    match value.get(key)? {
+        Value::String(text) => Decimal::from_str(text).ok(),
+        Value::Number(number) => Decimal::from_str(&number.to_string()).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_second_and_millisecond_timestamps() {
+        let at = DateTime::from_timestamp(1_722_339_648, 0).unwrap();
+        assert_eq!(parse_timestamp(1_722_339_648), Some(at));
+        assert_eq!(parse_timestamp(1_722_339_648_000), Some(at));
+
+        let level = serde_json::json!({ "price": "3000.50", "size": 0.25, "side": true });
+        assert_eq!(
+            decimal_field(&level, "price"),
+            Decimal::from_str("3000.50").ok()
+        );
+        assert_eq!(
+            decimal_field(&level, "size"),
+            Decimal::from_str("0.25").ok()
+        );
+        assert_eq!(decimal_field(&level, "side"), None);
+        assert_eq!(decimal_field(&level, "missing"), None);
+    }
+}
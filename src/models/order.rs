@@ -32,6 +32,82 @@ pub enum TimeInForce {
     Po,  // Post Only
 }
 
+impl TimeInForce {
+    /// Time-in-force code used in Lighter's signed order payload. Day orders
+    /// are sent as good-till-time with an end-of-day expiry.
+    pub fn lighter_code(&self) -> i32 {
+        match self {
+            Self::Ioc => 0,
+            Self::Gtc | Self::Day => 1,
+            Self::Po => 2,
+        }
+    }
+
+    /// Inverse of [`TimeInForce::lighter_code`]; good-till-time maps to `Gtc`.
+    pub fn from_lighter_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Ioc),
+            1 => Some(Self::Gtc),
+            2 => Some(Self::Po),
+            _ => None,
+        }
+    }
+}
+
+/// Time-in-force modes of Lighter's cancel-all transaction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelAllMode {
+    /// Cancel every open order right away.
+    Immediate,
+    /// Cancel every open order at the given time unless aborted first.
+    Scheduled(DateTime<Utc>),
+    /// Abort a previously scheduled cancel-all.
+    AbortScheduled,
+}
+
+impl CancelAllMode {
+    /// Time-in-force code expected by the signer.
+    pub fn time_in_force(&self) -> i32 {
+        match self {
+            Self::Immediate => 0,
+            Self::Scheduled(_) => 1,
+            Self::AbortScheduled => 2,
+        }
+    }
+
+    /// Scheduled execution time in milliseconds, or `0` when not scheduled.
+    pub fn time_ms(&self) -> i64 {
+        match self {
+            Self::Scheduled(at) => at.timestamp_millis(),
+            _ => 0,
+        }
+    }
+}
+
+/// How the orders of a grouped-order transaction are linked.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderGrouping {
+    /// The second order is placed once the first fills.
+    OneTriggersOther,
+    /// Filling either order cancels the other.
+    OneCancelsOther,
+    /// A filled first order places two orders that cancel each other.
+    OneTriggersOneCancelsOther,
+}
+
+impl OrderGrouping {
+    /// Grouping code expected by the signer.
+    pub fn lighter_code(&self) -> i32 {
+        match self {
+            Self::OneTriggersOther => 1,
+            Self::OneCancelsOther => 2,
+            Self::OneTriggersOneCancelsOther => 3,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateOrderRequest {
     pub symbol: String,
@@ -35,6 +35,28 @@ impl NonceManager {
         }
     }
 
+    /// Reserve `count` consecutive nonces and return the first one.
+    pub fn reserve(&self, count: u64) -> Result<u64> {
+        if count == 0 {
+            return Err(LighterError::Nonce(
+                "cannot reserve an empty nonce range".to_string(),
+            ));
+        }
+        loop {
+            let previous = self.last_nonce.load(Ordering::Acquire);
+            let candidate = previous
+                .checked_add(count)
+                .ok_or_else(|| LighterError::Nonce("nonce overflow".to_string()))?;
+            if self
+                .last_nonce
+                .compare_exchange(previous, candidate, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return Ok(previous + 1);
+            }
+        }
+    }
+
     pub fn synchronise(&self, next_nonce: u64) {
         if next_nonce == 0 {
             return;
@@ -149,4 +171,12 @@ mod tests {
         let second = manager.generate().expect("nonce");
         assert_eq!(second, first + 1);
     }
+
+    #[test]
+    fn test_nonce_reserve_is_contiguous() {
+        let manager = NonceManager::with_seed(10);
+        assert_eq!(manager.reserve(5).unwrap(), 10);
+        assert_eq!(manager.generate().unwrap(), 15);
+        assert!(manager.reserve(0).is_err());
+    }
 }
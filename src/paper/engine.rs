@@ -0,0 +1,551 @@
+use super::{PaperConfig, PaperFill, PaperOrder, PaperPosition};
+use crate::error::{LighterError, Result};
+use crate::metadata::MarketInfo;
+use crate::models::common::{OrderBook, Side};
+use crate::models::order::TimeInForce;
+use crate::strategy::{MarketTrade, StrategyOrder, StrategyOrderKind};
+use crate::trading::{OrderState, TrackedOrder};
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+const BPS_DENOMINATOR: i64 = 10_000;
+
+/// Last seen book of a market and how much of each level simulated orders
+/// have already taken.
+#[derive(Debug, Default)]
+struct Book {
+    /// Best first.
+    bids: Vec<(Decimal, Decimal)>,
+    /// Best first.
+    asks: Vec<(Decimal, Decimal)>,
+    consumed: HashMap<(bool, Decimal), Decimal>,
+}
+
+impl Book {
+    fn from_order_book(book: &OrderBook) -> Self {
+        let parse = |levels: &[crate::models::common::PriceLevel]| {
+            levels
+                .iter()
+                .filter_map(|level| {
+                    Some((
+                        Decimal::from_str(&level.price).ok()?,
+                        Decimal::from_str(&level.quantity).ok()?,
+                    ))
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut bids = parse(&book.bids);
+        let mut asks = parse(&book.asks);
+        bids.sort_by_key(|(price, _)| std::cmp::Reverse(*price));
+        asks.sort_by_key(|(price, _)| *price);
+        Self {
+            bids,
+            asks,
+            consumed: HashMap::new(),
+        }
+    }
+
+    fn mid(&self) -> Option<Decimal> {
+        Some((self.bids.first()?.0 + self.asks.first()?.0) / Decimal::TWO)
+    }
+
+    /// Levels a buyer (`is_buy`) or seller can trade against, at or better
+    /// than `limit`, with the share still available to simulated orders.
+    fn available(
+        &self,
+        is_buy: bool,
+        limit: Decimal,
+        fill_ratio: Decimal,
+    ) -> Vec<(Decimal, Decimal)> {
+        let levels = if is_buy { &self.asks } else { &self.bids };
+        levels
+            .iter()
+            .take_while(|(price, _)| {
+                if is_buy {
+                    *price <= limit
+                } else {
+                    *price >= limit
+                }
+            })
+            .filter_map(|(price, size)| {
+                let used = self
+                    .consumed
+                    .get(&(is_buy, *price))
+                    .copied()
+                    .unwrap_or_default();
+                let left = *size * fill_ratio - used;
+                (left > Decimal::ZERO).then_some((*price, left))
+            })
+            .collect()
+    }
+
+    fn consume(&mut self, is_buy: bool, price: Decimal, size: Decimal) {
+        *self.consumed.entry((is_buy, price)).or_default() += size;
+    }
+}
+
+/// Matching and accounting state behind [`super::PaperExchange`].
+#[derive(Debug)]
+pub(super) struct Engine {
+    config: PaperConfig,
+    markets: HashMap<i32, MarketInfo>,
+    books: HashMap<i32, Book>,
+    orders: BTreeMap<i64, PaperOrder>,
+    positions: BTreeMap<i32, PaperPosition>,
+    collateral: Decimal,
+    fees_paid: Decimal,
+    fills: Vec<PaperFill>,
+    updates: Vec<TrackedOrder>,
+    next_order_index: i64,
+    next_client_index: i64,
+    clock: Option<DateTime<Utc>>,
+}
+
+impl Engine {
+    pub(super) fn new(config: PaperConfig) -> Self {
+        Self {
+            collateral: config.initial_collateral,
+            config,
+            markets: HashMap::new(),
+            books: HashMap::new(),
+            orders: BTreeMap::new(),
+            positions: BTreeMap::new(),
+            fees_paid: Decimal::ZERO,
+            fills: Vec::new(),
+            updates: Vec::new(),
+            next_order_index: 1,
+            next_client_index: 1,
+            clock: None,
+        }
+    }
+
+    pub(super) fn add_market(&mut self, info: MarketInfo) {
+        self.markets.insert(info.market_id, info);
+    }
+
+    pub(super) fn market(&self, symbol: &str) -> Result<&MarketInfo> {
+        self.markets
+            .values()
+            .find(|info| info.symbol.eq_ignore_ascii_case(symbol))
+            .ok_or_else(|| LighterError::Api {
+                status: 404,
+                message: format!("Unknown paper market {symbol}"),
+            })
+    }
+
+    /// Market data time, or wall time before any data has been seen.
+    pub(super) fn now(&self) -> DateTime<Utc> {
+        self.clock.unwrap_or_else(Utc::now)
+    }
+
+    pub(super) fn collateral(&self) -> Decimal {
+        self.collateral
+    }
+
+    pub(super) fn fees_paid(&self) -> Decimal {
+        self.fees_paid
+    }
+
+    pub(super) fn equity(&self) -> Decimal {
+        self.collateral
+            + self
+                .positions
+                .values()
+                .filter_map(|position| {
+                    let mark = self.books.get(&position.market_id)?.mid()?;
+                    Some(position.unrealized_pnl(mark))
+                })
+                .sum::<Decimal>()
+    }
+
+    pub(super) fn positions(&self) -> Vec<PaperPosition> {
+        self.positions
+            .values()
+            .filter(|position| !position.size.is_zero())
+            .cloned()
+            .collect()
+    }
+
+    pub(super) fn orders(&self) -> impl Iterator<Item = &PaperOrder> {
+        self.orders.values()
+    }
+
+    pub(super) fn order(&self, client_order_index: i64) -> Option<&PaperOrder> {
+        self.orders.get(&client_order_index)
+    }
+
+    pub(super) fn fills(&self) -> &[PaperFill] {
+        &self.fills
+    }
+
+    pub(super) fn take_updates(&mut self) -> Vec<TrackedOrder> {
+        std::mem::take(&mut self.updates)
+    }
+
+    pub(super) fn assign_client_index(&mut self) -> i64 {
+        let index = self.next_client_index;
+        self.next_client_index += 1;
+        index
+    }
+
+    /// Accept an order. Orders become active after the configured latency;
+    /// until then they cannot fill.
+    pub(super) fn submit(
+        &mut self,
+        order: &StrategyOrder,
+        client_order_index: i64,
+    ) -> Result<PaperOrder> {
+        if self.orders.contains_key(&client_order_index) {
+            return Err(LighterError::OrderValidation(format!(
+                "client order index {client_order_index} is already in use"
+            )));
+        }
+        let info = self.market(&order.symbol)?.clone();
+        let base_amount = order.base_amount.round_dp_with_strategy(
+            info.supported_size_decimals.unwrap_or(0),
+            RoundingStrategy::ToZero,
+        );
+        if base_amount <= Decimal::ZERO {
+            return Err(LighterError::OrderValidation(format!(
+                "order size {} must be positive",
+                order.base_amount
+            )));
+        }
+
+        let (price, time_in_force, is_market) = match order.kind {
+            StrategyOrderKind::Limit {
+                price,
+                time_in_force,
+            } => {
+                if price <= Decimal::ZERO {
+                    return Err(LighterError::OrderValidation(format!(
+                        "order price {price} must be positive"
+                    )));
+                }
+                (
+                    price.round_dp(info.supported_price_decimals.unwrap_or(0)),
+                    time_in_force,
+                    false,
+                )
+            }
+            StrategyOrderKind::Market { max_slippage_bps } => {
+                let book = self.books.get(&info.market_id);
+                let touch = book.and_then(|book| {
+                    if order.is_buy {
+                        book.asks.first()
+                    } else {
+                        book.bids.first()
+                    }
+                });
+                let Some((touch, _)) = touch else {
+                    return Err(LighterError::OrderValidation(format!(
+                        "no resting liquidity to price a market order on {}",
+                        info.symbol
+                    )));
+                };
+                let slippage = Decimal::from(max_slippage_bps) / Decimal::from(BPS_DENOMINATOR);
+                let limit = if order.is_buy {
+                    *touch * (Decimal::ONE + slippage)
+                } else {
+                    *touch * (Decimal::ONE - slippage)
+                };
+                (limit, TimeInForce::Ioc, true)
+            }
+        };
+
+        let now = self.now();
+        let paper = PaperOrder {
+            client_order_index,
+            order_index: self.next_order_index,
+            market_id: info.market_id,
+            symbol: info.symbol.clone(),
+            is_buy: order.is_buy,
+            price,
+            base_amount,
+            filled_base: Decimal::ZERO,
+            filled_quote: Decimal::ZERO,
+            time_in_force,
+            is_market,
+            reduce_only: order.reduce_only,
+            state: OrderState::Submitted,
+            active_at: now + self.config.latency,
+        };
+        self.next_order_index += 1;
+        self.orders.insert(client_order_index, paper);
+        self.activate_due();
+        Ok(self.orders[&client_order_index].clone())
+    }
+
+    pub(super) fn cancel(&mut self, client_order_index: i64) -> Result<()> {
+        let order = self.orders.get_mut(&client_order_index).ok_or_else(|| {
+            LighterError::OrderValidation(format!("unknown client order {client_order_index}"))
+        })?;
+        if order.state.is_terminal() {
+            return Err(LighterError::OrderValidation(format!(
+                "client order {client_order_index} is no longer open"
+            )));
+        }
+        order.state = OrderState::Cancelled;
+        let snapshot = order.tracked();
+        self.updates.push(snapshot);
+        Ok(())
+    }
+
+    pub(super) fn cancel_all(&mut self) {
+        let open: Vec<i64> = self
+            .orders
+            .values()
+            .filter(|order| !order.state.is_terminal())
+            .map(|order| order.client_order_index)
+            .collect();
+        for client_order_index in open {
+            // Only open orders were collected, so cancelling cannot fail.
+            let _ = self.cancel(client_order_index);
+        }
+    }
+
+    pub(super) fn on_book(&mut self, market_id: i32, book: &OrderBook) {
+        self.advance_clock(book.timestamp);
+        self.books.insert(market_id, Book::from_order_book(book));
+        self.activate_due();
+
+        // Resting orders the new book trades through fill as makers.
+        let resting = self.resting(market_id);
+        for client_order_index in resting {
+            let Some(order) = self.orders.get(&client_order_index) else {
+                continue;
+            };
+            let (is_buy, price) = (order.is_buy, order.price);
+            let Some(book) = self.books.get(&market_id) else {
+                return;
+            };
+            let available: Decimal = book
+                .available(is_buy, price, self.config.fill_ratio)
+                .iter()
+                .map(|(_, size)| *size)
+                .sum();
+            if available > Decimal::ZERO {
+                let filled = self.fill(client_order_index, price, available, true);
+                self.consume_through(market_id, is_buy, price, filled);
+            }
+        }
+    }
+
+    /// Fill resting orders on the side the trade's taker hit: a buying taker
+    /// lifts asks, a selling taker hits bids.
+    pub(super) fn on_trade(&mut self, trade: &MarketTrade) {
+        self.advance_clock(trade.timestamp);
+        self.activate_due();
+
+        let hit_bids = trade.taker_side == Side::Sell;
+        let mut left = trade.size * self.config.fill_ratio;
+        for client_order_index in self.resting(trade.market_id) {
+            if left <= Decimal::ZERO {
+                break;
+            }
+            let Some(order) = self.orders.get(&client_order_index) else {
+                continue;
+            };
+            if order.is_buy != hit_bids {
+                continue;
+            }
+            let through = if order.is_buy {
+                trade.price <= order.price
+            } else {
+                trade.price >= order.price
+            };
+            if through {
+                let price = order.price;
+                left -= self.fill(client_order_index, price, left, true);
+            }
+        }
+    }
+
+    fn advance_clock(&mut self, at: DateTime<Utc>) {
+        if self.clock.is_none_or(|clock| at > clock) {
+            self.clock = Some(at);
+        }
+    }
+
+    /// Active resting orders of a market, best priced and oldest first.
+    fn resting(&self, market_id: i32) -> Vec<i64> {
+        let mut resting: Vec<&PaperOrder> = self
+            .orders
+            .values()
+            .filter(|order| {
+                order.market_id == market_id
+                    && matches!(
+                        order.state,
+                        OrderState::Acknowledged | OrderState::PartiallyFilled
+                    )
+            })
+            .collect();
+        resting.sort_by_key(|order| {
+            let priority = if order.is_buy {
+                -order.price
+            } else {
+                order.price
+            };
+            (order.is_buy, priority, order.order_index)
+        });
+        resting
+            .into_iter()
+            .map(|order| order.client_order_index)
+            .collect()
+    }
+
+    fn activate_due(&mut self) {
+        let now = self.now();
+        let due: Vec<i64> = self
+            .orders
+            .values()
+            .filter(|order| order.state == OrderState::Submitted && order.active_at <= now)
+            .map(|order| order.client_order_index)
+            .collect();
+        for client_order_index in due {
+            self.activate(client_order_index);
+        }
+    }
+
+    /// Order reaches the simulated matching engine: enforce reduce-only and
+    /// post-only, take liquidity, then rest or cancel the remainder.
+    fn activate(&mut self, client_order_index: i64) {
+        let Some(order) = self.orders.get(&client_order_index).cloned() else {
+            return;
+        };
+
+        let mut remaining = order.base_amount;
+        if order.reduce_only {
+            let position = self
+                .positions
+                .get(&order.market_id)
+                .map(|position| position.size)
+                .unwrap_or_default();
+            let reducible = if order.is_buy {
+                (-position).max(Decimal::ZERO)
+            } else {
+                position.max(Decimal::ZERO)
+            };
+            remaining = remaining.min(reducible);
+            if let Some(order) = self.orders.get_mut(&client_order_index) {
+                order.base_amount = remaining;
+            }
+        }
+
+        let crossing = self
+            .books
+            .get(&order.market_id)
+            .map(|book| book.available(order.is_buy, order.price, self.config.fill_ratio))
+            .unwrap_or_default();
+        let rejected =
+            remaining.is_zero() || (order.time_in_force == TimeInForce::Po && !crossing.is_empty());
+        if rejected {
+            self.set_state(client_order_index, OrderState::Rejected);
+            return;
+        }
+
+        self.set_state(client_order_index, OrderState::Acknowledged);
+        for (price, size) in crossing {
+            if remaining.is_zero() {
+                break;
+            }
+            let filled = self.fill(client_order_index, price, size.min(remaining), false);
+            if let Some(book) = self.books.get_mut(&order.market_id) {
+                book.consume(order.is_buy, price, filled);
+            }
+            remaining -= filled;
+        }
+
+        let unfilled = self
+            .orders
+            .get(&client_order_index)
+            .is_some_and(|order| !order.state.is_terminal());
+        if unfilled && order.time_in_force == TimeInForce::Ioc {
+            self.set_state(client_order_index, OrderState::Cancelled);
+        }
+    }
+
+    /// Fill up to `size` of an order at `price` and book the position,
+    /// fee and collateral change. Returns the size filled.
+    fn fill(
+        &mut self,
+        client_order_index: i64,
+        price: Decimal,
+        size: Decimal,
+        is_maker: bool,
+    ) -> Decimal {
+        let now = self.now();
+        let Some(order) = self.orders.get_mut(&client_order_index) else {
+            return Decimal::ZERO;
+        };
+        let size = size.min(order.base_amount - order.filled_base);
+        if size <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+        order.filled_base += size;
+        order.filled_quote += size * price;
+        order.state = if order.filled_base == order.base_amount {
+            OrderState::Filled
+        } else {
+            OrderState::PartiallyFilled
+        };
+        let (market_id, symbol, is_buy) = (order.market_id, order.symbol.clone(), order.is_buy);
+        self.updates.push(order.tracked());
+
+        let rate = if is_maker {
+            self.config.maker_fee
+        } else {
+            self.config.taker_fee
+        };
+        let fee = size * price * rate;
+        let position = self
+            .positions
+            .entry(market_id)
+            .or_insert_with(|| PaperPosition {
+                market_id,
+                symbol: symbol.clone(),
+                ..Default::default()
+            });
+        let realized = position.apply_fill(is_buy, size, price);
+        position.realized_pnl += realized;
+        self.collateral += realized - fee;
+        self.fees_paid += fee;
+        self.fills.push(PaperFill {
+            client_order_index,
+            symbol,
+            is_buy,
+            price,
+            size,
+            fee,
+            is_maker,
+            timestamp: now,
+        });
+        size
+    }
+
+    /// Remove liquidity a resting order took so later orders in the same
+    /// book do not fill against it again.
+    fn consume_through(&mut self, market_id: i32, is_buy: bool, limit: Decimal, mut size: Decimal) {
+        let fill_ratio = self.config.fill_ratio;
+        let Some(book) = self.books.get_mut(&market_id) else {
+            return;
+        };
+        for (price, available) in book.available(is_buy, limit, fill_ratio) {
+            if size.is_zero() {
+                break;
+            }
+            let take = available.min(size);
+            book.consume(is_buy, price, take);
+            size -= take;
+        }
+    }
+
+    fn set_state(&mut self, client_order_index: i64, state: OrderState) {
+        if let Some(order) = self.orders.get_mut(&client_order_index) {
+            order.state = state;
+            self.updates.push(order.tracked());
+        }
+    }
+}
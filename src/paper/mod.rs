@@ -0,0 +1,609 @@
+use crate::api::transaction_api::TxResponse;
+use crate::error::Result;
+use crate::metadata::MarketInfo;
+use crate::models::order::TimeInForce;
+use crate::signers::SignedCreateOrder;
+use crate::strategy::{BookUpdate, MarketTrade, OrderEntry, StrategyEvent, StrategyOrder};
+use crate::trading::{ClientOrderId, OrderState, SubmittedOrder, TrackedOrder};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::*;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::{Mutex, MutexGuard};
+
+mod engine;
+
+use engine::Engine;
+
+/// Simulation parameters of a [`PaperExchange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaperConfig {
+    /// Starting collateral, in quote units.
+    pub initial_collateral: Decimal,
+    /// Delay between submission and the order reaching the simulated book,
+    /// measured on the market data clock.
+    pub latency: Duration,
+    /// Fee rate charged on resting fills, as a fraction of notional.
+    pub maker_fee: Decimal,
+    /// Fee rate charged on aggressive fills, as a fraction of notional.
+    pub taker_fee: Decimal,
+    /// Share of each book level and each printed trade simulated orders may
+    /// fill against. Values below one model queue competition and produce
+    /// partial fills.
+    pub fill_ratio: Decimal,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            initial_collateral: Decimal::ZERO,
+            latency: Duration::zero(),
+            maker_fee: Decimal::ZERO,
+            taker_fee: Decimal::ZERO,
+            fill_ratio: Decimal::ONE,
+        }
+    }
+}
+
+impl PaperConfig {
+    pub fn new(initial_collateral: Decimal) -> Self {
+        Self {
+            initial_collateral,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_fees(mut self, maker_fee: Decimal, taker_fee: Decimal) -> Self {
+        self.maker_fee = maker_fee;
+        self.taker_fee = taker_fee;
+        self
+    }
+
+    pub fn with_fill_ratio(mut self, fill_ratio: Decimal) -> Self {
+        self.fill_ratio = fill_ratio.clamp(Decimal::ZERO, Decimal::ONE);
+        self
+    }
+}
+
+/// Order held by a [`PaperExchange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaperOrder {
+    pub client_order_index: i64,
+    pub order_index: i64,
+    pub market_id: i32,
+    pub symbol: String,
+    pub is_buy: bool,
+    /// Limit price; the worst acceptable price for market orders.
+    pub price: Decimal,
+    pub base_amount: Decimal,
+    pub filled_base: Decimal,
+    pub filled_quote: Decimal,
+    pub time_in_force: TimeInForce,
+    pub is_market: bool,
+    pub reduce_only: bool,
+    pub state: OrderState,
+    /// When the order reaches the simulated book.
+    pub active_at: DateTime<Utc>,
+}
+
+impl PaperOrder {
+    pub fn remaining_base_amount(&self) -> Decimal {
+        self.base_amount - self.filled_base
+    }
+
+    fn tracked(&self) -> TrackedOrder {
+        TrackedOrder {
+            client_order_index: self.client_order_index,
+            order_index: Some(self.order_index),
+            market_index: self.market_id,
+            state: self.state,
+            is_ask: !self.is_buy,
+            filled_base: self.filled_base,
+            filled_quote: self.filled_quote,
+            tx_hash: None,
+        }
+    }
+}
+
+/// Simulated position in one market.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaperPosition {
+    pub market_id: i32,
+    pub symbol: String,
+    /// Positive when long, negative when short.
+    pub size: Decimal,
+    /// Average price of the open position.
+    pub entry_price: Decimal,
+    pub realized_pnl: Decimal,
+}
+
+impl PaperPosition {
+    pub fn unrealized_pnl(&self, mark_price: Decimal) -> Decimal {
+        (mark_price - self.entry_price) * self.size
+    }
+
+    /// Add a fill and return the PnL it realized.
+    fn apply_fill(&mut self, is_buy: bool, size: Decimal, price: Decimal) -> Decimal {
+        let signed = if is_buy { size } else { -size };
+        if self.size.is_zero() || self.size.is_sign_positive() == is_buy {
+            let total = self.size.abs() + size;
+            self.entry_price = (self.entry_price * self.size.abs() + price * size) / total;
+            self.size += signed;
+            return Decimal::ZERO;
+        }
+
+        let closing = size.min(self.size.abs());
+        let direction = if self.size.is_sign_positive() {
+            Decimal::ONE
+        } else {
+            -Decimal::ONE
+        };
+        let realized = (price - self.entry_price) * closing * direction;
+        self.size += signed;
+        if self.size.is_zero() {
+            self.entry_price = Decimal::ZERO;
+        } else if size > closing {
+            // Flipped through flat; the remainder opened at this price.
+            self.entry_price = price;
+        }
+        realized
+    }
+}
+
+/// A simulated execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PaperFill {
+    pub client_order_index: i64,
+    pub symbol: String,
+    pub is_buy: bool,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee: Decimal,
+    pub is_maker: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Simulated venue that matches orders against live or recorded book and
+/// trade data instead of sending them to Lighter.
+///
+/// It implements [`OrderEntry`], so a [`crate::strategy::StrategyRuntime`]
+/// can run a strategy against it unchanged. Aggressive orders take the
+/// displayed book at the time they become active; resting orders fill at
+/// their own price once the book or a trade reaches it. No margin checks
+/// are made.
+#[derive(Debug)]
+pub struct PaperExchange {
+    engine: Mutex<Engine>,
+}
+
+impl PaperExchange {
+    pub fn new(config: PaperConfig) -> Self {
+        Self {
+            engine: Mutex::new(Engine::new(config)),
+        }
+    }
+
+    pub fn with_market(self, info: MarketInfo) -> Self {
+        self.engine().add_market(info);
+        self
+    }
+
+    pub fn add_market(&self, info: MarketInfo) {
+        self.engine().add_market(info);
+    }
+
+    /// Feed a book snapshot; resting orders it trades through fill.
+    pub fn on_book(&self, update: &BookUpdate) {
+        self.engine().on_book(update.market_id, &update.book);
+    }
+
+    /// Feed a public trade; resting orders on the side its taker hit fill
+    /// when at or through its price.
+    pub fn on_trade(&self, trade: &MarketTrade) {
+        self.engine().on_trade(trade);
+    }
+
+    /// Submit an order, assigning a client order index when none is set.
+    pub fn submit_order(&self, order: &StrategyOrder) -> Result<SubmittedOrder> {
+        let mut engine = self.engine();
+        let client_order_index = match &order.client_id {
+            Some(client_id) => client_id.client_order_index()?,
+            None => engine.assign_client_index(),
+        };
+        let paper = engine.submit(order, client_order_index)?;
+        let info = engine.market(&paper.symbol)?.clone();
+        Ok(submitted(&info, &paper))
+    }
+
+    pub fn create_limit_order(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+        time_in_force: TimeInForce,
+    ) -> Result<SubmittedOrder> {
+        self.submit_order(
+            &StrategyOrder::limit(symbol, is_buy, *base_amount, *limit_price)
+                .with_time_in_force(time_in_force)
+                .with_reduce_only(reduce_only),
+        )
+    }
+
+    pub fn create_market_order_with_slippage(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        max_slippage_bps: u32,
+        reduce_only: bool,
+    ) -> Result<SubmittedOrder> {
+        self.submit_order(
+            &StrategyOrder::market(symbol, is_buy, *base_amount, max_slippage_bps)
+                .with_reduce_only(reduce_only),
+        )
+    }
+
+    pub fn cancel_order_by_client_id(&self, client_id: impl Into<ClientOrderId>) -> Result<()> {
+        let client_order_index = client_id.into().client_order_index()?;
+        self.engine().cancel(client_order_index)
+    }
+
+    pub fn cancel_all_orders(&self) {
+        self.engine().cancel_all();
+    }
+
+    pub fn order(&self, client_order_index: i64) -> Option<PaperOrder> {
+        self.engine().order(client_order_index).cloned()
+    }
+
+    /// Orders that are pending or resting.
+    pub fn open_orders(&self) -> Vec<PaperOrder> {
+        self.engine()
+            .orders()
+            .filter(|order| !order.state.is_terminal())
+            .cloned()
+            .collect()
+    }
+
+    pub fn positions(&self) -> Vec<PaperPosition> {
+        self.engine().positions()
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<PaperPosition> {
+        self.positions()
+            .into_iter()
+            .find(|position| position.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Cash balance: initial collateral plus realized PnL minus fees.
+    pub fn collateral(&self) -> Decimal {
+        self.engine().collateral()
+    }
+
+    /// Collateral plus open positions marked at the book mid.
+    pub fn equity(&self) -> Decimal {
+        self.engine().equity()
+    }
+
+    pub fn fees_paid(&self) -> Decimal {
+        self.engine().fees_paid()
+    }
+
+    pub fn fills(&self) -> Vec<PaperFill> {
+        self.engine().fills().to_vec()
+    }
+
+    /// Current simulated time: the latest market data timestamp.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.engine().now()
+    }
+
+    fn engine(&self) -> MutexGuard<'_, Engine> {
+        // The engine is only mutated through complete operations, so a
+        // poisoned lock still holds consistent state.
+        self.engine
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl OrderEntry for PaperExchange {
+    async fn market_info(&self, symbol: &str) -> Result<MarketInfo> {
+        self.engine().market(symbol).cloned()
+    }
+
+    async fn submit(&self, order: &StrategyOrder) -> Result<SubmittedOrder> {
+        self.submit_order(order)
+    }
+
+    async fn cancel(&self, _symbol: &str, client_order_index: i64) -> Result<()> {
+        self.engine().cancel(client_order_index)
+    }
+
+    async fn cancel_all(&self) -> Result<()> {
+        self.cancel_all_orders();
+        Ok(())
+    }
+
+    fn on_market_event(&self, event: &StrategyEvent) {
+        match event {
+            StrategyEvent::Book(update) => self.on_book(update),
+            StrategyEvent::Trade(trade) => self.on_trade(trade),
+            StrategyEvent::Order(_) | StrategyEvent::Timer(_) => {}
+        }
+    }
+
+    fn take_order_updates(&self) -> Vec<TrackedOrder> {
+        self.engine().take_updates()
+    }
+}
+
+/// Describe a paper order the way the signer would have.
+fn submitted(info: &MarketInfo, order: &PaperOrder) -> SubmittedOrder {
+    let scale = |value: Decimal, decimals: Option<u32>| {
+        (value * Decimal::from(10i64.pow(decimals.unwrap_or(0))))
+            .to_i64()
+            .unwrap_or_default()
+    };
+    SubmittedOrder {
+        order: SignedCreateOrder {
+            account_index: 0,
+            api_key_index: 0,
+            market_index: order.market_id,
+            client_order_index: order.client_order_index,
+            base_amount: scale(order.base_amount, info.supported_size_decimals),
+            price: scale(order.price, info.supported_price_decimals),
+            is_ask: !order.is_buy,
+            order_type: i32::from(order.is_market),
+            time_in_force: order.time_in_force.lighter_code(),
+            reduce_only: order.reduce_only,
+            trigger_price: 0,
+            order_expiry: -1,
+            expired_at: 0,
+            nonce: order.order_index,
+            signature: None,
+        },
+        response: TxResponse {
+            code: 200,
+            tx_hash: Some(format!("paper-{}", order.order_index)),
+            message: None,
+            predicted_execution_time_ms: Some(order.active_at.timestamp_millis()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::{OrderBook, PriceLevel, Side};
+
+    fn eth() -> MarketInfo {
+        MarketInfo {
+            market_id: 0,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(2),
+            supported_price_decimals: Some(2),
+            ..Default::default()
+        }
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    fn book(seconds: i64, bids: &[(i64, i64)], asks: &[(i64, i64)]) -> BookUpdate {
+        let levels = |levels: &[(i64, i64)]| {
+            levels
+                .iter()
+                .map(|(price, size)| PriceLevel {
+                    price: price.to_string(),
+                    quantity: size.to_string(),
+                })
+                .collect()
+        };
+        BookUpdate {
+            symbol: "ETH".to_string(),
+            market_id: 0,
+            book: OrderBook {
+                bids: levels(bids),
+                asks: levels(asks),
+                timestamp: at(seconds),
+            },
+        }
+    }
+
+    fn exchange(config: PaperConfig) -> PaperExchange {
+        let exchange = PaperExchange::new(config).with_market(eth());
+        exchange.on_book(&book(0, &[(99, 2), (98, 5)], &[(101, 1), (102, 3)]));
+        exchange
+    }
+
+    #[test]
+    fn market_orders_walk_the_book_and_pay_taker_fees() {
+        let exchange = exchange(
+            PaperConfig::new(Decimal::from(10_000))
+                .with_fees(Decimal::new(1, 4), Decimal::new(5, 4)),
+        );
+        let order = exchange
+            .create_market_order_with_slippage("ETH", true, &Decimal::from(2), 200, false)
+            .unwrap();
+        let paper = exchange.order(order.client_order_index()).unwrap();
+        assert_eq!(paper.state, OrderState::Filled);
+        assert_eq!(paper.filled_quote, Decimal::from(101 + 102));
+
+        let position = exchange.position("ETH").unwrap();
+        assert_eq!(position.size, Decimal::from(2));
+        assert_eq!(position.entry_price, Decimal::new(1015, 1));
+        assert_eq!(exchange.fees_paid(), Decimal::new(1015, 4));
+
+        // The liquidity just taken is gone for the rest of this snapshot.
+        let again = exchange
+            .create_market_order_with_slippage("ETH", true, &Decimal::from(3), 200, false)
+            .unwrap();
+        let paper = exchange.order(again.client_order_index()).unwrap();
+        assert_eq!(paper.state, OrderState::Cancelled);
+        assert_eq!(paper.filled_base, Decimal::from(2));
+    }
+
+    #[test]
+    fn resting_orders_fill_partially_after_latency() {
+        let exchange = exchange(
+            PaperConfig::new(Decimal::from(10_000))
+                .with_latency(Duration::seconds(1))
+                .with_fill_ratio(Decimal::new(5, 1)),
+        );
+        let order = exchange
+            .create_limit_order(
+                "ETH",
+                false,
+                &Decimal::from(2),
+                &Decimal::from(100),
+                false,
+                TimeInForce::Gtc,
+            )
+            .unwrap();
+        let index = order.client_order_index();
+        assert_eq!(exchange.order(index).unwrap().state, OrderState::Submitted);
+
+        exchange.on_book(&book(2, &[(99, 2)], &[(101, 1)]));
+        assert_eq!(
+            exchange.order(index).unwrap().state,
+            OrderState::Acknowledged
+        );
+
+        // A selling taker hits bids and leaves the resting ask alone.
+        exchange.on_trade(&MarketTrade {
+            symbol: "ETH".to_string(),
+            market_id: 0,
+            trade_id: 0,
+            price: Decimal::from(100),
+            size: Decimal::from(2),
+            taker_side: Side::Sell,
+            timestamp: at(3),
+        });
+        assert_eq!(
+            exchange.order(index).unwrap().state,
+            OrderState::Acknowledged
+        );
+
+        exchange.on_trade(&MarketTrade {
+            symbol: "ETH".to_string(),
+            market_id: 0,
+            trade_id: 1,
+            price: Decimal::from(100),
+            size: Decimal::from(2),
+            taker_side: Side::Buy,
+            timestamp: at(3),
+        });
+        let paper = exchange.order(index).unwrap();
+        assert_eq!(paper.state, OrderState::PartiallyFilled);
+        assert_eq!(paper.filled_base, Decimal::ONE);
+        assert_eq!(exchange.position("ETH").unwrap().size, -Decimal::ONE);
+
+        let updates = exchange.take_order_updates();
+        assert_eq!(
+            updates.iter().map(|order| order.state).collect::<Vec<_>>(),
+            [OrderState::Acknowledged, OrderState::PartiallyFilled]
+        );
+    }
+
+    #[test]
+    fn enforces_post_only_and_reduce_only() {
+        let exchange = exchange(PaperConfig::new(Decimal::from(1_000)));
+        let crossing = exchange
+            .create_limit_order(
+                "ETH",
+                true,
+                &Decimal::ONE,
+                &Decimal::from(101),
+                false,
+                TimeInForce::Po,
+            )
+            .unwrap();
+        assert_eq!(
+            exchange.order(crossing.client_order_index()).unwrap().state,
+            OrderState::Rejected
+        );
+
+        let flat_close = exchange
+            .create_market_order_with_slippage("ETH", false, &Decimal::ONE, 100, true)
+            .unwrap();
+        assert_eq!(
+            exchange
+                .order(flat_close.client_order_index())
+                .unwrap()
+                .state,
+            OrderState::Rejected
+        );
+    }
+
+    #[derive(Default)]
+    struct BuyOnce {
+        fills: Vec<OrderState>,
+    }
+
+    impl crate::strategy::Strategy for BuyOnce {
+        fn on_book(&mut self, ctx: &mut crate::strategy::StrategyContext, book: &BookUpdate) {
+            if self.fills.is_empty() {
+                ctx.submit(StrategyOrder::market(&book.symbol, true, Decimal::ONE, 100))
+                    .unwrap();
+            }
+        }
+
+        fn on_order_update(
+            &mut self,
+            _ctx: &mut crate::strategy::StrategyContext,
+            transition: &crate::trading::OrderTransition,
+        ) {
+            self.fills.push(transition.to);
+        }
+    }
+
+    #[tokio::test]
+    async fn drives_a_strategy_runtime() {
+        let exchange = std::sync::Arc::new(
+            PaperExchange::new(PaperConfig::new(Decimal::from(1_000))).with_market(eth()),
+        );
+        let mut runtime =
+            crate::strategy::StrategyRuntime::new(BuyOnce::default(), exchange.clone())
+                .with_market("ETH");
+        runtime.start().await.unwrap();
+        runtime
+            .handle(StrategyEvent::Book(book(0, &[(99, 2)], &[(101, 1)])))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            runtime.strategy().fills,
+            [OrderState::Acknowledged, OrderState::Filled]
+        );
+        assert_eq!(runtime.tracker().open_orders().count(), 0);
+        assert_eq!(exchange.position("ETH").unwrap().size, Decimal::ONE);
+    }
+
+    #[test]
+    fn positions_realize_pnl_when_reduced() {
+        let mut position = PaperPosition::default();
+        assert_eq!(
+            position.apply_fill(true, Decimal::from(2), Decimal::from(100)),
+            Decimal::ZERO
+        );
+        assert_eq!(
+            position.apply_fill(false, Decimal::from(3), Decimal::from(110)),
+            Decimal::from(20)
+        );
+        assert_eq!(position.size, -Decimal::ONE);
+        assert_eq!(position.entry_price, Decimal::from(110));
+        assert_eq!(
+            position.unrealized_pnl(Decimal::from(105)),
+            Decimal::from(5)
+        );
+    }
+}
@@ -1,11 +1,11 @@
 use crate::error::{LighterError, Result};
 use crate::models::common::OrderType;
-use crate::models::order::TimeInForce;
+use crate::models::order::{CancelAllMode, OrderGrouping, TimeInForce};
 use libloading::{Library, Symbol};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::ffi::{CStr, CString};
-use std::os::raw::{c_char, c_int, c_longlong};
+use std::os::raw::{c_char, c_int, c_longlong, c_uchar, c_uint};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -16,6 +16,58 @@ pub struct StrOrErr {
     pub err: *mut c_char,
 }
 
+/// Order layout expected by `SignCreateGroupedOrders`.
+#[repr(C)]
+struct CreateOrderTxReq {
+    market_index: c_uchar,
+    client_order_index: c_longlong,
+    base_amount: c_longlong,
+    price: c_uint,
+    is_ask: c_uchar,
+    order_type: c_uchar,
+    time_in_force: c_uchar,
+    reduce_only: c_uchar,
+    trigger_price: c_uint,
+    order_expiry: c_longlong,
+}
+
+/// One order of a grouped-order transaction, in scaled exchange units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupedOrderLeg {
+    pub market_index: i32,
+    pub client_order_index: i64,
+    /// `0` sizes a triggered child from its parent's fill.
+    pub base_amount: i64,
+    pub price: i32,
+    pub is_ask: bool,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub reduce_only: bool,
+    pub trigger_price: i32,
+    pub order_expiry: i64,
+}
+
+impl GroupedOrderLeg {
+    fn to_ffi(self) -> Result<CreateOrderTxReq> {
+        let narrow =
+            |field: &str| LighterError::Signing(format!("grouped order {field} out of range"));
+        Ok(CreateOrderTxReq {
+            market_index: c_uchar::try_from(self.market_index)
+                .map_err(|_| narrow("market index"))?,
+            client_order_index: self.client_order_index as c_longlong,
+            base_amount: self.base_amount as c_longlong,
+            price: c_uint::try_from(self.price).map_err(|_| narrow("price"))?,
+            is_ask: u8::from(self.is_ask),
+            order_type: self.order_type.lighter_code() as c_uchar,
+            time_in_force: self.time_in_force.lighter_code() as c_uchar,
+            reduce_only: u8::from(self.reduce_only),
+            trigger_price: c_uint::try_from(self.trigger_price)
+                .map_err(|_| narrow("trigger price"))?,
+            order_expiry: self.order_expiry as c_longlong,
+        })
+    }
+}
+
 const DEFAULT_AUTH_TOKEN_TTL_SECS: i64 = 10 * 60;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,6 +81,67 @@ pub struct SignedTransaction {
     pub transaction: serde_json::Value,
 }
 
+/// Signed create-order payload as produced by `SignCreateOrder`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SignedCreateOrder {
+    pub account_index: i64,
+    pub api_key_index: i32,
+    pub market_index: i32,
+    pub client_order_index: i64,
+    pub base_amount: i64,
+    pub price: i64,
+    #[serde(with = "int_bool")]
+    pub is_ask: bool,
+    #[serde(rename = "Type")]
+    pub order_type: i32,
+    pub time_in_force: i32,
+    #[serde(with = "int_bool")]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub trigger_price: i64,
+    #[serde(default)]
+    pub order_expiry: i64,
+    #[serde(default)]
+    pub expired_at: i64,
+    pub nonce: i64,
+    #[serde(rename = "Sig", default)]
+    pub signature: Option<String>,
+}
+
+impl SignedCreateOrder {
+    pub fn order_type(&self) -> Option<OrderType> {
+        OrderType::from_lighter_code(self.order_type)
+    }
+
+    pub fn time_in_force(&self) -> Option<TimeInForce> {
+        TimeInForce::from_lighter_code(self.time_in_force)
+    }
+}
+
+/// The signer encodes flags as `0`/`1`; accept either form when reading.
+mod int_bool {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Int(u8),
+    }
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        Ok(match Flag::deserialize(deserializer)? {
+            Flag::Bool(value) => value,
+            Flag::Int(value) => value != 0,
+        })
+    }
+}
+
 pub struct FFISigner {
     library: Arc<Library>,
     url: String,
@@ -66,6 +179,20 @@ impl FFISigner {
         Ok(signer)
     }
 
+    /// Signer bound to the test binary instead of the signer library, for
+    /// tests of everything up to signing. Every signing call fails.
+    #[cfg(all(test, unix))]
+    pub(crate) fn unlinked(api_key_index: i32, account_index: i32) -> Self {
+        Self {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            url: String::new(),
+            private_key: String::new(),
+            chain_id: 300,
+            api_key_index: api_key_index as c_int,
+            account_index: account_index as c_longlong,
+        }
+    }
+
     fn get_library_path() -> Result<PathBuf> {
         let lib_name = if cfg!(target_os = "macos") && cfg!(target_arch = "aarch64") {
             "signer-arm64.dylib"
@@ -209,9 +336,9 @@ impl FFISigner {
                 .get(b"SignCreateOrder")
                 .map_err(|e| LighterError::Signing(e.to_string()))?;
 
-            let order_type_int = order_type as c_int;
+            let order_type_int = order_type.lighter_code() as c_int;
 
-            let tif_int = time_in_force as c_int;
+            let tif_int = time_in_force.lighter_code() as c_int;
 
             let result = sign_fn(
                 market_index as c_int,
@@ -260,12 +387,45 @@ impl FFISigner {
         }
     }
 
-    pub fn sign_cancel_all_orders(
+    pub fn sign_modify_order(
         &self,
         market_index: i32,
-        client_cancel_index: i64,
+        order_index: i64,
+        base_amount: i64,
+        price: i64,
+        trigger_price: i64,
         nonce: i64,
     ) -> Result<String> {
+        unsafe {
+            #[allow(clippy::type_complexity)]
+            let sign_fn: Symbol<
+                unsafe extern "C" fn(
+                    c_int,
+                    c_longlong,
+                    c_longlong,
+                    c_longlong,
+                    c_longlong,
+                    c_longlong,
+                ) -> StrOrErr,
+            > = self
+                .library
+                .get(b"SignModifyOrder")
+                .map_err(|e| LighterError::Signing(e.to_string()))?;
+
+            let result = sign_fn(
+                market_index as c_int,
+                order_index as c_longlong,
+                base_amount as c_longlong,
+                price as c_longlong,
+                trigger_price as c_longlong,
+                nonce as c_longlong,
+            );
+
+            self.parse_result(result)
+        }
+    }
+
+    pub fn sign_cancel_all_orders(&self, mode: CancelAllMode, nonce: i64) -> Result<String> {
         unsafe {
             let sign_fn: Symbol<unsafe extern "C" fn(c_int, c_longlong, c_longlong) -> StrOrErr> =
                 self.library
@@ -273,8 +433,8 @@ impl FFISigner {
                     .map_err(|e| LighterError::Signing(e.to_string()))?;
 
             let result = sign_fn(
-                market_index as c_int,
-                client_cancel_index as c_longlong,
+                mode.time_in_force() as c_int,
+                mode.time_ms() as c_longlong,
                 nonce as c_longlong,
             );
 
@@ -282,21 +442,70 @@ impl FFISigner {
         }
     }
 
-    pub fn sign_transfer(&self, receiver: &str, amount: i64, nonce: i64) -> Result<String> {
+    /// Whether the loaded signer library can sign grouped orders. Older
+    /// builds predate the grouped-order transaction.
+    pub fn supports_grouped_orders(&self) -> bool {
         unsafe {
+            self.library
+                .get::<unsafe extern "C" fn()>(b"SignCreateGroupedOrders")
+                .is_ok()
+        }
+    }
+
+    pub fn sign_create_grouped_orders(
+        &self,
+        grouping: OrderGrouping,
+        legs: &[GroupedOrderLeg],
+        nonce: i64,
+    ) -> Result<String> {
+        let orders = legs
+            .iter()
+            .map(|leg| leg.to_ffi())
+            .collect::<Result<Vec<_>>>()?;
+        unsafe {
+            #[allow(clippy::type_complexity)]
             let sign_fn: Symbol<
-                unsafe extern "C" fn(*const c_char, c_longlong, c_longlong) -> StrOrErr,
+                unsafe extern "C" fn(
+                    c_uchar,
+                    *const CreateOrderTxReq,
+                    c_int,
+                    c_longlong,
+                ) -> StrOrErr,
             > = self
                 .library
-                .get(b"SignTransfer")
+                .get(b"SignCreateGroupedOrders")
                 .map_err(|e| LighterError::Signing(e.to_string()))?;
 
-            let c_receiver = CString::new(receiver)
-                .map_err(|_| LighterError::Signing("Invalid receiver".to_string()))?;
+            let result = sign_fn(
+                grouping.lighter_code() as c_uchar,
+                orders.as_ptr(),
+                orders.len() as c_int,
+                nonce as c_longlong,
+            );
+
+            self.parse_result(result)
+        }
+    }
+
+    /// Sign a transfer of `usdc_amount` (scaled by 1e6) to another Lighter
+    /// account, addressed by its account index.
+    pub fn sign_transfer(
+        &self,
+        to_account_index: i64,
+        usdc_amount: i64,
+        nonce: i64,
+    ) -> Result<String> {
+        unsafe {
+            let sign_fn: Symbol<
+                unsafe extern "C" fn(c_longlong, c_longlong, c_longlong) -> StrOrErr,
+            > = self
+                .library
+                .get(b"SignTransfer")
+                .map_err(|e| LighterError::Signing(e.to_string()))?;
 
             let result = sign_fn(
-                c_receiver.as_ptr(),
-                amount as c_longlong,
+                to_account_index as c_longlong,
+                usdc_amount as c_longlong,
                 nonce as c_longlong,
             );
 
@@ -304,6 +513,21 @@ impl FFISigner {
         }
     }
 
+    /// Sign the creation of a sub-account owned by the signing account's L1
+    /// address.
+    pub fn sign_create_sub_account(&self, nonce: i64) -> Result<String> {
+        unsafe {
+            let sign_fn: Symbol<unsafe extern "C" fn(c_longlong) -> StrOrErr> = self
+                .library
+                .get(b"SignCreateSubAccount")
+                .map_err(|e| LighterError::Signing(e.to_string()))?;
+
+            let result = sign_fn(nonce as c_longlong);
+
+            self.parse_result(result)
+        }
+    }
+
     pub fn sign_withdraw(&self, receiver: &str, amount: i64, nonce: i64) -> Result<String> {
         unsafe {
             let sign_fn: Symbol<
@@ -326,6 +550,61 @@ impl FFISigner {
         }
     }
 
+    /// Sign an update-leverage tx. `initial_margin_fraction` is in basis
+    /// points (`1000` is 10x); `margin_mode` is a
+    /// [`crate::models::account::MarginType`] code.
+    pub fn sign_update_leverage(
+        &self,
+        market_index: i32,
+        initial_margin_fraction: i32,
+        margin_mode: i32,
+        nonce: i64,
+    ) -> Result<String> {
+        unsafe {
+            let sign_fn: Symbol<unsafe extern "C" fn(c_int, c_int, c_int, c_longlong) -> StrOrErr> =
+                self.library
+                    .get(b"SignUpdateLeverage")
+                    .map_err(|e| LighterError::Signing(e.to_string()))?;
+
+            let result = sign_fn(
+                market_index as c_int,
+                initial_margin_fraction as c_int,
+                margin_mode as c_int,
+                nonce as c_longlong,
+            );
+
+            self.parse_result(result)
+        }
+    }
+
+    /// Sign an update-margin tx moving `usdc_amount` (scaled by 1e6) into
+    /// (`direction` 1) or out of (`direction` 0) an isolated position.
+    pub fn sign_update_margin(
+        &self,
+        market_index: i32,
+        usdc_amount: i64,
+        direction: i32,
+        nonce: i64,
+    ) -> Result<String> {
+        unsafe {
+            let sign_fn: Symbol<
+                unsafe extern "C" fn(c_int, c_longlong, c_int, c_longlong) -> StrOrErr,
+            > = self
+                .library
+                .get(b"SignUpdateMargin")
+                .map_err(|e| LighterError::Signing(e.to_string()))?;
+
+            let result = sign_fn(
+                market_index as c_int,
+                usdc_amount as c_longlong,
+                direction as c_int,
+                nonce as c_longlong,
+            );
+
+            self.parse_result(result)
+        }
+    }
+
     pub fn create_auth_token_with_expiry(&self, deadline: Option<i64>) -> Result<String> {
         unsafe {
             let create_auth_fn: Symbol<unsafe extern "C" fn(c_longlong) -> StrOrErr> = self
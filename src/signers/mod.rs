@@ -43,6 +43,7 @@ struct AccountTierSignatureData {
     nonce: u64,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn order_signature_message(
     symbol: &str,
     side: Side,
@@ -73,6 +74,7 @@ pub fn order_signature_message(
     serialize_payload(&payload)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn sign_order_payload(
     signer: &dyn Signer,
     symbol: &str,
@@ -0,0 +1,513 @@
+use super::OrderEntry;
+use crate::error::{LighterError, Result};
+use chrono::{Duration, Utc};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{error, warn};
+
+/// Shortest exchange-side cancel-all window Lighter accepts.
+const MIN_SCHEDULED_WINDOW: Duration = Duration::minutes(5);
+/// Longest exchange-side cancel-all window Lighter accepts.
+const MAX_SCHEDULED_WINDOW: Duration = Duration::days(15);
+/// Attempts made to cancel everything once triggered.
+const CANCEL_ATTEMPTS: u32 = 3;
+const CANCEL_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// What a [`DeadMansSwitch`] watches for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadMansSwitchConfig {
+    /// Trigger when [`Heartbeat::beat`] has not been called for this long.
+    pub heartbeat_timeout: Option<Duration>,
+    /// Keep an exchange-side cancel-all scheduled this far ahead, pushed
+    /// forward while the process is alive, so orders die even if it is
+    /// killed outright.
+    pub scheduled_cancel_window: Option<Duration>,
+    /// Trigger on SIGINT and, on Unix, SIGTERM. The signals no longer end
+    /// the process by themselves; wait on [`DeadMansSwitch::triggered`] and
+    /// exit from there.
+    pub handle_signals: bool,
+    /// Trigger when any thread panics. The cancel-all runs on the runtime,
+    /// so a panic that takes the runtime down with it (on the main thread,
+    /// or with `panic = "abort"`) is only covered by
+    /// [`Self::scheduled_cancel_window`].
+    pub handle_panics: bool,
+}
+
+impl Default for DeadMansSwitchConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_timeout: None,
+            scheduled_cancel_window: None,
+            handle_signals: true,
+            handle_panics: true,
+        }
+    }
+}
+
+impl DeadMansSwitchConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_heartbeat_timeout(mut self, timeout: Duration) -> Self {
+        self.heartbeat_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_scheduled_cancel(mut self, window: Duration) -> Self {
+        self.scheduled_cancel_window = Some(window);
+        self
+    }
+
+    pub fn with_signal_handling(mut self, handle_signals: bool) -> Self {
+        self.handle_signals = handle_signals;
+        self
+    }
+
+    pub fn with_panic_handling(mut self, handle_panics: bool) -> Self {
+        self.handle_panics = handle_panics;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(timeout) = self.heartbeat_timeout {
+            if timeout <= Duration::zero() {
+                return Err(LighterError::OrderValidation(
+                    "heartbeat timeout must be positive".to_string(),
+                ));
+            }
+        }
+        if let Some(window) = self.scheduled_cancel_window {
+            if !(MIN_SCHEDULED_WINDOW..=MAX_SCHEDULED_WINDOW).contains(&window) {
+                return Err(LighterError::OrderValidation(format!(
+                    "scheduled cancel-all window {window} must be between 5 minutes and 15 days"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a [`DeadMansSwitch`] fired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerReason {
+    Interrupt,
+    Terminate,
+    Panic(String),
+    HeartbeatTimeout,
+    /// [`DeadMansSwitch::trigger`] was called, e.g. on a planned shutdown.
+    Manual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwitchState {
+    Armed,
+    /// Fired; `cancelled` tells whether cancel-all went through.
+    Triggered {
+        reason: TriggerReason,
+        cancelled: bool,
+    },
+    Disarmed,
+}
+
+/// Cheap handle the strategy uses to prove it is alive.
+#[derive(Debug, Clone)]
+pub struct Heartbeat(Arc<Mutex<Instant>>);
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        *lock(&self.0) = Instant::now();
+    }
+
+    fn last(&self) -> Instant {
+        *lock(&self.0)
+    }
+}
+
+enum Command {
+    Trigger(oneshot::Sender<Result<()>>),
+    Disarm(oneshot::Sender<Result<()>>),
+}
+
+/// Cancels every open order when the process is interrupted, panics or
+/// stops sending heartbeats. Dropping the handle leaves it armed; call
+/// [`Self::disarm`] to stand it down.
+pub struct DeadMansSwitch {
+    heartbeat: Heartbeat,
+    state: watch::Receiver<SwitchState>,
+    commands: mpsc::Sender<Command>,
+    task: JoinHandle<()>,
+}
+
+impl DeadMansSwitch {
+    /// Arm the switch. With a scheduled cancel configured, the first one is
+    /// in place on the exchange before this returns.
+    pub async fn arm<E: OrderEntry + 'static>(
+        entry: Arc<E>,
+        config: DeadMansSwitchConfig,
+    ) -> Result<Self> {
+        config.validate()?;
+        if let Some(window) = config.scheduled_cancel_window {
+            entry.schedule_cancel_all(Some(Utc::now() + window)).await?;
+        }
+
+        let panics = config.handle_panics.then(install_panic_hook);
+        let signals = if config.handle_signals {
+            Some(Signals::new()?)
+        } else {
+            None
+        };
+
+        let heartbeat = Heartbeat(Arc::new(Mutex::new(Instant::now())));
+        let (state_tx, state) = watch::channel(SwitchState::Armed);
+        let (commands, command_rx) = mpsc::channel(4);
+        let watcher = Watcher {
+            entry,
+            config,
+            heartbeat: heartbeat.clone(),
+            state: state_tx,
+            commands: command_rx,
+            panics,
+            signals,
+        };
+        Ok(Self {
+            heartbeat,
+            state,
+            commands,
+            task: tokio::spawn(watcher.run()),
+        })
+    }
+
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat.clone()
+    }
+
+    pub fn beat(&self) {
+        self.heartbeat.beat();
+    }
+
+    pub fn state(&self) -> SwitchState {
+        self.state.borrow().clone()
+    }
+
+    /// Wait until the switch fires and return why. Returns `None` once it is
+    /// disarmed instead.
+    pub async fn triggered(&mut self) -> Option<TriggerReason> {
+        let state = self
+            .state
+            .wait_for(|state| *state != SwitchState::Armed)
+            .await
+            .ok()?;
+        match &*state {
+            SwitchState::Triggered { reason, .. } => Some(reason.clone()),
+            _ => None,
+        }
+    }
+
+    /// Fire now: cancel every open order, e.g. as the last step of a
+    /// graceful shutdown.
+    pub async fn trigger(&self) -> Result<()> {
+        self.request(Command::Trigger).await
+    }
+
+    /// Stand down without cancelling orders, aborting any scheduled
+    /// cancel-all on the exchange.
+    pub async fn disarm(self) -> Result<()> {
+        let result = self.request(Command::Disarm).await;
+        let _ = self.task.await;
+        result
+    }
+
+    async fn request(&self, command: fn(oneshot::Sender<Result<()>>) -> Command) -> Result<()> {
+        let (reply, response) = oneshot::channel();
+        if self.commands.send(command(reply)).await.is_err() {
+            return Err(LighterError::Unknown(
+                "dead man's switch is no longer armed".to_string(),
+            ));
+        }
+        response.await.unwrap_or_else(|_| {
+            Err(LighterError::Unknown(
+                "dead man's switch stopped before replying".to_string(),
+            ))
+        })
+    }
+}
+
+struct Watcher<E> {
+    entry: Arc<E>,
+    config: DeadMansSwitchConfig,
+    heartbeat: Heartbeat,
+    state: watch::Sender<SwitchState>,
+    commands: mpsc::Receiver<Command>,
+    panics: Option<mpsc::UnboundedReceiver<String>>,
+    signals: Option<Signals>,
+}
+
+impl<E: OrderEntry> Watcher<E> {
+    async fn run(mut self) {
+        let timeout = self.config.heartbeat_timeout.and_then(|t| t.to_std().ok());
+        let refresh = self
+            .config
+            .scheduled_cancel_window
+            .and_then(|window| (window / 3).to_std().ok());
+        let mut refresh_at = refresh.map(|every| Instant::now() + every);
+        let mut commands_open = true;
+
+        let (reason, reply) = loop {
+            let expires_at = timeout.map(|timeout| self.heartbeat.last() + timeout);
+            tokio::select! {
+                _ = sleep_until(expires_at) => {
+                    // A beat may have landed while asleep; re-arm from it.
+                    if timeout.is_some_and(|timeout| self.heartbeat.last() + timeout <= Instant::now()) {
+                        break (TriggerReason::HeartbeatTimeout, None);
+                    }
+                }
+                _ = sleep_until(refresh_at) => {
+                    if let (Some(window), Some(every)) = (self.config.scheduled_cancel_window, refresh) {
+                        if let Err(err) = self.entry.schedule_cancel_all(Some(Utc::now() + window)).await {
+                            warn!("failed to push back scheduled cancel-all: {}", err);
+                        }
+                        refresh_at = Some(Instant::now() + every);
+                    }
+                }
+                command = self.commands.recv(), if commands_open => match command {
+                    Some(Command::Trigger(reply)) => break (TriggerReason::Manual, Some(reply)),
+                    Some(Command::Disarm(reply)) => {
+                        let result = match self.config.scheduled_cancel_window {
+                            Some(_) => self.entry.schedule_cancel_all(None).await,
+                            None => Ok(()),
+                        };
+                        self.state.send_replace(SwitchState::Disarmed);
+                        let _ = reply.send(result);
+                        return;
+                    }
+                    None => commands_open = false,
+                },
+                message = recv_panic(&mut self.panics) => {
+                    break (TriggerReason::Panic(message), None);
+                }
+                reason = recv_signal(&mut self.signals) => break (reason, None),
+            }
+        };
+
+        error!(
+            "dead man's switch triggered ({:?}); cancelling all orders",
+            reason
+        );
+        let result = self.cancel_all().await;
+        self.state.send_replace(SwitchState::Triggered {
+            reason,
+            cancelled: result.is_ok(),
+        });
+        match reply {
+            Some(reply) => {
+                let _ = reply.send(result);
+            }
+            None => {
+                if let Err(err) = result {
+                    error!("dead man's switch could not cancel orders: {}", err);
+                }
+            }
+        }
+    }
+
+    async fn cancel_all(&self) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            match self.entry.cancel_all().await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt >= CANCEL_ATTEMPTS => return Err(err),
+                Err(err) => {
+                    warn!("cancel-all attempt {} failed: {}", attempt, err);
+                    attempt += 1;
+                    tokio::time::sleep(CANCEL_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+}
+
+async fn sleep_until(at: Option<Instant>) {
+    match at {
+        Some(at) => tokio::time::sleep_until(at).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn recv_panic(panics: &mut Option<mpsc::UnboundedReceiver<String>>) -> String {
+    match panics {
+        Some(receiver) => match receiver.recv().await {
+            Some(message) => message,
+            None => std::future::pending().await,
+        },
+        None => std::future::pending().await,
+    }
+}
+
+/// Chain a hook in front of the current panic hook that reports panics to
+/// the switch. The hook goes quiet once the switch is gone.
+///
+/// The hook only queues the panic; cancelling happens on the watcher task,
+/// which never gets to run if the panic ends the process. The exchange-side
+/// scheduled cancel is the backstop for that case.
+fn install_panic_hook() -> mpsc::UnboundedReceiver<String> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = sender.send(info.to_string());
+        previous(info);
+    }));
+    receiver
+}
+
+struct Signals {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+}
+
+impl Signals {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            terminate: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?,
+        })
+    }
+}
+
+async fn recv_signal(signals: &mut Option<Signals>) -> TriggerReason {
+    let Some(signals) = signals else {
+        return std::future::pending().await;
+    };
+    #[cfg(unix)]
+    {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => TriggerReason::Interrupt,
+            _ = signals.terminate.recv() => TriggerReason::Terminate,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = signals;
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => TriggerReason::Interrupt,
+            Err(_) => std::future::pending().await,
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::MarketInfo;
+    use crate::strategy::StrategyOrder;
+    use crate::trading::SubmittedOrder;
+    use chrono::DateTime;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingEntry {
+        cancel_alls: AtomicUsize,
+        scheduled: Mutex<Vec<Option<DateTime<Utc>>>>,
+    }
+
+    impl OrderEntry for CountingEntry {
+        async fn market_info(&self, _symbol: &str) -> Result<MarketInfo> {
+            unimplemented!()
+        }
+
+        async fn submit(&self, _order: &StrategyOrder) -> Result<SubmittedOrder> {
+            unimplemented!()
+        }
+
+        async fn cancel(&self, _symbol: &str, _client_order_index: i64) -> Result<()> {
+            Ok(())
+        }
+
+        async fn cancel_all(&self) -> Result<()> {
+            self.cancel_alls.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn schedule_cancel_all(&self, at: Option<DateTime<Utc>>) -> Result<()> {
+            lock(&self.scheduled).push(at);
+            Ok(())
+        }
+    }
+
+    fn quiet() -> DeadMansSwitchConfig {
+        DeadMansSwitchConfig::new()
+            .with_signal_handling(false)
+            .with_panic_handling(false)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fires_when_heartbeats_stop() {
+        let entry = Arc::new(CountingEntry::default());
+        let mut switch = DeadMansSwitch::arm(
+            entry.clone(),
+            quiet().with_heartbeat_timeout(Duration::seconds(60)),
+        )
+        .await
+        .unwrap();
+
+        let heartbeat = switch.heartbeat();
+        for _ in 0..4 {
+            tokio::time::advance(std::time::Duration::from_secs(40)).await;
+            heartbeat.beat();
+        }
+        assert_eq!(switch.state(), SwitchState::Armed);
+        assert_eq!(entry.cancel_alls.load(Ordering::SeqCst), 0);
+
+        tokio::time::advance(std::time::Duration::from_secs(60)).await;
+
+        assert_eq!(
+            switch.triggered().await,
+            Some(TriggerReason::HeartbeatTimeout)
+        );
+        assert_eq!(entry.cancel_alls.load(Ordering::SeqCst), 1);
+        assert!(switch.trigger().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn keeps_a_scheduled_cancel_until_disarmed() {
+        let entry = Arc::new(CountingEntry::default());
+        let switch = DeadMansSwitch::arm(
+            entry.clone(),
+            quiet().with_scheduled_cancel(Duration::minutes(10)),
+        )
+        .await
+        .unwrap();
+        assert!(lock(&entry.scheduled)[0].is_some_and(|at| at > Utc::now()));
+
+        switch.disarm().await.unwrap();
+        assert_eq!(lock(&entry.scheduled).last(), Some(&None));
+        assert_eq!(entry.cancel_alls.load(Ordering::SeqCst), 0);
+
+        let too_short =
+            DeadMansSwitch::arm(entry, quiet().with_scheduled_cancel(Duration::minutes(1))).await;
+        assert!(matches!(too_short, Err(LighterError::OrderValidation(_))));
+    }
+
+    #[tokio::test]
+    async fn manual_trigger_cancels_and_reports() {
+        let entry = Arc::new(CountingEntry::default());
+        let mut switch = DeadMansSwitch::arm(entry.clone(), quiet()).await.unwrap();
+        switch.trigger().await.unwrap();
+        assert_eq!(switch.triggered().await, Some(TriggerReason::Manual));
+        assert_eq!(
+            switch.state(),
+            SwitchState::Triggered {
+                reason: TriggerReason::Manual,
+                cancelled: true
+            }
+        );
+    }
+}
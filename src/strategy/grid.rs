@@ -0,0 +1,665 @@
+use crate::error::{LighterError, Result};
+use crate::ffi_client::AccountOrder;
+use crate::metadata::MarketInfo;
+use crate::models::order::TimeInForce;
+use crate::trading::{
+    ClientOrderId, LighterFfiTradingClient, OrderOptions, OrderState, OrderTransition,
+    SubmittedOrder,
+};
+use rust_decimal::prelude::*;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Closed orders fetched per page when resuming a grid.
+const RESUME_HISTORY_PAGE: u32 = 100;
+
+/// How level prices are spread between the bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridSpacing {
+    /// Equal price distance between levels.
+    Arithmetic,
+    /// Equal percentage distance between levels.
+    Geometric,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridConfig {
+    /// Prefix of the client order tags, unique per grid.
+    pub grid_id: String,
+    pub symbol: String,
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    /// Number of price levels, including both bounds.
+    pub levels: u32,
+    pub spacing: GridSpacing,
+    /// Size of every grid order.
+    pub order_size: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelStatus {
+    Idle,
+    /// An order is working (or being submitted) at this level.
+    Working {
+        is_buy: bool,
+        client_order_index: i64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridLevel {
+    pub price: Decimal,
+    pub status: LevelStatus,
+    /// Orders placed at this level so far; keeps client tags unique.
+    pub generation: u32,
+}
+
+/// An order the grid wants placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridOrder {
+    pub level: usize,
+    pub is_buy: bool,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub client_id: ClientOrderId,
+}
+
+/// Exchange-independent grid bookkeeping: which level has which order and
+/// what to place when one fills.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridState {
+    pub config: GridConfig,
+    pub levels: Vec<GridLevel>,
+    /// Whether the initial ladder has been laid out.
+    pub seeded: bool,
+}
+
+impl GridState {
+    /// Compute level prices snapped to the market's tick and the order size
+    /// snapped to its size step.
+    pub fn new(mut config: GridConfig, info: &MarketInfo) -> Result<Self> {
+        if config.grid_id.is_empty() {
+            return Err(LighterError::OrderValidation(
+                "grid id must not be empty".to_string(),
+            ));
+        }
+        if config.levels < 2
+            || config.lower_price <= Decimal::ZERO
+            || config.upper_price <= config.lower_price
+        {
+            return Err(LighterError::OrderValidation(format!(
+                "grid needs at least 2 levels and 0 < lower < upper, got {} levels in [{}, {}]",
+                config.levels, config.lower_price, config.upper_price
+            )));
+        }
+        config.order_size = config.order_size.round_dp_with_strategy(
+            info.supported_size_decimals.unwrap_or(0),
+            RoundingStrategy::ToZero,
+        );
+        if config.order_size <= Decimal::ZERO {
+            return Err(LighterError::OrderValidation(
+                "grid order size rounds to zero".to_string(),
+            ));
+        }
+
+        let price_decimals = info.supported_price_decimals.unwrap_or(0);
+        let prices = level_prices(&config)?
+            .into_iter()
+            .map(|price| price.round_dp(price_decimals))
+            .collect::<Vec<_>>();
+        if prices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(LighterError::OrderValidation(format!(
+                "grid levels collapse at {} price decimals; use fewer levels",
+                price_decimals
+            )));
+        }
+
+        Ok(Self {
+            levels: prices
+                .into_iter()
+                .map(|price| GridLevel {
+                    price,
+                    status: LevelStatus::Idle,
+                    generation: 0,
+                })
+                .collect(),
+            config,
+            seeded: false,
+        })
+    }
+
+    /// Lay out buys below and sells above `reference_price` on every idle
+    /// level but the one nearest it, which stays free as the grid's gap.
+    /// Working levels are left alone, so seeding again only refills levels
+    /// whose orders are gone.
+    pub fn seed(&mut self, reference_price: Decimal) -> Vec<GridOrder> {
+        self.seeded = true;
+        let gap = (0..self.levels.len())
+            .filter(|index| self.levels[*index].status == LevelStatus::Idle)
+            .min_by_key(|index| (self.levels[*index].price - reference_price).abs());
+        let mut orders = Vec::new();
+        for index in 0..self.levels.len() {
+            if self.levels[index].status != LevelStatus::Idle || Some(index) == gap {
+                continue;
+            }
+            let price = self.levels[index].price;
+            orders.push(self.claim(index, price < reference_price));
+        }
+        orders
+    }
+
+    /// Record a filled grid order and return the opposite order one level
+    /// away, if that level is free.
+    pub fn on_fill(&mut self, client_order_index: i64) -> Option<GridOrder> {
+        let (index, is_buy) = self.find(client_order_index)?;
+        self.levels[index].status = LevelStatus::Idle;
+        let target = if is_buy {
+            index
+                .checked_add(1)
+                .filter(|next| *next < self.levels.len())?
+        } else {
+            index.checked_sub(1)?
+        };
+        (self.levels[target].status == LevelStatus::Idle).then(|| self.claim(target, !is_buy))
+    }
+
+    /// Release a level whose order was cancelled, rejected or expired.
+    pub fn on_order_gone(&mut self, client_order_index: i64) -> bool {
+        match self.find(client_order_index) {
+            Some((index, _)) => {
+                self.levels[index].status = LevelStatus::Idle;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Release the levels of orders that were claimed but never placed.
+    pub fn release(&mut self, orders: impl IntoIterator<Item = GridOrder>) {
+        for order in orders {
+            if let Ok(index) = order.client_id.client_order_index() {
+                self.on_order_gone(index);
+            }
+        }
+    }
+
+    /// Client order indices of every working grid order.
+    pub fn working_orders(&self) -> impl Iterator<Item = i64> + '_ {
+        self.levels.iter().filter_map(|level| match level.status {
+            LevelStatus::Working {
+                client_order_index, ..
+            } => Some(client_order_index),
+            LevelStatus::Idle => None,
+        })
+    }
+
+    fn find(&self, client_order_index: i64) -> Option<(usize, bool)> {
+        self.levels
+            .iter()
+            .position(|level| {
+                matches!(level.status, LevelStatus::Working { client_order_index: working, .. } if working == client_order_index)
+            })
+            .map(|index| match self.levels[index].status {
+                LevelStatus::Working { is_buy, .. } => (index, is_buy),
+                LevelStatus::Idle => unreachable!("position matched a working level"),
+            })
+    }
+
+    fn claim(&mut self, index: usize, is_buy: bool) -> GridOrder {
+        let level = &mut self.levels[index];
+        level.generation += 1;
+        let client_id = ClientOrderId::Tag(format!(
+            "{}:{}:{}",
+            self.config.grid_id, index, level.generation
+        ));
+        // Tags always map to a valid index.
+        let client_order_index = client_id.client_order_index().unwrap_or_default();
+        level.status = LevelStatus::Working {
+            is_buy,
+            client_order_index,
+        };
+        GridOrder {
+            level: index,
+            is_buy,
+            price: level.price,
+            size: self.config.order_size,
+            client_id,
+        }
+    }
+}
+
+fn level_prices(config: &GridConfig) -> Result<Vec<Decimal>> {
+    let steps = Decimal::from(config.levels - 1);
+    match config.spacing {
+        GridSpacing::Arithmetic => {
+            let step = (config.upper_price - config.lower_price) / steps;
+            Ok((0..config.levels)
+                .map(|index| config.lower_price + step * Decimal::from(index))
+                .collect())
+        }
+        GridSpacing::Geometric => {
+            let ratio = (config.upper_price / config.lower_price)
+                .to_f64()
+                .map(|ratio| ratio.powf(1.0 / f64::from(config.levels - 1)))
+                .and_then(Decimal::from_f64)
+                .ok_or_else(|| {
+                    LighterError::OrderValidation("grid range is out of range".to_string())
+                })?;
+            let mut price = config.lower_price;
+            let mut prices = Vec::with_capacity(config.levels as usize);
+            for _ in 0..config.levels {
+                prices.push(price);
+                price *= ratio;
+            }
+            // Pin the top level to the bound despite float error.
+            if let Some(last) = prices.last_mut() {
+                *last = config.upper_price;
+            }
+            Ok(prices)
+        }
+    }
+}
+
+/// Grid driven through a [`LighterFfiTradingClient`], persisting its state
+/// to a JSON file before every submission so a restart neither doubles the
+/// ladder nor forgets working orders.
+#[derive(Debug)]
+pub struct GridStrategy {
+    state: GridState,
+    path: Option<PathBuf>,
+}
+
+impl GridStrategy {
+    pub fn new(state: GridState) -> Self {
+        Self { state, path: None }
+    }
+
+    /// Load the grid persisted at `path`, or start a new one from `config`.
+    /// A persisted grid must have been created from the same `config`.
+    pub fn open(path: impl Into<PathBuf>, config: GridConfig, info: &MarketInfo) -> Result<Self> {
+        let path = path.into();
+        let fresh = GridState::new(config, info)?;
+        let state = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let state: GridState = serde_json::from_slice(&bytes)?;
+                if state.config != fresh.config {
+                    return Err(LighterError::Config(format!(
+                        "grid state at {} was created with a different config",
+                        path.display()
+                    )));
+                }
+                state
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => fresh,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            state,
+            path: Some(path),
+        })
+    }
+
+    pub fn state(&self) -> &GridState {
+        &self.state
+    }
+
+    /// Place the initial ladder around `reference_price`. A grid restored
+    /// from disk is first reconciled with the exchange: orders that filled
+    /// while offline are answered, and levels left idle by vanished orders
+    /// or failed submissions are seeded again around `reference_price`.
+    pub async fn start(
+        &mut self,
+        client: &LighterFfiTradingClient,
+        reference_price: Decimal,
+    ) -> Result<Vec<SubmittedOrder>> {
+        let orders = if self.state.seeded {
+            self.reconcile(client, reference_price).await?
+        } else {
+            self.state.seed(reference_price)
+        };
+        self.submit(client, orders).await
+    }
+
+    /// Feed an order transition; fills re-place the opposite order.
+    pub async fn on_transition(
+        &mut self,
+        client: &LighterFfiTradingClient,
+        transition: &OrderTransition,
+    ) -> Result<Vec<SubmittedOrder>> {
+        let client_order_index = transition.order.client_order_index;
+        match transition.to {
+            OrderState::Filled => {
+                let next = self.state.on_fill(client_order_index);
+                self.submit(client, next.into_iter().collect()).await
+            }
+            OrderState::Cancelled | OrderState::Rejected | OrderState::Expired => {
+                if self.state.on_order_gone(client_order_index) {
+                    self.save()?;
+                }
+                Ok(Vec::new())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Cancel every working grid order and release its level.
+    pub async fn stop(&mut self, client: &LighterFfiTradingClient) -> Result<()> {
+        let active = client.active_orders(&self.state.config.symbol).await?;
+        let working: Vec<i64> = self.state.working_orders().collect();
+        for client_order_index in working {
+            if let Some(order) = find(&active, client_order_index) {
+                client
+                    .cancel_order(&self.state.config.symbol, &order.order_index.to_string())
+                    .await?;
+            }
+            self.state.on_order_gone(client_order_index);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    async fn reconcile(
+        &mut self,
+        client: &LighterFfiTradingClient,
+        reference_price: Decimal,
+    ) -> Result<Vec<GridOrder>> {
+        let symbol = self.state.config.symbol.clone();
+        let active = client.active_orders(&symbol).await?;
+        let mut pending: Vec<i64> = self
+            .state
+            .working_orders()
+            .filter(|index| find(&active, *index).is_none())
+            .collect();
+
+        // Page back through the history until every missing order is found.
+        let mut filled = Vec::new();
+        let mut cursor = None;
+        while !pending.is_empty() {
+            let (closed, next) = client
+                .inactive_orders_page(&symbol, RESUME_HISTORY_PAGE, cursor.as_deref())
+                .await?;
+            pending.retain(|index| match find(&closed, *index) {
+                Some(order) => {
+                    if order.status == "filled" {
+                        filled.push(*index);
+                    }
+                    false
+                }
+                None => true,
+            });
+            match next {
+                Some(next) if !closed.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        let mut replacements = Vec::new();
+        let working: Vec<i64> = self.state.working_orders().collect();
+        for client_order_index in working {
+            if find(&active, client_order_index).is_some() {
+                continue;
+            }
+            if filled.contains(&client_order_index) {
+                replacements.extend(self.state.on_fill(client_order_index));
+            } else {
+                self.state.on_order_gone(client_order_index);
+            }
+        }
+        replacements.extend(self.state.seed(reference_price));
+        Ok(replacements)
+    }
+
+    async fn submit(
+        &mut self,
+        client: &LighterFfiTradingClient,
+        orders: Vec<GridOrder>,
+    ) -> Result<Vec<SubmittedOrder>> {
+        // Persist the claimed levels first so a crash mid-submission cannot
+        // lead to a second order at the same level.
+        self.save()?;
+        let mut submitted = Vec::with_capacity(orders.len());
+        let mut orders = orders.into_iter();
+        while let Some(order) = orders.next() {
+            let options = OrderOptions::new().with_client_order_id(order.client_id.clone());
+            let result = client
+                .create_limit_order_with_options(
+                    &self.state.config.symbol,
+                    order.is_buy,
+                    &order.size,
+                    &order.price,
+                    false,
+                    TimeInForce::Gtc,
+                    options,
+                )
+                .await;
+            match result {
+                Ok(placed) => submitted.push(placed),
+                Err(err) => {
+                    // Neither this order nor the ones after it reached the
+                    // exchange; free their levels for the next attempt.
+                    self.state.release(std::iter::once(order).chain(orders));
+                    self.save()?;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(submitted)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        write_atomically(path, &serde_json::to_vec_pretty(&self.state)?)
+    }
+}
+
+fn find(orders: &[AccountOrder], client_order_index: i64) -> Option<&AccountOrder> {
+    orders
+        .iter()
+        .find(|order| order.client_order_index == client_order_index)
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> MarketInfo {
+        MarketInfo {
+            market_id: 0,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(3),
+            supported_price_decimals: Some(2),
+            ..Default::default()
+        }
+    }
+
+    fn config(spacing: GridSpacing) -> GridConfig {
+        GridConfig {
+            grid_id: "eth-grid".to_string(),
+            symbol: "ETH".to_string(),
+            lower_price: Decimal::from(2_800),
+            upper_price: Decimal::from(3_200),
+            levels: 5,
+            spacing,
+            order_size: Decimal::new(12_345, 4),
+        }
+    }
+
+    #[test]
+    fn builds_arithmetic_and_geometric_levels() {
+        let arithmetic = GridState::new(config(GridSpacing::Arithmetic), &info()).unwrap();
+        let prices: Vec<_> = arithmetic.levels.iter().map(|level| level.price).collect();
+        assert_eq!(
+            prices,
+            [2_800, 2_900, 3_000, 3_100, 3_200].map(Decimal::from)
+        );
+        assert_eq!(arithmetic.config.order_size, Decimal::new(1_234, 3));
+
+        let geometric = GridState::new(config(GridSpacing::Geometric), &info()).unwrap();
+        let gaps: Vec<_> = geometric
+            .levels
+            .windows(2)
+            .map(|pair| pair[1].price - pair[0].price)
+            .collect();
+        assert!(gaps.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(geometric
+            .levels
+            .iter()
+            .all(|level| level.price.scale() <= 2));
+    }
+
+    #[test]
+    fn fills_replace_the_opposite_side() {
+        let mut state = GridState::new(config(GridSpacing::Arithmetic), &info()).unwrap();
+        let seeded = state.seed(Decimal::from(3_000));
+        assert_eq!(seeded.len(), 4);
+        assert!(seeded
+            .iter()
+            .filter(|order| order.is_buy)
+            .all(|order| order.price < Decimal::from(3_000)));
+        // A second seed, as after a restart, adds nothing.
+        assert!(state.seed(Decimal::from(3_000)).is_empty());
+
+        let buy = seeded.iter().find(|order| order.level == 1).unwrap();
+        let index = buy.client_id.client_order_index().unwrap();
+        let sell = state.on_fill(index).unwrap();
+        assert_eq!((sell.level, sell.is_buy), (2, false));
+        assert_eq!(sell.price, Decimal::from(3_000));
+        assert_ne!(sell.client_id, buy.client_id);
+
+        let sell_index = sell.client_id.client_order_index().unwrap();
+        assert!(state.on_order_gone(sell_index));
+        assert!(!state.on_order_gone(sell_index));
+        assert_eq!(state.working_orders().count(), 3);
+    }
+
+    #[test]
+    fn releases_unsubmitted_levels() {
+        let mut state = GridState::new(config(GridSpacing::Arithmetic), &info()).unwrap();
+        let seeded = state.seed(Decimal::from(3_000));
+        state.release(seeded.into_iter().skip(1));
+        assert_eq!(state.working_orders().count(), 1);
+        assert_eq!(
+            state
+                .levels
+                .iter()
+                .filter(|level| level.status == LevelStatus::Idle)
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn reseeding_keeps_a_gap_at_the_reference_price() {
+        let mut state = GridState::new(config(GridSpacing::Arithmetic), &info()).unwrap();
+        let seeded = state.seed(Decimal::from(2_950));
+        assert_eq!(seeded.len(), 4);
+        assert_eq!(state.levels[1].status, LevelStatus::Idle);
+
+        // The price fell through the bottom buy, and the top sell went,
+        // e.g. cancelled.
+        let buy = seeded.iter().find(|order| order.level == 0).unwrap();
+        let sell = state.on_fill(buy.client_id.client_order_index().unwrap());
+        assert_eq!(
+            sell.map(|order| (order.level, order.is_buy)),
+            Some((1, false))
+        );
+        let far_sell = seeded.iter().find(|order| order.level == 4).unwrap();
+        state.on_order_gone(far_sell.client_id.client_order_index().unwrap());
+
+        // The filled level nearest the price stays free.
+        let refilled = state.seed(Decimal::from(2_850));
+        let sides: Vec<_> = refilled
+            .iter()
+            .map(|order| (order.level, order.is_buy))
+            .collect();
+        assert_eq!(sides, [(4, false)]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn restart_restores_the_ladder_after_failed_submission() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let mut server = mockito::Server::new_async().await;
+        let markets = json!({
+            "code": 200,
+            "order_books": [{
+                "market_id": 0,
+                "symbol": "ETH",
+                "supported_size_decimals": 3,
+                "supported_price_decimals": 2
+            }]
+        });
+        let _markets = server
+            .mock("GET", "/api/v1/orderBooks")
+            .with_status(200)
+            .with_body(markets.to_string())
+            .create_async()
+            .await;
+        let _active = server
+            .mock("GET", "/api/v1/accountActiveOrders")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(json!({ "code": 200, "orders": [] }).to_string())
+            .create_async()
+            .await;
+        let api_config = Config::new().with_base_url(server.url()).unwrap();
+        // Signing always fails, so no order reaches the exchange.
+        let client = LighterFfiTradingClient::unsigned(api_config, 7, 1).unwrap();
+
+        let state = GridState::new(config(GridSpacing::Arithmetic), &info()).unwrap();
+        let mut strategy = GridStrategy::new(state);
+        assert!(strategy.start(&client, Decimal::from(3_000)).await.is_err());
+        assert!(strategy.state().seeded);
+        assert_eq!(strategy.state().working_orders().count(), 0);
+
+        let restored = strategy
+            .reconcile(&client, Decimal::from(3_000))
+            .await
+            .unwrap();
+        let levels: Vec<_> = restored.iter().map(|order| order.level).collect();
+        assert_eq!(levels, [0, 1, 3, 4]);
+        assert_eq!(strategy.state().working_orders().count(), 4);
+    }
+
+    #[test]
+    fn rejects_grids_finer_than_the_tick() {
+        let mut dense = config(GridSpacing::Arithmetic);
+        dense.upper_price = Decimal::new(280_002, 2);
+        assert!(matches!(
+            GridState::new(dense, &info()),
+            Err(LighterError::OrderValidation(_))
+        ));
+    }
+
+    #[test]
+    fn persisted_state_round_trips() {
+        let dir = std::env::temp_dir().join(format!("lighter-grid-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("grid.json");
+
+        let mut strategy =
+            GridStrategy::open(&path, config(GridSpacing::Arithmetic), &info()).unwrap();
+        strategy.state.seed(Decimal::from(3_000));
+        strategy.save().unwrap();
+
+        let reopened = GridStrategy::open(&path, config(GridSpacing::Arithmetic), &info()).unwrap();
+        assert_eq!(reopened.state(), strategy.state());
+        assert!(matches!(
+            GridStrategy::open(&path, config(GridSpacing::Geometric), &info()),
+            Err(LighterError::Config(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
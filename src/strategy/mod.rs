@@ -0,0 +1,324 @@
+use crate::error::Result;
+use crate::models::common::{OrderBook, Side};
+use crate::models::order::TimeInForce;
+use crate::trading::{ClientOrderId, OrderTransition};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+
+mod dead_mans_switch;
+pub mod grid;
+mod runtime;
+
+pub use dead_mans_switch::{
+    DeadMansSwitch, DeadMansSwitchConfig, Heartbeat, SwitchState, TriggerReason,
+};
+pub use grid::{GridConfig, GridSpacing, GridState, GridStrategy};
+pub use runtime::{OrderEntry, StrategyRuntime};
+
+/// Trading logic driven by a [`StrategyRuntime`]. Hooks run one at a time
+/// and never block on the exchange: orders and cancels are queued on the
+/// [`StrategyContext`] and executed by the runtime once the hook returns.
+pub trait Strategy {
+    fn on_start(&mut self, _ctx: &mut StrategyContext) {}
+
+    /// The book of a subscribed market changed.
+    fn on_book(&mut self, _ctx: &mut StrategyContext, _book: &BookUpdate) {}
+
+    /// A public trade printed in a subscribed market.
+    fn on_trade(&mut self, _ctx: &mut StrategyContext, _trade: &MarketTrade) {}
+
+    /// An order of the account changed state, including orders the runtime
+    /// failed to submit, which are reported as rejected.
+    fn on_order_update(&mut self, _ctx: &mut StrategyContext, _transition: &OrderTransition) {}
+
+    /// A timer set with [`StrategyContext::set_timer`] fired.
+    fn on_timer(&mut self, _ctx: &mut StrategyContext, _timer: &str) {}
+
+    fn on_stop(&mut self, _ctx: &mut StrategyContext) {}
+}
+
+/// Input delivered to a [`Strategy`].
+#[derive(Debug, Clone)]
+pub enum StrategyEvent {
+    Book(BookUpdate),
+    Trade(MarketTrade),
+    Order(OrderTransition),
+    Timer(String),
+}
+
+/// Full book of one market after applying an update.
+#[derive(Debug, Clone)]
+pub struct BookUpdate {
+    pub symbol: String,
+    pub market_id: i32,
+    pub book: OrderBook,
+}
+
+impl BookUpdate {
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.book
+            .bids
+            .first()
+            .and_then(|level| level.price.parse().ok())
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.book
+            .asks
+            .first()
+            .and_then(|level| level.price.parse().ok())
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_bid()? + self.best_ask()?) / Decimal::TWO)
+    }
+}
+
+/// Public trade from the `trade` channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketTrade {
+    pub symbol: String,
+    pub market_id: i32,
+    pub trade_id: i64,
+    pub price: Decimal,
+    pub size: Decimal,
+    /// Side of the aggressor.
+    pub taker_side: Side,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyOrderKind {
+    Limit {
+        price: Decimal,
+        time_in_force: TimeInForce,
+    },
+    /// Market order priced from the book, see
+    /// [`crate::trading::LighterFfiTradingClient::create_market_order_with_slippage`].
+    Market { max_slippage_bps: u32 },
+}
+
+/// Order requested by a strategy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyOrder {
+    pub symbol: String,
+    pub is_buy: bool,
+    pub base_amount: Decimal,
+    pub kind: StrategyOrderKind,
+    pub reduce_only: bool,
+    /// Assigned by [`StrategyContext::submit`] when left empty.
+    pub client_id: Option<ClientOrderId>,
+}
+
+impl StrategyOrder {
+    /// Good-till-cancel limit order.
+    pub fn limit(
+        symbol: impl Into<String>,
+        is_buy: bool,
+        base_amount: Decimal,
+        price: Decimal,
+    ) -> Self {
+        Self {
+            symbol: symbol.into(),
+            is_buy,
+            base_amount,
+            kind: StrategyOrderKind::Limit {
+                price,
+                time_in_force: TimeInForce::Gtc,
+            },
+            reduce_only: false,
+            client_id: None,
+        }
+    }
+
+    pub fn market(
+        symbol: impl Into<String>,
+        is_buy: bool,
+        base_amount: Decimal,
+        max_slippage_bps: u32,
+    ) -> Self {
+        Self {
+            symbol: symbol.into(),
+            is_buy,
+            base_amount,
+            kind: StrategyOrderKind::Market { max_slippage_bps },
+            reduce_only: false,
+            client_id: None,
+        }
+    }
+
+    /// Only meaningful for limit orders.
+    pub fn with_time_in_force(mut self, tif: TimeInForce) -> Self {
+        if let StrategyOrderKind::Limit { time_in_force, .. } = &mut self.kind {
+            *time_in_force = tif;
+        }
+        self
+    }
+
+    pub fn with_reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    pub fn with_client_id(mut self, client_id: impl Into<ClientOrderId>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+}
+
+/// Exchange operation queued by a strategy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StrategyAction {
+    Submit(StrategyOrder),
+    Cancel {
+        symbol: String,
+        client_order_index: i64,
+    },
+    CancelAll,
+}
+
+/// Handle passed to every [`Strategy`] hook.
+#[derive(Debug)]
+pub struct StrategyContext {
+    now: DateTime<Utc>,
+    next_client_index: i64,
+    actions: Vec<StrategyAction>,
+    timers: Vec<(String, Option<Duration>)>,
+    stop_requested: bool,
+}
+
+impl StrategyContext {
+    /// Context at `now` that numbers unlabelled orders from
+    /// `next_client_index` upwards.
+    pub fn new(now: DateTime<Utc>, next_client_index: i64) -> Self {
+        Self {
+            now,
+            next_client_index,
+            actions: Vec::new(),
+            timers: Vec::new(),
+            stop_requested: false,
+        }
+    }
+
+    /// Time of the event being handled; simulated when backtesting.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    /// Queue an order and return the client order index it will carry.
+    pub fn submit(&mut self, mut order: StrategyOrder) -> Result<i64> {
+        let client_id = match order.client_id.take() {
+            Some(client_id) => client_id,
+            None => {
+                let index = self.next_client_index;
+                self.next_client_index += 1;
+                ClientOrderId::Index(index)
+            }
+        };
+        let client_order_index = client_id.client_order_index()?;
+        order.client_id = Some(client_id);
+        self.actions.push(StrategyAction::Submit(order));
+        Ok(client_order_index)
+    }
+
+    pub fn cancel(&mut self, symbol: impl Into<String>, client_order_index: i64) {
+        self.actions.push(StrategyAction::Cancel {
+            symbol: symbol.into(),
+            client_order_index,
+        });
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.actions.push(StrategyAction::CancelAll);
+    }
+
+    /// Fire [`Strategy::on_timer`] with `name` every `every`, replacing any
+    /// timer of the same name.
+    pub fn set_timer(&mut self, name: impl Into<String>, every: Duration) {
+        self.timers.push((name.into(), Some(every)));
+    }
+
+    pub fn cancel_timer(&mut self, name: impl Into<String>) {
+        self.timers.push((name.into(), None));
+    }
+
+    /// Ask the runtime to call [`Strategy::on_stop`] and return.
+    pub fn stop(&mut self) {
+        self.stop_requested = true;
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.stop_requested
+    }
+
+    pub fn next_client_index(&self) -> i64 {
+        self.next_client_index
+    }
+
+    /// Take the queued actions, in the order they were requested.
+    pub fn take_actions(&mut self) -> Vec<StrategyAction> {
+        std::mem::take(&mut self.actions)
+    }
+
+    /// Take the timer changes; `None` cancels the named timer.
+    pub fn take_timers(&mut self) -> Vec<(String, Option<Duration>)> {
+        std::mem::take(&mut self.timers)
+    }
+}
+
+/// Deliver `event` to the matching hook of `strategy`.
+pub fn dispatch<S: Strategy + ?Sized>(
+    strategy: &mut S,
+    ctx: &mut StrategyContext,
+    event: &StrategyEvent,
+) {
+    match event {
+        StrategyEvent::Book(book) => strategy.on_book(ctx, book),
+        StrategyEvent::Trade(trade) => strategy.on_trade(ctx, trade),
+        StrategyEvent::Order(transition) => strategy.on_order_update(ctx, transition),
+        StrategyEvent::Timer(name) => strategy.on_timer(ctx, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_numbers_orders_and_keeps_request_order() {
+        let mut ctx = StrategyContext::new(Utc::now(), 7);
+        let first = ctx
+            .submit(StrategyOrder::limit(
+                "ETH",
+                true,
+                Decimal::ONE,
+                Decimal::from(3_000),
+            ))
+            .unwrap();
+        ctx.cancel("ETH", first);
+        let tagged = ctx
+            .submit(StrategyOrder::market("ETH", false, Decimal::ONE, 50).with_client_id("exit"))
+            .unwrap();
+
+        assert_eq!(first, 7);
+        assert_eq!(ctx.next_client_index(), 8);
+        assert_eq!(
+            tagged,
+            ClientOrderId::from("exit").client_order_index().unwrap()
+        );
+        let actions = ctx.take_actions();
+        assert_eq!(actions.len(), 3);
+        assert!(matches!(
+            &actions[0],
+            StrategyAction::Submit(order) if order.client_id == Some(ClientOrderId::Index(7))
+        ));
+        assert!(matches!(
+            actions[1],
+            StrategyAction::Cancel {
+                client_order_index: 7,
+                ..
+            }
+        ));
+        assert!(ctx.take_actions().is_empty());
+    }
+}
@@ -0,0 +1,1012 @@
+use super::{
+    dispatch, BookUpdate, MarketTrade, Strategy, StrategyAction, StrategyContext, StrategyEvent,
+    StrategyOrder, StrategyOrderKind,
+};
+use crate::auth::AuthTokenProvider;
+use crate::client::ws_client::WebSocketClient;
+use crate::error::{LighterError, Result};
+use crate::metadata::MarketInfo;
+use crate::models::common::{decimal_field, parse_timestamp, OrderBook, PriceLevel, Side};
+use crate::models::order::CancelAllMode;
+use crate::trading::{
+    LighterFfiTradingClient, OrderOptions, OrderState, OrderTracker, OrderTransition,
+    SubmittedOrder, TrackedOrder,
+};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Price levels per side handed to [`Strategy::on_book`] by default.
+const DEFAULT_BOOK_DEPTH: usize = 20;
+/// Reconnects attempted after the WS connection drops, before giving up.
+const DEFAULT_RECONNECT_ATTEMPTS: u32 = 5;
+/// Wait before the first reconnect, doubled for every further attempt.
+const DEFAULT_RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Order entry used by a [`StrategyRuntime`] to carry out strategy actions.
+pub trait OrderEntry: Send + Sync {
+    fn market_info(&self, symbol: &str) -> impl Future<Output = Result<MarketInfo>> + Send;
+
+    /// Submit `order`, whose client id has been assigned by the context.
+    fn submit(&self, order: &StrategyOrder) -> impl Future<Output = Result<SubmittedOrder>> + Send;
+
+    fn cancel(
+        &self,
+        symbol: &str,
+        client_order_index: i64,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn cancel_all(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// Schedule a venue-side cancel-all at `at`, replacing any earlier one,
+    /// or abort the scheduled cancel with `None`. Venues without the feature
+    /// accept and ignore it.
+    fn schedule_cancel_all(
+        &self,
+        _at: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Market data seen by the runtime, for venues that match orders
+    /// themselves. Called before the strategy sees the event.
+    fn on_market_event(&self, _event: &StrategyEvent) {}
+
+    /// Order changes produced by the venue since the last call. Live venues
+    /// report through the account stream instead and return nothing.
+    fn take_order_updates(&self) -> Vec<TrackedOrder> {
+        Vec::new()
+    }
+}
+
+impl OrderEntry for LighterFfiTradingClient {
+    async fn market_info(&self, symbol: &str) -> Result<MarketInfo> {
+        self.market(symbol).await
+    }
+
+    async fn submit(&self, order: &StrategyOrder) -> Result<SubmittedOrder> {
+        let mut options = OrderOptions::new();
+        options.client_order_id = order.client_id.clone();
+        match order.kind {
+            StrategyOrderKind::Limit {
+                price,
+                time_in_force,
+            } => {
+                self.create_limit_order_with_options(
+                    &order.symbol,
+                    order.is_buy,
+                    &order.base_amount,
+                    &price,
+                    order.reduce_only,
+                    time_in_force,
+                    options,
+                )
+                .await
+            }
+            StrategyOrderKind::Market { max_slippage_bps } => {
+                self.create_market_order_with_slippage_and_options(
+                    &order.symbol,
+                    order.is_buy,
+                    &order.base_amount,
+                    max_slippage_bps,
+                    order.reduce_only,
+                    options,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn cancel(&self, _symbol: &str, client_order_index: i64) -> Result<()> {
+        self.cancel_order_by_client_id(client_order_index)
+            .await
+            .map(|_| ())
+    }
+
+    async fn cancel_all(&self) -> Result<()> {
+        self.cancel_all_orders(None).await.map(|_| ())
+    }
+
+    async fn schedule_cancel_all(&self, at: Option<DateTime<Utc>>) -> Result<()> {
+        let mode = match at {
+            Some(at) => CancelAllMode::Scheduled(at),
+            None => CancelAllMode::AbortScheduled,
+        };
+        self.cancel_all_orders_with_mode(mode).await.map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Timer {
+    every: Duration,
+    next_at: DateTime<Utc>,
+}
+
+/// Local copy of a market's book, rebuilt from WS snapshots and deltas.
+#[derive(Debug, Default)]
+struct LocalBook {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl LocalBook {
+    fn apply(&mut self, snapshot: bool, bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) {
+        if snapshot {
+            self.bids.clear();
+            self.asks.clear();
+        }
+        for (side, levels) in [(&mut self.bids, bids), (&mut self.asks, asks)] {
+            for (price, size) in levels {
+                if size.is_zero() {
+                    side.remove(price);
+                } else {
+                    side.insert(*price, *size);
+                }
+            }
+        }
+    }
+
+    fn to_order_book(&self, depth: usize, timestamp: DateTime<Utc>) -> OrderBook {
+        let level = |(price, size): (&Decimal, &Decimal)| PriceLevel {
+            price: price.to_string(),
+            quantity: size.to_string(),
+        };
+        OrderBook {
+            bids: self.bids.iter().rev().take(depth).map(level).collect(),
+            asks: self.asks.iter().take(depth).map(level).collect(),
+            timestamp,
+        }
+    }
+}
+
+enum Hook {
+    Start,
+    Event(StrategyEvent),
+    Stop,
+}
+
+/// Single-threaded event loop connecting a [`Strategy`] to Lighter market
+/// data, the account order stream and order entry.
+///
+/// Events are handled strictly one after another: a hook runs, the actions
+/// it queued are executed in order, any resulting order updates are
+/// dispatched, and only then is the next message read.
+pub struct StrategyRuntime<S, E> {
+    strategy: S,
+    entry: Arc<E>,
+    symbols: Vec<String>,
+    markets: HashMap<i32, MarketInfo>,
+    books: HashMap<i32, LocalBook>,
+    book_depth: usize,
+    account: Option<(i64, Arc<AuthTokenProvider>)>,
+    reconnect_attempts: u32,
+    reconnect_delay: std::time::Duration,
+    tracker: OrderTracker,
+    timers: BTreeMap<String, Timer>,
+    next_client_index: i64,
+    /// Set when replaying data; otherwise wall time is used.
+    simulated_now: Option<DateTime<Utc>>,
+    started: bool,
+    stopped: bool,
+}
+
+impl<S: Strategy, E: OrderEntry> StrategyRuntime<S, E> {
+    pub fn new(strategy: S, entry: Arc<E>) -> Self {
+        Self {
+            strategy,
+            entry,
+            symbols: Vec::new(),
+            markets: HashMap::new(),
+            books: HashMap::new(),
+            book_depth: DEFAULT_BOOK_DEPTH,
+            account: None,
+            reconnect_attempts: DEFAULT_RECONNECT_ATTEMPTS,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            tracker: OrderTracker::new(),
+            timers: BTreeMap::new(),
+            // Millisecond start time keeps indices distinct across restarts.
+            next_client_index: Utc::now().timestamp_millis(),
+            simulated_now: None,
+            started: false,
+            stopped: false,
+        }
+    }
+
+    /// Subscribe to the book and trades of `symbol`.
+    pub fn with_market(mut self, symbol: impl Into<String>) -> Self {
+        self.symbols.push(symbol.into());
+        self
+    }
+
+    /// Subscribe to the orders of `account_index` on
+    /// `account_all_orders/{account_index}`. A fresh token is minted
+    /// from `auth` for every subscription, including after a reconnect.
+    pub fn with_account_stream(mut self, account_index: i64, auth: Arc<AuthTokenProvider>) -> Self {
+        self.account = Some((account_index, auth));
+        self
+    }
+
+    /// How often [`Self::run`] tries to reconnect after the WS connection
+    /// drops, waiting `delay` before the first attempt and twice as long
+    /// before each further one. Zero attempts ends the run on a drop.
+    pub fn with_reconnect(mut self, attempts: u32, delay: std::time::Duration) -> Self {
+        self.reconnect_attempts = attempts;
+        self.reconnect_delay = delay;
+        self
+    }
+
+    pub fn with_book_depth(mut self, depth: usize) -> Self {
+        self.book_depth = depth;
+        self
+    }
+
+    pub fn with_first_client_index(mut self, client_order_index: i64) -> Self {
+        self.next_client_index = client_order_index;
+        self
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    pub fn strategy_mut(&mut self) -> &mut S {
+        &mut self.strategy
+    }
+
+    pub fn tracker(&self) -> &OrderTracker {
+        &self.tracker
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn into_strategy(self) -> S {
+        self.strategy
+    }
+
+    /// Switch to a simulated clock reading `now`, as used when replaying
+    /// recorded data. The clock only moves when this is called again.
+    pub fn set_time(&mut self, now: DateTime<Utc>) {
+        self.simulated_now = Some(now);
+    }
+
+    /// Simulated time if set, otherwise wall time.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.simulated_now.unwrap_or_else(Utc::now)
+    }
+
+    /// When the earliest timer fires next.
+    pub fn next_timer(&self) -> Option<DateTime<Utc>> {
+        self.timers.values().map(|timer| timer.next_at).min()
+    }
+
+    /// Resolve the configured markets and call [`Strategy::on_start`].
+    pub async fn start(&mut self) -> Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        for symbol in &self.symbols {
+            let info = self.entry.market_info(symbol).await?;
+            self.markets.insert(info.market_id, info);
+        }
+        self.started = true;
+        self.process(Hook::Start).await
+    }
+
+    /// Call [`Strategy::on_stop`] and execute what it queued.
+    pub async fn stop(&mut self) -> Result<()> {
+        if !self.started {
+            return Ok(());
+        }
+        self.started = false;
+        self.stopped = true;
+        self.process(Hook::Stop).await
+    }
+
+    /// Deliver one event, e.g. from a source other than the WS feed.
+    pub async fn handle(&mut self, event: StrategyEvent) -> Result<()> {
+        if matches!(event, StrategyEvent::Book(_) | StrategyEvent::Trade(_)) {
+            self.entry.on_market_event(&event);
+        }
+        // Fills caused by this data are delivered before the data itself.
+        let mut hooks = self.venue_updates();
+        hooks.push_back(Hook::Event(event));
+        self.process_all(hooks).await
+    }
+
+    /// Route a raw WS message: books and trades of subscribed markets go to
+    /// the strategy, account order updates through the order tracker.
+    pub async fn handle_ws_message(&mut self, message: &Value) -> Result<()> {
+        let kind = message.get("type").and_then(Value::as_str).unwrap_or("");
+        let events = if kind.ends_with("/order_book") {
+            self.apply_book_message(message, kind.starts_with("subscribed/"))
+                .map(StrategyEvent::Book)
+                .into_iter()
+                .collect()
+        } else if kind.ends_with("/trade") {
+            self.parse_trades(message)
+                .into_iter()
+                .map(StrategyEvent::Trade)
+                .collect()
+        } else if kind.contains("account") {
+            self.tracker
+                .apply_ws_message(message)
+                .into_iter()
+                .map(StrategyEvent::Order)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for event in events {
+            self.handle(event).await?;
+        }
+        Ok(())
+    }
+
+    /// Fire every timer due at `now`, earliest first.
+    pub async fn fire_timers(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let mut due: Vec<(DateTime<Utc>, String)> = self
+            .timers
+            .iter()
+            .filter(|(_, timer)| timer.next_at <= now)
+            .map(|(name, timer)| (timer.next_at, name.clone()))
+            .collect();
+        due.sort();
+        for (_, name) in due {
+            let Some(timer) = self.timers.get_mut(&name) else {
+                continue;
+            };
+            timer.next_at += timer.every;
+            if timer.next_at <= now {
+                timer.next_at = now + timer.every;
+            }
+            self.handle(StrategyEvent::Timer(name)).await?;
+        }
+        Ok(())
+    }
+
+    /// Connect `ws` if needed, subscribe and dispatch until the strategy
+    /// stops. A dropped connection is reconnected and subscribed again as
+    /// configured with [`Self::with_reconnect`]; once that fails the run
+    /// ends with an error after [`Strategy::on_stop`] has been called.
+    pub async fn run(&mut self, ws: &mut WebSocketClient) -> Result<()> {
+        // Markets are resolved first so their channels can be subscribed.
+        self.start().await?;
+        if let Err(err) = self.connect(ws).await {
+            self.stop().await?;
+            return Err(err);
+        }
+
+        while !self.stopped {
+            let wait = self
+                .next_timer()
+                .map(|next_at| (next_at - self.now()).to_std().unwrap_or_default());
+
+            tokio::select! {
+                message = ws.next_message() => match message {
+                    Ok(Some(message)) => self.handle_ws_message(&message).await?,
+                    Ok(None) if ws.is_connected() => {}
+                    Ok(None) => {
+                        self.reconnect(ws, LighterError::WebSocket(Box::new(
+                            tungstenite::Error::ConnectionClosed,
+                        )))
+                        .await?;
+                    }
+                    Err(err @ LighterError::WebSocket(_)) => self.reconnect(ws, err).await?,
+                    Err(err) => {
+                        self.stop().await?;
+                        return Err(err);
+                    }
+                },
+                _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {
+                    self.fire_timers(self.now()).await?;
+                }
+            }
+        }
+        self.stop().await
+    }
+
+    async fn connect(&self, ws: &mut WebSocketClient) -> Result<()> {
+        if !ws.is_connected() {
+            ws.connect().await?;
+        }
+        self.subscribe(ws).await
+    }
+
+    /// Reconnect after `cause` dropped the connection, backing off between
+    /// attempts. Book snapshots sent on resubscribing replace the local books.
+    /// Stops the strategy and returns the last error once every attempt
+    /// failed.
+    async fn reconnect(&mut self, ws: &mut WebSocketClient, cause: LighterError) -> Result<()> {
+        let mut last_err = cause;
+        let mut delay = self.reconnect_delay;
+        for attempt in 1..=self.reconnect_attempts {
+            warn!(
+                "WS connection lost ({}), reconnecting (attempt {}/{})",
+                last_err, attempt, self.reconnect_attempts
+            );
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            let _ = ws.close().await;
+            match self.connect(ws).await {
+                Ok(()) => return Ok(()),
+                Err(err) => last_err = err,
+            }
+        }
+        self.stop().await?;
+        Err(last_err)
+    }
+
+    async fn subscribe(&self, ws: &mut WebSocketClient) -> Result<()> {
+        for market_id in self.market_ids() {
+            for channel in [
+                format!("order_book/{market_id}"),
+                format!("trade/{market_id}"),
+            ] {
+                ws.subscribe(&channel, Some(serde_json::json!({ "channel": channel })))
+                    .await?;
+            }
+        }
+        if let Some((channel, params)) = self.account_subscription()? {
+            ws.subscribe(&channel, Some(params)).await?;
+        }
+        Ok(())
+    }
+
+    /// Channel and parameters of the account stream. The exchange only checks
+    /// the token when subscribing, so each subscription gets a newly minted
+    /// one rather than a cached token that may be close to expiry.
+    fn account_subscription(&self) -> Result<Option<(String, Value)>> {
+        let Some((account_index, auth)) = &self.account else {
+            return Ok(None);
+        };
+        auth.invalidate();
+        let token = auth.token()?;
+        let channel = format!("account_all_orders/{account_index}");
+        let params = serde_json::json!({ "channel": channel, "auth": token });
+        Ok(Some((channel, params)))
+    }
+
+    fn market_ids(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self.markets.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    async fn process(&mut self, hook: Hook) -> Result<()> {
+        self.process_all(VecDeque::from([hook])).await
+    }
+
+    async fn process_all(&mut self, mut queue: VecDeque<Hook>) -> Result<()> {
+        while let Some(hook) = queue.pop_front() {
+            let mut ctx = StrategyContext::new(self.now(), self.next_client_index);
+            match &hook {
+                Hook::Start => self.strategy.on_start(&mut ctx),
+                Hook::Event(event) => dispatch(&mut self.strategy, &mut ctx, event),
+                Hook::Stop => self.strategy.on_stop(&mut ctx),
+            }
+            self.next_client_index = ctx.next_client_index();
+            if ctx.stop_requested() {
+                self.stopped = true;
+            }
+            for (name, every) in ctx.take_timers() {
+                match every {
+                    Some(every) => {
+                        let next_at = ctx.now() + every;
+                        self.timers.insert(name, Timer { every, next_at });
+                    }
+                    None => {
+                        self.timers.remove(&name);
+                    }
+                }
+            }
+            for action in ctx.take_actions() {
+                if let Some(transition) = self.execute(action).await? {
+                    queue.push_back(Hook::Event(StrategyEvent::Order(transition)));
+                }
+            }
+            queue.extend(self.venue_updates());
+        }
+        Ok(())
+    }
+
+    fn venue_updates(&mut self) -> VecDeque<Hook> {
+        self.entry
+            .take_order_updates()
+            .iter()
+            .filter_map(|order| self.tracker.apply_tracked(order))
+            .map(|transition| Hook::Event(StrategyEvent::Order(transition)))
+            .collect()
+    }
+
+    /// Carry out one action. Failed submissions come back as a rejection
+    /// for the strategy; failed cancels are only logged, since the order
+    /// stream will still report the order's fate.
+    async fn execute(&mut self, action: StrategyAction) -> Result<Option<OrderTransition>> {
+        match action {
+            StrategyAction::Submit(order) => match self.entry.submit(&order).await {
+                Ok(submitted) => {
+                    self.tracker.track_submission(&submitted);
+                    Ok(None)
+                }
+                Err(err) => {
+                    warn!("strategy order on {} rejected: {}", order.symbol, err);
+                    let client_order_index = match &order.client_id {
+                        Some(client_id) => client_id.client_order_index()?,
+                        None => return Err(err),
+                    };
+                    let market_index = self
+                        .markets
+                        .values()
+                        .find(|info| info.symbol.eq_ignore_ascii_case(&order.symbol))
+                        .map_or(-1, |info| info.market_id);
+                    Ok(Some(OrderTransition {
+                        from: OrderState::Submitted,
+                        to: OrderState::Rejected,
+                        order: TrackedOrder {
+                            client_order_index,
+                            order_index: None,
+                            market_index,
+                            state: OrderState::Rejected,
+                            is_ask: !order.is_buy,
+                            filled_base: Decimal::ZERO,
+                            filled_quote: Decimal::ZERO,
+                            tx_hash: None,
+                        },
+                    }))
+                }
+            },
+            StrategyAction::Cancel {
+                symbol,
+                client_order_index,
+            } => {
+                if let Err(err) = self.entry.cancel(&symbol, client_order_index).await {
+                    warn!(
+                        "cancel of client order {} on {} failed: {}",
+                        client_order_index, symbol, err
+                    );
+                }
+                Ok(None)
+            }
+            StrategyAction::CancelAll => {
+                if let Err(err) = self.entry.cancel_all().await {
+                    warn!("strategy cancel-all failed: {}", err);
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn apply_book_message(&mut self, message: &Value, snapshot: bool) -> Option<BookUpdate> {
+        let market_id = channel_market_id(message)?;
+        let info = self.markets.get(&market_id)?;
+        let book = message.get("order_book")?;
+        let bids = parse_levels(book.get("bids"));
+        let asks = parse_levels(book.get("asks"));
+
+        let symbol = info.symbol.clone();
+        let now = self.now();
+        let local = self.books.entry(market_id).or_default();
+        local.apply(snapshot, &bids, &asks);
+        Some(BookUpdate {
+            symbol,
+            market_id,
+            book: local.to_order_book(self.book_depth, now),
+        })
+    }
+
+    fn parse_trades(&self, message: &Value) -> Vec<MarketTrade> {
+        let Some(trades) = message.get("trades").and_then(Value::as_array) else {
+            return Vec::new();
+        };
+        trades
+            .iter()
+            .filter_map(|trade| {
+                let market_id = trade
+                    .get("market_id")
+                    .and_then(Value::as_i64)
+                    .and_then(|id| i32::try_from(id).ok())
+                    .or_else(|| channel_market_id(message))?;
+                let info = self.markets.get(&market_id)?;
+                let is_maker_ask = trade.get("is_maker_ask").and_then(Value::as_bool)?;
+                Some(MarketTrade {
+                    symbol: info.symbol.clone(),
+                    market_id,
+                    trade_id: trade
+                        .get("trade_id")
+                        .and_then(Value::as_i64)
+                        .unwrap_or_default(),
+                    price: decimal_field(trade, "price")?,
+                    size: decimal_field(trade, "size")?,
+                    // A resting ask was lifted by a buyer.
+                    taker_side: if is_maker_ask { Side::Buy } else { Side::Sell },
+                    timestamp: trade
+                        .get("timestamp")
+                        .and_then(Value::as_i64)
+                        .and_then(parse_timestamp)
+                        .unwrap_or_else(|| self.now()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Market id from a channel such as `order_book:0` or `trade/0`.
+fn channel_market_id(message: &Value) -> Option<i32> {
+    message
+        .get("channel")?
+        .as_str()?
+        .rsplit([':', '/'])
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn parse_levels(levels: Option<&Value>) -> Vec<(Decimal, Decimal)> {
+    levels
+        .and_then(Value::as_array)
+        .map(|levels| {
+            levels
+                .iter()
+                .filter_map(|level| {
+                    Some((
+                        decimal_field(level, "price")?,
+                        decimal_field(level, "size")?,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::transaction_api::TxResponse;
+    use crate::signers::SignedCreateOrder;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingEntry {
+        actions: Mutex<Vec<String>>,
+        reject: bool,
+    }
+
+    impl OrderEntry for RecordingEntry {
+        async fn market_info(&self, symbol: &str) -> Result<MarketInfo> {
+            Ok(MarketInfo {
+                market_id: 0,
+                symbol: symbol.to_string(),
+                supported_size_decimals: Some(3),
+                supported_price_decimals: Some(2),
+                ..Default::default()
+            })
+        }
+
+        async fn submit(&self, order: &StrategyOrder) -> Result<SubmittedOrder> {
+            let client_order_index = order.client_id.as_ref().unwrap().client_order_index()?;
+            self.actions
+                .lock()
+                .unwrap()
+                .push(format!("submit {client_order_index}"));
+            if self.reject {
+                return Err(LighterError::OrderValidation("rejected".to_string()));
+            }
+            Ok(SubmittedOrder {
+                order: SignedCreateOrder {
+                    account_index: 1,
+                    api_key_index: 0,
+                    market_index: 0,
+                    client_order_index,
+                    base_amount: 1_000,
+                    price: 300_000,
+                    is_ask: !order.is_buy,
+                    order_type: 0,
+                    time_in_force: 1,
+                    reduce_only: false,
+                    trigger_price: 0,
+                    order_expiry: -1,
+                    expired_at: 0,
+                    nonce: 1,
+                    signature: None,
+                },
+                response: TxResponse {
+                    code: 200,
+                    tx_hash: None,
+                    message: None,
+                    predicted_execution_time_ms: None,
+                },
+            })
+        }
+
+        async fn cancel(&self, _symbol: &str, client_order_index: i64) -> Result<()> {
+            self.actions
+                .lock()
+                .unwrap()
+                .push(format!("cancel {client_order_index}"));
+            Ok(())
+        }
+
+        async fn cancel_all(&self) -> Result<()> {
+            self.actions.lock().unwrap().push("cancel_all".to_string());
+            Ok(())
+        }
+    }
+
+    /// Quotes one bid under the touch and re-quotes on every book change.
+    #[derive(Default)]
+    struct Quoter {
+        working: Option<i64>,
+        rejections: usize,
+        updates: Vec<OrderState>,
+        stopped: bool,
+    }
+
+    impl Strategy for Quoter {
+        fn on_start(&mut self, ctx: &mut StrategyContext) {
+            ctx.set_timer("heartbeat", Duration::seconds(1));
+        }
+
+        fn on_book(&mut self, ctx: &mut StrategyContext, book: &BookUpdate) {
+            if let Some(working) = self.working.take() {
+                ctx.cancel(&book.symbol, working);
+            }
+            let price = book.best_bid().unwrap() - Decimal::ONE;
+            self.working = ctx
+                .submit(StrategyOrder::limit(
+                    &book.symbol,
+                    true,
+                    Decimal::ONE,
+                    price,
+                ))
+                .ok();
+        }
+
+        fn on_order_update(&mut self, _ctx: &mut StrategyContext, transition: &OrderTransition) {
+            self.updates.push(transition.to);
+            if transition.to == OrderState::Rejected {
+                self.rejections += 1;
+            }
+        }
+
+        fn on_timer(&mut self, ctx: &mut StrategyContext, _timer: &str) {
+            ctx.stop();
+        }
+
+        fn on_stop(&mut self, ctx: &mut StrategyContext) {
+            self.stopped = true;
+            ctx.cancel_all();
+        }
+    }
+
+    fn book_message(kind: &str, bids: Value) -> Value {
+        serde_json::json!({
+            "type": kind,
+            "channel": "order_book:0",
+            "order_book": { "bids": bids, "asks": [{ "price": "3001.00", "size": "1.0" }] }
+        })
+    }
+
+    #[tokio::test]
+    async fn dispatches_in_order_and_executes_actions() {
+        let entry = Arc::new(RecordingEntry::default());
+        let mut runtime = StrategyRuntime::new(Quoter::default(), entry.clone())
+            .with_market("ETH")
+            .with_first_client_index(100);
+        runtime.start().await.unwrap();
+
+        let snapshot = book_message(
+            "subscribed/order_book",
+            serde_json::json!([
+                { "price": "3000.00", "size": "2.0" },
+                { "price": "2999.00", "size": "1.0" }
+            ]),
+        );
+        runtime.handle_ws_message(&snapshot).await.unwrap();
+        // The top bid is pulled, so the next quote keys off 2999.
+        let delta = book_message(
+            "update/order_book",
+            serde_json::json!([{ "price": "3000.00", "size": "0" }]),
+        );
+        runtime.handle_ws_message(&delta).await.unwrap();
+
+        assert_eq!(
+            *entry.actions.lock().unwrap(),
+            ["submit 100", "cancel 100", "submit 101"]
+        );
+        assert_eq!(runtime.tracker().open_orders().count(), 2);
+
+        runtime
+            .fire_timers(Utc::now() + Duration::seconds(2))
+            .await
+            .unwrap();
+        assert!(runtime.is_stopped());
+        runtime.stop().await.unwrap();
+        assert!(runtime.strategy().stopped);
+        assert_eq!(entry.actions.lock().unwrap().last().unwrap(), "cancel_all");
+    }
+
+    #[tokio::test]
+    async fn failed_submissions_are_reported_as_rejections() {
+        let entry = Arc::new(RecordingEntry {
+            reject: true,
+            ..Default::default()
+        });
+        let mut runtime = StrategyRuntime::new(Quoter::default(), entry).with_market("ETH");
+        runtime.start().await.unwrap();
+        let snapshot = book_message(
+            "subscribed/order_book",
+            serde_json::json!([{ "price": "3000.00", "size": "2.0" }]),
+        );
+        runtime.handle_ws_message(&snapshot).await.unwrap();
+        assert_eq!(runtime.strategy().rejections, 1);
+    }
+
+    #[tokio::test]
+    async fn applies_account_order_updates() {
+        let entry = Arc::new(RecordingEntry::default());
+        let mut runtime = StrategyRuntime::new(Quoter::default(), entry)
+            .with_market("ETH")
+            .with_first_client_index(100);
+        runtime.start().await.unwrap();
+        let snapshot = book_message(
+            "subscribed/order_book",
+            serde_json::json!([{ "price": "3000.00", "size": "2.0" }]),
+        );
+        runtime.handle_ws_message(&snapshot).await.unwrap();
+
+        // As sent on `account_all_orders/{account_index}`: orders keyed by
+        // market index.
+        let update = serde_json::json!({
+            "channel": "account_all_orders:7",
+            "type": "update/account_all_orders",
+            "orders": {
+                "0": [{
+                    "order_index": 281475565888172i64,
+                    "client_order_index": 100,
+                    "order_id": "281475565888172",
+                    "market_index": 0,
+                    "owner_account_index": 7,
+                    "initial_base_amount": "1.000",
+                    "remaining_base_amount": "0.400",
+                    "filled_base_amount": "0.600",
+                    "filled_quote_amount": "1799.400",
+                    "price": "2999.00",
+                    "is_ask": false,
+                    "side": "buy",
+                    "type": "limit",
+                    "time_in_force": "good-till-time",
+                    "reduce_only": false,
+                    "status": "open",
+                    "timestamp": 1722339648
+                }]
+            }
+        });
+        runtime.handle_ws_message(&update).await.unwrap();
+
+        assert_eq!(runtime.strategy().updates, [OrderState::PartiallyFilled]);
+        let order = runtime.tracker().get(100).unwrap();
+        assert_eq!(order.order_index, Some(281475565888172));
+        assert_eq!(order.filled_base, Decimal::new(6, 1));
+    }
+
+    /// Reads the account subscription of one connection and returns its
+    /// auth token, then hangs up if `drop` is set.
+    async fn serve_subscription(listener: &tokio::net::TcpListener, drop: bool) -> String {
+        use futures::StreamExt;
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+        let request = loop {
+            if let tungstenite::Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                break serde_json::from_str::<Value>(&text).unwrap();
+            }
+        };
+        let token = request["params"]["auth"].as_str().unwrap().to_string();
+        if drop {
+            ws.close(None).await.unwrap();
+        } else {
+            tokio::spawn(async move { while ws.next().await.is_some() {} });
+        }
+        token
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_resubscribes_with_a_fresh_token() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = crate::config::Config::new()
+            .with_ws_url(format!("ws://{}", listener.local_addr().unwrap()))
+            .unwrap();
+        let minted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = minted.clone();
+        let auth = Arc::new(AuthTokenProvider::with_minter(move |_| {
+            let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(format!("token-{n}"))
+        }));
+
+        let server = tokio::spawn(async move {
+            let first = serve_subscription(&listener, true).await;
+            let second = serve_subscription(&listener, false).await;
+            (first, second)
+        });
+
+        let entry = Arc::new(RecordingEntry::default());
+        let mut runtime = StrategyRuntime::new(Quoter::default(), entry.clone())
+            .with_account_stream(7, auth)
+            .with_reconnect(3, std::time::Duration::from_millis(10));
+        let mut ws = WebSocketClient::new(config);
+        // The quoter's one-second timer stops the run on the second
+        // connection.
+        runtime.run(&mut ws).await.unwrap();
+
+        let (first, second) = server.await.unwrap();
+        assert_eq!((first.as_str(), second.as_str()), ("token-0", "token-1"));
+        assert!(runtime.strategy().stopped);
+        assert_eq!(entry.actions.lock().unwrap().last().unwrap(), "cancel_all");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_configured_reconnects() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = crate::config::Config::new()
+            .with_ws_url(format!("ws://{}", listener.local_addr().unwrap()))
+            .unwrap();
+        let auth = Arc::new(AuthTokenProvider::with_minter(|_| Ok("token".to_string())));
+        tokio::spawn(async move {
+            serve_subscription(&listener, true).await;
+            // Refuse every reconnect.
+            drop(listener);
+        });
+
+        let entry = Arc::new(RecordingEntry::default());
+        let mut runtime = StrategyRuntime::new(Quoter::default(), entry.clone())
+            .with_account_stream(7, auth)
+            .with_reconnect(2, std::time::Duration::from_millis(10));
+        let mut ws = WebSocketClient::new(config);
+        assert!(matches!(
+            runtime.run(&mut ws).await,
+            Err(LighterError::WebSocket(_))
+        ));
+        assert!(runtime.strategy().stopped);
+    }
+
+    #[test]
+    fn parses_trades_and_channels() {
+        let mut runtime =
+            StrategyRuntime::new(Quoter::default(), Arc::new(RecordingEntry::default()));
+        runtime.markets.insert(
+            0,
+            MarketInfo {
+                market_id: 0,
+                symbol: "ETH".to_string(),
+                ..Default::default()
+            },
+        );
+        let message = serde_json::json!({
+            "type": "update/trade",
+            "channel": "trade:0",
+            "trades": [{
+                "trade_id": 9,
+                "market_id": 0,
+                "price": "3000.50",
+                "size": "0.25",
+                "is_maker_ask": false,
+                "timestamp": 1722339648000i64
+            }]
+        });
+        let trades = runtime.parse_trades(&message);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].taker_side, Side::Sell);
+        assert_eq!(trades[0].price, Decimal::new(300_050, 2));
+        assert_eq!(trades[0].timestamp.timestamp(), 1_722_339_648);
+        assert_eq!(
+            channel_market_id(&serde_json::json!({ "channel": "order_book/12" })),
+            Some(12)
+        );
+    }
+}
@@ -0,0 +1,501 @@
+use super::{
+    conform_order, normalise_client_order_index, parse_order_index, ClientOrderId,
+    LighterFfiTradingClient, OrderExpiry, OrderSpec, RiskOrder,
+};
+use crate::api::transaction_api::{BatchTx, BatchTxResponse, MAX_BATCH_SIZE};
+use crate::error::{LighterError, Result};
+use crate::metadata::MarketInfo;
+use crate::models::common::OrderType;
+use crate::models::order::TimeInForce;
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+/// One order-entry action within a batch.
+#[derive(Debug, Clone)]
+pub enum BatchOrderRequest {
+    Create {
+        symbol: String,
+        is_buy: bool,
+        base_amount: Decimal,
+        limit_price: Decimal,
+        reduce_only: bool,
+        time_in_force: TimeInForce,
+        client_order_id: Option<ClientOrderId>,
+    },
+    Cancel {
+        symbol: String,
+        order_id: String,
+    },
+    Modify {
+        symbol: String,
+        order_id: String,
+        new_size: Decimal,
+        new_price: Decimal,
+    },
+}
+
+/// Result of a single batch item, in request order.
+#[derive(Debug)]
+pub enum BatchItemOutcome {
+    /// Signed and accepted as part of the batch.
+    Submitted { tx_hash: Option<String> },
+    /// Failed local validation; it was never signed and used no nonce.
+    Rejected(LighterError),
+}
+
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub items: Vec<BatchItemOutcome>,
+    /// Exchange response, absent when every item was rejected locally.
+    pub response: Option<BatchTxResponse>,
+}
+
+impl BatchOutcome {
+    /// Whether every item made it into the submitted batch.
+    pub fn is_complete(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| matches!(item, BatchItemOutcome::Submitted { .. }))
+    }
+
+    /// Indices and errors of items that were not submitted.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &LighterError)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| match item {
+                BatchItemOutcome::Rejected(err) => Some((index, err)),
+                BatchItemOutcome::Submitted { .. } => None,
+            })
+    }
+}
+
+/// Scaled and validated batch item awaiting its nonce.
+enum PreparedTx {
+    Create {
+        info: MarketInfo,
+        spec: OrderSpec,
+        client_id: Option<ClientOrderId>,
+    },
+    Cancel {
+        market_index: i32,
+        order_index: i64,
+    },
+    Modify(BatchTx),
+}
+
+/// A batch ready to be signed: item outcomes so far, and the transactions
+/// of the accepted items numbered from `first_nonce`.
+struct PlannedBatch {
+    items: Vec<BatchItemOutcome>,
+    txs: Vec<BatchTx>,
+    first_nonce: i64,
+    created: Vec<(MarketInfo, i64, Option<ClientOrderId>)>,
+}
+
+/// Risk check deferred until the whole batch is prepared.
+struct RiskCheck {
+    info: MarketInfo,
+    order: RiskOrder,
+    new_order: bool,
+}
+
+impl PreparedTx {
+    fn into_batch_tx(self, nonce: i64) -> Result<BatchTx> {
+        let tx = match self {
+            Self::Create {
+                info,
+                spec,
+                client_id,
+            } => BatchTx::CreateOrder {
+                market_index: info.market_id,
+                client_order_index: match client_id {
+                    Some(client_id) => client_id.client_order_index()?,
+                    None => normalise_client_order_index(nonce)?,
+                },
+                base_amount: spec.amount,
+                price: spec.price,
+                is_ask: spec.is_ask,
+                order_type: spec.order_type,
+                time_in_force: spec.time_in_force,
+                reduce_only: spec.reduce_only,
+                trigger_price: spec.trigger_price,
+                order_expiry: spec.order_expiry,
+            },
+            Self::Cancel {
+                market_index,
+                order_index,
+            } => BatchTx::CancelOrder {
+                market_index,
+                client_cancel_index: normalise_client_order_index(nonce)?,
+                order_id: order_index.to_string(),
+            },
+            Self::Modify(tx) => tx,
+        };
+        Ok(tx)
+    }
+}
+
+impl LighterFfiTradingClient {
+    /// Sign up to [`MAX_BATCH_SIZE`] create/cancel/modify actions with
+    /// consecutive nonces and submit them in one request. Items that fail
+    /// local validation are reported individually and skipped; an exchange
+    /// rejection of the batch itself is returned as an error.
+    pub async fn submit_batch(&self, requests: Vec<BatchOrderRequest>) -> Result<BatchOutcome> {
+        let PlannedBatch {
+            mut items,
+            txs,
+            first_nonce,
+            created,
+        } = self.plan_batch(requests).await?;
+        if txs.is_empty() {
+            return Ok(BatchOutcome {
+                items,
+                response: None,
+            });
+        }
+
+        let response = self.transaction_api.submit_batch(&txs, first_nonce).await?;
+
+        for (info, client_order_index, client_id) in created {
+            self.register_order(&info, client_order_index, client_id);
+        }
+
+        let mut hashes = response.tx_hash.iter();
+        for item in items.iter_mut() {
+            if let BatchItemOutcome::Submitted { tx_hash } = item {
+                *tx_hash = hashes.next().cloned();
+            }
+        }
+
+        Ok(BatchOutcome {
+            items,
+            response: Some(response),
+        })
+    }
+
+    /// Validate, risk check and number every item, leaving only signing and
+    /// submission. Nonces are reserved only for the items that passed.
+    async fn plan_batch(&self, requests: Vec<BatchOrderRequest>) -> Result<PlannedBatch> {
+        if requests.is_empty() || requests.len() > MAX_BATCH_SIZE {
+            return Err(LighterError::OrderValidation(format!(
+                "order batch must contain between 1 and {MAX_BATCH_SIZE} requests, got {}",
+                requests.len()
+            )));
+        }
+
+        let mut slots = Vec::with_capacity(requests.len());
+        let mut items = Vec::with_capacity(requests.len());
+        let mut checks = Vec::new();
+        for (index, request) in requests.into_iter().enumerate() {
+            match self.prepare_batch_item(request).await {
+                Ok((tx, check)) => {
+                    slots.push(Some(tx));
+                    items.push(BatchItemOutcome::Submitted { tx_hash: None });
+                    checks.extend(check.map(|check| (index, check)));
+                }
+                Err(err) => {
+                    slots.push(None);
+                    items.push(BatchItemOutcome::Rejected(err));
+                }
+            }
+        }
+
+        // One risk check per market, so the account and book are fetched
+        // once and the batch's orders count against the limits together.
+        while let Some((_, first)) = checks.first() {
+            let key = (first.info.market_id, first.new_order);
+            let info = first.info.clone();
+            let (group, rest): (Vec<_>, Vec<_>) = checks
+                .into_iter()
+                .partition(|(_, check)| (check.info.market_id, check.new_order) == key);
+            checks = rest;
+            let orders: Vec<RiskOrder> = group.iter().map(|(_, check)| check.order).collect();
+            let results = self.check_risk(&info, &orders, key.1).await?;
+            for ((index, _), result) in group.into_iter().zip(results) {
+                if let Err(err) = result {
+                    slots[index] = None;
+                    items[index] = BatchItemOutcome::Rejected(err);
+                }
+            }
+        }
+        let prepared: Vec<PreparedTx> = slots.into_iter().flatten().collect();
+
+        if prepared.is_empty() {
+            return Ok(PlannedBatch {
+                items,
+                txs: Vec::new(),
+                first_nonce: 0,
+                created: Vec::new(),
+            });
+        }
+
+        let first_nonce = self.nonce_manager.reserve(prepared.len() as u64)? as i64;
+        let mut created = Vec::new();
+        let mut txs = Vec::with_capacity(prepared.len());
+        for (offset, tx) in prepared.into_iter().enumerate() {
+            let registration = match &tx {
+                PreparedTx::Create {
+                    info, client_id, ..
+                } => Some((info.clone(), client_id.clone())),
+                _ => None,
+            };
+            let tx = tx.into_batch_tx(first_nonce + offset as i64)?;
+            if let (
+                Some((info, client_id)),
+                BatchTx::CreateOrder {
+                    client_order_index, ..
+                },
+            ) = (registration, &tx)
+            {
+                created.push((info, *client_order_index, client_id));
+            }
+            txs.push(tx);
+        }
+
+        Ok(PlannedBatch {
+            items,
+            txs,
+            first_nonce,
+            created,
+        })
+    }
+
+    /// Validate and scale one item. Risk limits are returned to be checked
+    /// together with the rest of the batch.
+    async fn prepare_batch_item(
+        &self,
+        request: BatchOrderRequest,
+    ) -> Result<(PreparedTx, Option<RiskCheck>)> {
+        match request {
+            BatchOrderRequest::Create {
+                symbol,
+                is_buy,
+                base_amount,
+                limit_price,
+                reduce_only,
+                time_in_force,
+                client_order_id,
+            } => {
+                if let Some(client_id) = &client_order_id {
+                    client_id.client_order_index()?;
+                }
+                let info = self.market(&symbol).await?;
+                let (amount, price) = conform_order(
+                    &info,
+                    &base_amount,
+                    &limit_price,
+                    Some(!is_buy),
+                    self.rounding,
+                )?;
+                let spec = OrderSpec {
+                    amount,
+                    price,
+                    is_ask: !is_buy,
+                    order_type: OrderType::Limit,
+                    time_in_force,
+                    reduce_only,
+                    trigger_price: 0,
+                    order_expiry: OrderExpiry::Default.resolve(time_in_force, Utc::now())?,
+                };
+                let check = RiskCheck {
+                    order: RiskOrder::from_scaled(
+                        &info,
+                        amount,
+                        price,
+                        !is_buy,
+                        reduce_only,
+                        false,
+                    ),
+                    info: info.clone(),
+                    new_order: true,
+                };
+                let tx = PreparedTx::Create {
+                    info,
+                    spec,
+                    client_id: client_order_id,
+                };
+                Ok((tx, Some(check)))
+            }
+            BatchOrderRequest::Cancel { symbol, order_id } => {
+                let info = self.market(&symbol).await?;
+                let tx = PreparedTx::Cancel {
+                    market_index: info.market_id,
+                    order_index: parse_order_index(&order_id)?,
+                };
+                Ok((tx, None))
+            }
+            BatchOrderRequest::Modify {
+                symbol,
+                order_id,
+                new_size,
+                new_price,
+            } => {
+                let info = self.market(&symbol).await?;
+                let order_index = parse_order_index(&order_id)?;
+                let (base_amount, price) =
+                    conform_order(&info, &new_size, &new_price, None, self.rounding)?;
+                let check = RiskCheck {
+                    order: RiskOrder::from_scaled(&info, base_amount, price, false, false, false),
+                    info: info.clone(),
+                    new_order: false,
+                };
+                let tx = PreparedTx::Modify(BatchTx::ModifyOrder {
+                    market_index: info.market_id,
+                    order_index,
+                    base_amount,
+                    price: price as i64,
+                    trigger_price: 0,
+                });
+                Ok((tx, Some(check)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcome_reports_partial_failures() {
+        let outcome = BatchOutcome {
+            items: vec![
+                BatchItemOutcome::Submitted {
+                    tx_hash: Some("0xabc".to_string()),
+                },
+                BatchItemOutcome::Rejected(LighterError::OrderValidation("bad size".to_string())),
+            ],
+            response: None,
+        };
+
+        assert!(!outcome.is_complete());
+        let failures: Vec<_> = outcome.failures().map(|(index, _)| index).collect();
+        assert_eq!(failures, vec![1]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn plans_batches_with_grouped_risk_and_consecutive_nonces() {
+        use crate::config::Config;
+        use crate::trading::{RiskLimits, RiskViolation};
+        use serde_json::json;
+        use std::str::FromStr;
+
+        let mut server = mockito::Server::new_async().await;
+        let markets = json!({
+            "code": 200,
+            "order_books": [{
+                "market_id": 0,
+                "symbol": "ETH",
+                "supported_size_decimals": 4,
+                "supported_price_decimals": 2
+            }]
+        });
+        let _markets = server
+            .mock("GET", "/api/v1/orderBooks")
+            .with_status(200)
+            .with_body(markets.to_string())
+            .create_async()
+            .await;
+        let account = json!({
+            "code": 200,
+            "accounts": [{ "account_index": 7, "total_order_count": 1, "positions": [] }]
+        });
+        let account_mock = server
+            .mock("GET", "/api/v1/account")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(account.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let resting = json!({
+            "code": 200,
+            "orders": [{
+                "order_index": 900,
+                "client_order_index": 11,
+                "market_index": 0,
+                "is_ask": false,
+                "remaining_base_amount": "1.0000",
+                "status": "open"
+            }]
+        });
+        let resting_mock = server
+            .mock("GET", "/api/v1/accountActiveOrders")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(resting.to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = Config::new().with_base_url(server.url()).unwrap();
+        let client = LighterFfiTradingClient::unsigned(config, 7, 41)
+            .unwrap()
+            .with_risk_limits(RiskLimits::new().with_max_position(Decimal::from(2)));
+
+        let create = |is_buy: bool, size: &str| BatchOrderRequest::Create {
+            symbol: "ETH".to_string(),
+            is_buy,
+            base_amount: Decimal::from_str(size).unwrap(),
+            limit_price: Decimal::from(2500),
+            reduce_only: false,
+            time_in_force: TimeInForce::Gtc,
+            client_order_id: None,
+        };
+        let cancel = |order_id: &str| BatchOrderRequest::Cancel {
+            symbol: "ETH".to_string(),
+            order_id: order_id.to_string(),
+        };
+        let plan = client
+            .plan_batch(vec![
+                create(true, "0.5"),
+                cancel("not-an-index"),
+                // The resting bid and the first buy leave room for 0.5 more.
+                create(true, "1"),
+                create(false, "1"),
+                cancel("12345"),
+            ])
+            .await
+            .unwrap();
+
+        // One account and one resting-order fetch for the whole market.
+        account_mock.assert_async().await;
+        resting_mock.assert_async().await;
+
+        assert!(matches!(plan.items[0], BatchItemOutcome::Submitted { .. }));
+        assert!(matches!(
+            plan.items[1],
+            BatchItemOutcome::Rejected(LighterError::OrderValidation(_))
+        ));
+        assert!(matches!(
+            plan.items[2],
+            BatchItemOutcome::Rejected(LighterError::RiskLimit(RiskViolation::Position { .. }))
+        ));
+        assert!(matches!(plan.items[3], BatchItemOutcome::Submitted { .. }));
+        assert!(matches!(plan.items[4], BatchItemOutcome::Submitted { .. }));
+
+        // Only the three accepted items reserved nonces, in request order.
+        assert_eq!(plan.first_nonce, 41);
+        assert_eq!(client.nonce_manager.generate().unwrap(), 44);
+        assert_eq!(plan.txs.len(), 3);
+        assert!(matches!(
+            plan.txs[0],
+            BatchTx::CreateOrder { base_amount: 5_000, is_ask: false, client_order_index, .. }
+                if client_order_index == normalise_client_order_index(41).unwrap()
+        ));
+        assert!(matches!(
+            plan.txs[1],
+            BatchTx::CreateOrder { base_amount: 10_000, is_ask: true, client_order_index, .. }
+                if client_order_index == normalise_client_order_index(42).unwrap()
+        ));
+        assert!(matches!(
+            &plan.txs[2],
+            BatchTx::CancelOrder { order_id, client_cancel_index, .. }
+                if order_id == "12345"
+                    && *client_cancel_index == normalise_client_order_index(43).unwrap()
+        ));
+        assert_eq!(plan.created.len(), 2);
+    }
+}
@@ -0,0 +1,698 @@
+use super::{
+    conform_order, scale_trigger_price, ClientOrderId, LighterFfiTradingClient, OrderExpiry,
+    OrderOptions, OrderTransition, RiskOrder,
+};
+use crate::api::transaction_api::TxResponse;
+use crate::error::{LighterError, Result};
+use crate::metadata::MarketInfo;
+use crate::models::common::OrderType;
+use crate::models::order::{OrderGrouping, TimeInForce};
+use crate::signers::GroupedOrderLeg;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Take-profit or stop-loss child of a bracket. The child fires once the
+/// mark price crosses `trigger_price` and fills no worse than `limit_price`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BracketLeg {
+    pub trigger_price: Decimal,
+    pub limit_price: Decimal,
+}
+
+/// Entry order with optional take-profit and stop-loss children.
+#[derive(Debug, Clone)]
+pub struct BracketRequest {
+    /// Caller-chosen id; the orders of the group are tagged with it so they
+    /// can be found again after a restart.
+    pub group_id: String,
+    pub symbol: String,
+    pub is_buy: bool,
+    pub base_amount: Decimal,
+    pub entry_price: Decimal,
+    pub entry_time_in_force: TimeInForce,
+    pub take_profit: Option<BracketLeg>,
+    pub stop_loss: Option<BracketLeg>,
+}
+
+impl BracketRequest {
+    pub fn new(
+        group_id: impl Into<String>,
+        symbol: impl Into<String>,
+        is_buy: bool,
+        base_amount: Decimal,
+        entry_price: Decimal,
+    ) -> Self {
+        Self {
+            group_id: group_id.into(),
+            symbol: symbol.into(),
+            is_buy,
+            base_amount,
+            entry_price,
+            entry_time_in_force: TimeInForce::Gtc,
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+
+    pub fn with_entry_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.entry_time_in_force = time_in_force;
+        self
+    }
+
+    pub fn with_take_profit(mut self, trigger_price: Decimal, limit_price: Decimal) -> Self {
+        self.take_profit = Some(BracketLeg {
+            trigger_price,
+            limit_price,
+        });
+        self
+    }
+
+    pub fn with_stop_loss(mut self, trigger_price: Decimal, limit_price: Decimal) -> Self {
+        self.stop_loss = Some(BracketLeg {
+            trigger_price,
+            limit_price,
+        });
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.group_id.is_empty() {
+            return Err(LighterError::OrderValidation(
+                "bracket group id must not be empty".to_string(),
+            ));
+        }
+        if self.take_profit.is_none() && self.stop_loss.is_none() {
+            return Err(LighterError::OrderValidation(
+                "bracket needs a take-profit or a stop-loss".to_string(),
+            ));
+        }
+        // A long entry profits above the entry and stops out below it.
+        let above = |leg: &BracketLeg| leg.trigger_price > self.entry_price;
+        if self
+            .take_profit
+            .as_ref()
+            .is_some_and(|tp| above(tp) != self.is_buy)
+        {
+            return Err(LighterError::OrderValidation(format!(
+                "take-profit trigger is on the losing side of entry {}",
+                self.entry_price
+            )));
+        }
+        if self
+            .stop_loss
+            .as_ref()
+            .is_some_and(|sl| above(sl) == self.is_buy)
+        {
+            return Err(LighterError::OrderValidation(format!(
+                "stop-loss trigger is on the winning side of entry {}",
+                self.entry_price
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Who links the orders of a bracket together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BracketMode {
+    /// A single grouped-order transaction; the exchange places the children
+    /// on fill and cancels the survivor.
+    Native,
+    /// Separate orders linked by this client from fill events, for signer
+    /// builds without grouped-order support.
+    Emulated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BracketStatus {
+    /// The entry is still working; any part of it that filled is already
+    /// protected.
+    AwaitingEntry,
+    /// The entry is done and its children protect the position.
+    Protecting,
+    /// The position was exited, or the group ended without one.
+    Closed,
+}
+
+/// Take-profit or stop-loss of a placed bracket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BracketChild {
+    pub leg: BracketLeg,
+    /// Whether the child is on the book.
+    pub placed: bool,
+    /// Size still working on the book.
+    pub working: Decimal,
+    /// Base filled by the child so far.
+    pub filled: Decimal,
+}
+
+impl BracketChild {
+    fn new(leg: BracketLeg) -> Self {
+        Self {
+            leg,
+            placed: false,
+            working: Decimal::ZERO,
+            filled: Decimal::ZERO,
+        }
+    }
+}
+
+/// State of a placed bracket. It is serializable so it can be persisted and
+/// handed back to [`LighterFfiTradingClient::restore_bracket`] after a
+/// restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BracketGroup {
+    pub group_id: String,
+    pub symbol: String,
+    pub is_buy: bool,
+    pub base_amount: Decimal,
+    pub mode: BracketMode,
+    pub status: BracketStatus,
+    /// Children that have not been cancelled, rejected or finished.
+    pub take_profit: Option<BracketChild>,
+    pub stop_loss: Option<BracketChild>,
+    /// Base filled by the entry so far.
+    pub entry_filled: Decimal,
+    /// Base closed again by the children.
+    pub exited: Decimal,
+}
+
+/// Follow-up to a bracket transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BracketAction {
+    None,
+    /// The position is flat; cancel what is left.
+    Close,
+    /// Size the children to the open position.
+    Sync,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Entry,
+    TakeProfit,
+    StopLoss,
+}
+
+impl BracketGroup {
+    pub fn entry_id(&self) -> ClientOrderId {
+        leg_id(&self.group_id, Role::Entry)
+    }
+
+    pub fn take_profit_id(&self) -> ClientOrderId {
+        leg_id(&self.group_id, Role::TakeProfit)
+    }
+
+    pub fn stop_loss_id(&self) -> ClientOrderId {
+        leg_id(&self.group_id, Role::StopLoss)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.status == BracketStatus::Closed
+    }
+
+    /// Open position the children currently protect.
+    pub fn protected_amount(&self) -> Decimal {
+        (self.entry_filled - self.exited).max(Decimal::ZERO)
+    }
+
+    fn child_mut(&mut self, role: Role) -> Option<&mut Option<BracketChild>> {
+        match role {
+            Role::Entry => None,
+            Role::TakeProfit => Some(&mut self.take_profit),
+            Role::StopLoss => Some(&mut self.stop_loss),
+        }
+    }
+
+    /// Record a transition of one of the group's orders and decide what the
+    /// client has to do about it.
+    fn apply(&mut self, transition: &OrderTransition) -> BracketAction {
+        if self.is_closed() {
+            return BracketAction::None;
+        }
+        let order = &transition.order;
+        let Some(role) = self.role_of(order.client_order_index) else {
+            return BracketAction::None;
+        };
+        let terminal = transition.to.is_terminal();
+
+        if let Some(slot) = self.child_mut(role) {
+            let Some(child) = slot.as_mut() else {
+                return BracketAction::None;
+            };
+            let delta = (order.filled_base - child.filled).max(Decimal::ZERO);
+            child.filled += delta;
+            child.working = (child.working - delta).max(Decimal::ZERO);
+            if terminal {
+                *slot = None;
+            }
+            self.exited += delta;
+        } else {
+            self.entry_filled = self.entry_filled.max(order.filled_base);
+            if terminal {
+                self.status = BracketStatus::Protecting;
+            }
+        }
+
+        if self.protected_amount().is_zero()
+            && (self.status == BracketStatus::Protecting || !self.exited.is_zero())
+        {
+            // The entry ended without a position, or the children closed it.
+            return BracketAction::Close;
+        }
+        if self.status == BracketStatus::Protecting
+            && self.take_profit.is_none()
+            && self.stop_loss.is_none()
+        {
+            self.status = BracketStatus::Closed;
+            return BracketAction::None;
+        }
+        BracketAction::Sync
+    }
+
+    fn role_of(&self, client_order_index: i64) -> Option<Role> {
+        [Role::Entry, Role::TakeProfit, Role::StopLoss]
+            .into_iter()
+            .find(|role| {
+                leg_id(&self.group_id, *role)
+                    .client_order_index()
+                    .is_ok_and(|index| index == client_order_index)
+            })
+    }
+}
+
+fn leg_id(group_id: &str, role: Role) -> ClientOrderId {
+    let suffix = match role {
+        Role::Entry => "entry",
+        Role::TakeProfit => "tp",
+        Role::StopLoss => "sl",
+    };
+    ClientOrderId::Tag(format!("{group_id}:{suffix}"))
+}
+
+impl LighterFfiTradingClient {
+    /// Place an entry order with linked take-profit and stop-loss children.
+    /// Uses a native grouped-order transaction when the signer supports it
+    /// and falls back to client-side linking otherwise; emulated groups must
+    /// be driven with [`Self::advance_bracket`].
+    pub async fn place_bracket(&self, request: BracketRequest) -> Result<BracketGroup> {
+        request.validate()?;
+        let mode = if self.transaction_api.signer().supports_grouped_orders() {
+            BracketMode::Native
+        } else {
+            BracketMode::Emulated
+        };
+
+        match mode {
+            BracketMode::Native => {
+                self.submit_grouped_bracket(&request).await?;
+            }
+            BracketMode::Emulated => {
+                let options = OrderOptions::new()
+                    .with_client_order_id(leg_id(&request.group_id, Role::Entry));
+                self.create_limit_order_with_options(
+                    &request.symbol,
+                    request.is_buy,
+                    &request.base_amount,
+                    &request.entry_price,
+                    false,
+                    request.entry_time_in_force,
+                    options,
+                )
+                .await?;
+            }
+        }
+
+        Ok(BracketGroup {
+            group_id: request.group_id,
+            symbol: request.symbol,
+            is_buy: request.is_buy,
+            base_amount: request.base_amount,
+            mode,
+            status: BracketStatus::AwaitingEntry,
+            take_profit: request.take_profit.map(BracketChild::new),
+            stop_loss: request.stop_loss.map(BracketChild::new),
+            entry_filled: Decimal::ZERO,
+            exited: Decimal::ZERO,
+        })
+    }
+
+    /// Re-register a persisted bracket's orders so its children can be
+    /// cancelled and resized by client id again.
+    pub async fn restore_bracket(&self, group: &BracketGroup) -> Result<()> {
+        let info = self.market(&group.symbol).await?;
+        for id in [
+            group.entry_id(),
+            group.take_profit_id(),
+            group.stop_loss_id(),
+        ] {
+            let index = id.client_order_index()?;
+            self.register_order(&info, index, Some(id));
+        }
+        Ok(())
+    }
+
+    /// Feed an order transition (e.g. from an [`super::OrderTracker`]
+    /// callback) into a bracket. Transitions of unrelated orders are ignored.
+    ///
+    /// Emulated children are placed on the entry's first fill and resized to
+    /// the open position as the entry and the children fill, so a resting
+    /// entry is never partly unprotected. If placing or resizing fails the
+    /// error is returned with the group recording which children are on the
+    /// book; the next transition retries the rest.
+    pub async fn advance_bracket(
+        &self,
+        group: &mut BracketGroup,
+        transition: &OrderTransition,
+    ) -> Result<()> {
+        match group.apply(transition) {
+            BracketAction::None => Ok(()),
+            BracketAction::Close => {
+                self.close_bracket(group).await;
+                Ok(())
+            }
+            BracketAction::Sync => self.sync_children(group).await,
+        }
+    }
+
+    /// Cancel whatever is still working in a bracket.
+    pub async fn cancel_bracket(&self, group: &mut BracketGroup) -> Vec<Result<TxResponse>> {
+        let mut results = Vec::new();
+        if group.status == BracketStatus::AwaitingEntry {
+            results.push(self.cancel_order_by_client_id(group.entry_id()).await);
+        }
+        for (child, id) in [
+            (group.take_profit.take(), group.take_profit_id()),
+            (group.stop_loss.take(), group.stop_loss_id()),
+        ] {
+            if child.is_some_and(|child| child.placed) {
+                results.push(self.cancel_order_by_client_id(id).await);
+            }
+        }
+        group.status = BracketStatus::Closed;
+        results
+    }
+
+    /// Cancel the rest of a bracket whose position is flat. Orders that are
+    /// already gone are not an error.
+    async fn close_bracket(&self, group: &mut BracketGroup) {
+        if group.mode == BracketMode::Native {
+            // The exchange cancels the surviving child itself.
+            group.take_profit = None;
+            group.stop_loss = None;
+        }
+        let _ = self.cancel_bracket(group).await;
+    }
+
+    /// Bring every child in line with the open position: native children
+    /// are sized by the exchange, emulated ones are placed or amended here.
+    async fn sync_children(&self, group: &mut BracketGroup) -> Result<()> {
+        let target = group.protected_amount();
+        if target.is_zero() {
+            return Ok(());
+        }
+        let mode = group.mode;
+        let symbol = group.symbol.clone();
+        let group_id = group.group_id.clone();
+        let is_buy = group.is_buy;
+        let exits = [
+            (
+                &mut group.take_profit,
+                OrderType::TakeProfit,
+                Role::TakeProfit,
+            ),
+            (&mut group.stop_loss, OrderType::StopLoss, Role::StopLoss),
+        ];
+        for (slot, order_type, role) in exits {
+            let Some(child) = slot.as_mut() else {
+                continue;
+            };
+            if child.placed && child.working == target {
+                continue;
+            }
+            if mode == BracketMode::Emulated {
+                let id = leg_id(&group_id, role);
+                let leg = child.leg;
+                if child.placed {
+                    self.modify_order_by_client_id(
+                        id,
+                        &target,
+                        &leg.limit_price,
+                        Some(&leg.trigger_price),
+                    )
+                    .await?;
+                } else {
+                    self.create_trigger_order(
+                        &symbol,
+                        order_type,
+                        !is_buy,
+                        &target,
+                        &leg.trigger_price,
+                        &leg.limit_price,
+                        true,
+                        Some(id),
+                    )
+                    .await?;
+                }
+            }
+            child.placed = true;
+            child.working = target;
+        }
+        Ok(())
+    }
+
+    async fn submit_grouped_bracket(&self, request: &BracketRequest) -> Result<TxResponse> {
+        let info = self.market(&request.symbol).await?;
+        let (amount, price) = conform_order(
+            &info,
+            &request.base_amount,
+            &request.entry_price,
+            Some(!request.is_buy),
+            self.rounding,
+        )?;
+        let mut legs = vec![GroupedOrderLeg {
+            market_index: info.market_id,
+            client_order_index: leg_id(&request.group_id, Role::Entry).client_order_index()?,
+            base_amount: amount,
+            price,
+            is_ask: !request.is_buy,
+            order_type: OrderType::Limit,
+            time_in_force: request.entry_time_in_force,
+            reduce_only: false,
+            trigger_price: 0,
+            order_expiry: OrderExpiry::Default.resolve(request.entry_time_in_force, Utc::now())?,
+        }];
+        let exits = [
+            (request.take_profit, OrderType::TakeProfit, Role::TakeProfit),
+            (request.stop_loss, OrderType::StopLoss, Role::StopLoss),
+        ];
+        for (leg, order_type, role) in exits {
+            if let Some(leg) = leg {
+                legs.push(child_leg(
+                    &info,
+                    request,
+                    &leg,
+                    order_type,
+                    role,
+                    self.rounding,
+                )?);
+            }
+        }
+        let grouping = if legs.len() == 3 {
+            OrderGrouping::OneTriggersOneCancelsOther
+        } else {
+            OrderGrouping::OneTriggersOther
+        };
+
+        let risk_order =
+            RiskOrder::from_scaled(&info, amount, price, !request.is_buy, false, false);
+        self.enforce_risk(&info, &[risk_order], true).await?;
+        let nonce = self.nonce_manager.generate()? as i64;
+        let response = self
+            .transaction_api
+            .create_grouped_orders(grouping, &legs, nonce)
+            .await?;
+
+        for role in [Role::Entry, Role::TakeProfit, Role::StopLoss] {
+            let id = leg_id(&request.group_id, role);
+            self.register_order(&info, id.client_order_index()?, Some(id));
+        }
+        Ok(response)
+    }
+}
+
+/// Reduce-only exit sized from the entry's fill by the exchange.
+fn child_leg(
+    info: &MarketInfo,
+    request: &BracketRequest,
+    leg: &BracketLeg,
+    order_type: OrderType,
+    role: Role,
+    rounding: super::RoundingPolicy,
+) -> Result<GroupedOrderLeg> {
+    let (_, price) = conform_order(
+        info,
+        &request.base_amount,
+        &leg.limit_price,
+        Some(request.is_buy),
+        rounding,
+    )?;
+    Ok(GroupedOrderLeg {
+        market_index: info.market_id,
+        client_order_index: leg_id(&request.group_id, role).client_order_index()?,
+        base_amount: 0,
+        price,
+        is_ask: request.is_buy,
+        order_type,
+        time_in_force: TimeInForce::Ioc,
+        reduce_only: true,
+        trigger_price: scale_trigger_price(info, &leg.trigger_price)?,
+        order_expiry: OrderExpiry::Default.resolve_trigger(Utc::now())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::{OrderState, TrackedOrder};
+
+    fn long_bracket() -> BracketRequest {
+        BracketRequest::new("eth-1", "ETH", true, Decimal::ONE, Decimal::from(3_000))
+            .with_take_profit(Decimal::from(3_300), Decimal::from(3_290))
+            .with_stop_loss(Decimal::from(2_900), Decimal::from(2_880))
+    }
+
+    #[test]
+    fn validates_child_sides() {
+        assert!(long_bracket().validate().is_ok());
+
+        let inverted =
+            BracketRequest::new("eth-2", "ETH", true, Decimal::ONE, Decimal::from(3_000))
+                .with_take_profit(Decimal::from(2_900), Decimal::from(2_890));
+        assert!(matches!(
+            inverted.validate(),
+            Err(LighterError::OrderValidation(_))
+        ));
+
+        let short = BracketRequest::new("eth-3", "ETH", false, Decimal::ONE, Decimal::from(3_000))
+            .with_take_profit(Decimal::from(2_700), Decimal::from(2_710))
+            .with_stop_loss(Decimal::from(3_100), Decimal::from(3_120));
+        assert!(short.validate().is_ok());
+
+        let bare = BracketRequest::new("eth-4", "ETH", true, Decimal::ONE, Decimal::from(3_000));
+        assert!(bare.validate().is_err());
+    }
+
+    #[test]
+    fn identifies_legs_by_client_index() {
+        let group = BracketGroup {
+            group_id: "eth-1".to_string(),
+            symbol: "ETH".to_string(),
+            is_buy: true,
+            base_amount: Decimal::ONE,
+            mode: BracketMode::Emulated,
+            status: BracketStatus::AwaitingEntry,
+            take_profit: long_bracket().take_profit.map(BracketChild::new),
+            stop_loss: long_bracket().stop_loss.map(BracketChild::new),
+            entry_filled: Decimal::ZERO,
+            exited: Decimal::ZERO,
+        };
+        let sl = group.stop_loss_id().client_order_index().unwrap();
+        assert_eq!(group.role_of(sl), Some(Role::StopLoss));
+        assert_eq!(group.role_of(7), None);
+
+        let restored: BracketGroup =
+            serde_json::from_str(&serde_json::to_string(&group).unwrap()).unwrap();
+        assert_eq!(restored, group);
+    }
+
+    fn transition(
+        group: &BracketGroup,
+        role: Role,
+        to: OrderState,
+        filled: i64,
+    ) -> OrderTransition {
+        let order = TrackedOrder {
+            client_order_index: leg_id(&group.group_id, role).client_order_index().unwrap(),
+            order_index: Some(1),
+            market_index: 0,
+            state: to,
+            is_ask: role != Role::Entry,
+            filled_base: Decimal::new(filled, 1),
+            filled_quote: Decimal::ZERO,
+            tx_hash: None,
+        };
+        OrderTransition {
+            from: OrderState::Acknowledged,
+            to,
+            order,
+        }
+    }
+
+    fn emulated_group() -> BracketGroup {
+        let request = long_bracket();
+        BracketGroup {
+            group_id: request.group_id,
+            symbol: request.symbol,
+            is_buy: true,
+            base_amount: Decimal::ONE,
+            mode: BracketMode::Emulated,
+            status: BracketStatus::AwaitingEntry,
+            take_profit: request.take_profit.map(BracketChild::new),
+            stop_loss: request.stop_loss.map(BracketChild::new),
+            entry_filled: Decimal::ZERO,
+            exited: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn protects_partial_entry_fills() {
+        let mut group = emulated_group();
+        let partial = transition(&group, Role::Entry, OrderState::PartiallyFilled, 4);
+        assert_eq!(group.apply(&partial), BracketAction::Sync);
+        assert_eq!(group.status, BracketStatus::AwaitingEntry);
+        assert_eq!(group.protected_amount(), Decimal::new(4, 1));
+
+        let filled = transition(&group, Role::Entry, OrderState::Filled, 10);
+        assert_eq!(group.apply(&filled), BracketAction::Sync);
+        assert_eq!(group.status, BracketStatus::Protecting);
+        assert_eq!(group.protected_amount(), Decimal::ONE);
+
+        let mut unfilled = emulated_group();
+        let cancelled = transition(&unfilled, Role::Entry, OrderState::Cancelled, 0);
+        assert_eq!(unfilled.apply(&cancelled), BracketAction::Close);
+    }
+
+    #[test]
+    fn partial_exit_resizes_sibling() {
+        let mut group = emulated_group();
+        let filled = transition(&group, Role::Entry, OrderState::Filled, 10);
+        group.apply(&filled);
+        for child in [&mut group.take_profit, &mut group.stop_loss] {
+            let child = child.as_mut().unwrap();
+            child.placed = true;
+            child.working = Decimal::ONE;
+        }
+
+        let partial = transition(&group, Role::TakeProfit, OrderState::PartiallyFilled, 3);
+        assert_eq!(group.apply(&partial), BracketAction::Sync);
+        assert!(!group.is_closed());
+        assert!(group.stop_loss.is_some());
+        assert_eq!(group.protected_amount(), Decimal::new(7, 1));
+        assert_eq!(group.take_profit.unwrap().working, Decimal::new(7, 1));
+
+        let done = transition(&group, Role::TakeProfit, OrderState::Filled, 10);
+        assert_eq!(group.apply(&done), BracketAction::Close);
+        assert!(group.take_profit.is_none());
+    }
+
+    #[test]
+    fn exit_fill_during_entry_closes_group() {
+        let mut group = emulated_group();
+        let partial = transition(&group, Role::Entry, OrderState::PartiallyFilled, 5);
+        group.apply(&partial);
+        let stop = transition(&group, Role::StopLoss, OrderState::Filled, 5);
+        assert_eq!(group.apply(&stop), BracketAction::Close);
+    }
+}
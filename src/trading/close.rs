@@ -0,0 +1,206 @@
+use super::{LighterFfiTradingClient, SubmittedOrder};
+use crate::error::{LighterError, Result};
+use crate::ffi_client::{fetch_account_snapshot, fetch_order_book_orders, AccountPosition};
+use crate::metadata::MarketInfo;
+use crate::models::order::TimeInForce;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Slippage allowed by [`LighterFfiTradingClient::close_position`].
+pub const DEFAULT_CLOSE_SLIPPAGE_BPS: u32 = 50;
+
+/// How a closing order is sent to the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseStyle {
+    /// Reduce-only market order priced from the book, see
+    /// [`LighterFfiTradingClient::create_market_order_with_slippage`].
+    Market { max_slippage_bps: u32 },
+    /// Reduce-only good-till-cancel limit placed `offset_bps` through the
+    /// opposite touch; any remainder keeps working on the book.
+    AggressiveLimit { offset_bps: u32 },
+}
+
+impl Default for CloseStyle {
+    fn default() -> Self {
+        Self::Market {
+            max_slippage_bps: DEFAULT_CLOSE_SLIPPAGE_BPS,
+        }
+    }
+}
+
+impl LighterFfiTradingClient {
+    /// Reduce the position in `symbol` by `fraction` (`1` closes it) with a
+    /// slippage-bounded market order. Returns `None` when already flat.
+    pub async fn close_position(
+        &self,
+        symbol: &str,
+        fraction: Decimal,
+    ) -> Result<Option<SubmittedOrder>> {
+        self.close_position_with(symbol, fraction, CloseStyle::default())
+            .await
+    }
+
+    pub async fn close_position_with(
+        &self,
+        symbol: &str,
+        fraction: Decimal,
+        style: CloseStyle,
+    ) -> Result<Option<SubmittedOrder>> {
+        if fraction <= Decimal::ZERO || fraction > Decimal::ONE {
+            return Err(LighterError::OrderValidation(format!(
+                "close fraction {fraction} must be in (0, 1]"
+            )));
+        }
+        let info = self.market(symbol).await?;
+        let account = fetch_account_snapshot(&self.api_client, self.account_index).await?;
+        let Some(position) = account
+            .positions
+            .iter()
+            .find(|position| position.market_id == info.market_id)
+        else {
+            return Ok(None);
+        };
+        self.close(&info, position, fraction, style).await
+    }
+
+    /// Flatten every open position, continuing past individual failures.
+    pub async fn close_all_positions(
+        &self,
+        style: CloseStyle,
+    ) -> Result<Vec<(String, Result<SubmittedOrder>)>> {
+        let account = fetch_account_snapshot(&self.api_client, self.account_index).await?;
+        let mut results = Vec::new();
+        for position in &account.positions {
+            if position.signed_size().is_none_or(|size| size.is_zero()) {
+                continue;
+            }
+            let result = match self.market(&position.symbol).await {
+                Ok(info) => self.close(&info, position, Decimal::ONE, style).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(Some(order)) => results.push((position.symbol.clone(), Ok(order))),
+                Ok(None) => {}
+                Err(err) => results.push((position.symbol.clone(), Err(err))),
+            }
+        }
+        Ok(results)
+    }
+
+    async fn close(
+        &self,
+        info: &MarketInfo,
+        position: &AccountPosition,
+        fraction: Decimal,
+        style: CloseStyle,
+    ) -> Result<Option<SubmittedOrder>> {
+        let signed = position.signed_size().ok_or_else(|| {
+            LighterError::AccountState(format!(
+                "unparseable {} position size: {}",
+                position.symbol, position.size
+            ))
+        })?;
+        if signed.is_zero() {
+            return Ok(None);
+        }
+        let size = close_size(info, signed, fraction)?;
+        // A long is closed by selling, a short by buying.
+        let is_buy = signed < Decimal::ZERO;
+
+        let order = match style {
+            CloseStyle::Market { max_slippage_bps } => {
+                self.create_market_order_with_slippage(
+                    &info.symbol,
+                    is_buy,
+                    &size,
+                    max_slippage_bps,
+                    true,
+                )
+                .await?
+            }
+            CloseStyle::AggressiveLimit { offset_bps } => {
+                let book = fetch_order_book_orders(&self.api_client, info.market_id, 1).await?;
+                let touch = if is_buy {
+                    book.best_ask()
+                } else {
+                    book.best_bid()
+                }
+                .ok_or_else(|| {
+                    LighterError::OrderValidation(format!(
+                        "no resting liquidity to close {} against",
+                        info.symbol
+                    ))
+                })?;
+                let price = aggressive_price(info, touch, is_buy, offset_bps);
+                self.create_limit_order(&info.symbol, is_buy, &size, &price, true, TimeInForce::Gtc)
+                    .await?
+            }
+        };
+        Ok(Some(order))
+    }
+}
+
+/// Size of a closing order, rounded down to the market's size step.
+fn close_size(info: &MarketInfo, signed: Decimal, fraction: Decimal) -> Result<Decimal> {
+    let size = (signed.abs() * fraction).round_dp_with_strategy(
+        info.supported_size_decimals.unwrap_or(0),
+        RoundingStrategy::ToZero,
+    );
+    if size.is_zero() {
+        return Err(LighterError::OrderValidation(format!(
+            "closing {fraction} of {signed} {} rounds to zero",
+            info.symbol
+        )));
+    }
+    Ok(size)
+}
+
+fn aggressive_price(info: &MarketInfo, touch: Decimal, is_buy: bool, offset_bps: u32) -> Decimal {
+    let offset = Decimal::from(offset_bps) / Decimal::from(10_000);
+    let (price, strategy) = if is_buy {
+        (
+            touch * (Decimal::ONE + offset),
+            RoundingStrategy::AwayFromZero,
+        )
+    } else {
+        (touch * (Decimal::ONE - offset), RoundingStrategy::ToZero)
+    };
+    price.round_dp_with_strategy(info.supported_price_decimals.unwrap_or(0), strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth() -> MarketInfo {
+        MarketInfo {
+            market_id: 0,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(2),
+            supported_price_decimals: Some(2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sizes_close_from_signed_position() {
+        let info = eth();
+        assert_eq!(
+            close_size(&info, Decimal::new(-125, 2), Decimal::ONE).unwrap(),
+            Decimal::new(125, 2)
+        );
+        assert_eq!(
+            close_size(&info, Decimal::new(125, 2), Decimal::new(5, 1)).unwrap(),
+            Decimal::new(62, 2)
+        );
+        assert!(close_size(&info, Decimal::new(1, 2), Decimal::new(5, 1)).is_err());
+    }
+
+    #[test]
+    fn aggressive_price_crosses_the_touch() {
+        let info = eth();
+        let buy = aggressive_price(&info, Decimal::from(3_000), true, 25);
+        assert_eq!(buy, Decimal::new(300_750, 2));
+        let sell = aggressive_price(&info, Decimal::new(300_001, 2), false, 25);
+        assert_eq!(sell, Decimal::new(299_250, 2));
+    }
+}
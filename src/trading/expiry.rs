@@ -0,0 +1,163 @@
+use crate::error::{LighterError, Result};
+use crate::models::order::TimeInForce;
+use chrono::{DateTime, Days, Duration, Utc};
+
+/// Shortest good-till-time window the exchange accepts.
+pub const MIN_ORDER_EXPIRY: Duration = Duration::minutes(5);
+/// Longest good-till-time window the exchange accepts.
+pub const MAX_ORDER_EXPIRY: Duration = Duration::days(30);
+
+/// Expiry sentinel that lets the signer apply its default 28-day expiry.
+const SIGNER_DEFAULT_EXPIRY: i64 = -1;
+/// Expiry value for orders that never rest on the book.
+const IOC_EXPIRY: i64 = 0;
+
+/// When a resting order should be removed by the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrderExpiry {
+    /// Use the exchange default for the order's time in force.
+    #[default]
+    Default,
+    /// Expire at an absolute point in time.
+    At(DateTime<Utc>),
+    /// Expire once the given duration has elapsed from submission.
+    After(Duration),
+}
+
+impl From<DateTime<Utc>> for OrderExpiry {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::At(value)
+    }
+}
+
+impl From<Duration> for OrderExpiry {
+    fn from(value: Duration) -> Self {
+        Self::After(value)
+    }
+}
+
+impl From<std::time::Duration> for OrderExpiry {
+    fn from(value: std::time::Duration) -> Self {
+        Self::After(Duration::from_std(value).unwrap_or(Duration::MAX))
+    }
+}
+
+impl OrderExpiry {
+    /// Resolve the expiry into the millisecond timestamp the signer expects,
+    /// validating it against the exchange's allowed window.
+    pub fn resolve(&self, time_in_force: TimeInForce, now: DateTime<Utc>) -> Result<i64> {
+        if time_in_force == TimeInForce::Ioc {
+            return match self {
+                Self::Default => Ok(IOC_EXPIRY),
+                _ => Err(LighterError::OrderValidation(
+                    "immediate-or-cancel orders cannot carry an expiry".to_string(),
+                )),
+            };
+        }
+
+        let expires_at = match self {
+            Self::Default if time_in_force == TimeInForce::Day => end_of_day(now),
+            Self::Default => return Ok(SIGNER_DEFAULT_EXPIRY),
+            Self::At(at) => *at,
+            Self::After(after) => now.checked_add_signed(*after).ok_or_else(|| {
+                LighterError::OrderValidation(format!("order expiry {after} is out of range"))
+            })?,
+        };
+
+        let window = expires_at - now;
+        if window < MIN_ORDER_EXPIRY || window > MAX_ORDER_EXPIRY {
+            return Err(LighterError::OrderValidation(format!(
+                "order expiry {expires_at} must be between {} minutes and {} days from now",
+                MIN_ORDER_EXPIRY.num_minutes(),
+                MAX_ORDER_EXPIRY.num_days()
+            )));
+        }
+
+        Ok(expires_at.timestamp_millis())
+    }
+
+    /// Resolve the expiry of a stop-loss or take-profit order. Triggers rest
+    /// until armed even when they execute IOC, so they take the expiries of
+    /// resting orders rather than `0`.
+    pub fn resolve_trigger(&self, now: DateTime<Utc>) -> Result<i64> {
+        self.resolve(TimeInForce::Gtc, now)
+    }
+}
+
+fn end_of_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+        .unwrap_or(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn resolves_relative_and_absolute_expiry() {
+        let now = now();
+        let relative = OrderExpiry::from(Duration::hours(1))
+            .resolve(TimeInForce::Gtc, now)
+            .unwrap();
+        assert_eq!(relative, (now + Duration::hours(1)).timestamp_millis());
+
+        let at = now + Duration::days(2);
+        let absolute = OrderExpiry::from(at).resolve(TimeInForce::Po, now).unwrap();
+        assert_eq!(absolute, at.timestamp_millis());
+    }
+
+    #[test]
+    fn defaults_follow_time_in_force() {
+        let now = now();
+        assert_eq!(
+            OrderExpiry::Default.resolve(TimeInForce::Ioc, now).unwrap(),
+            IOC_EXPIRY
+        );
+        assert_eq!(
+            OrderExpiry::Default.resolve(TimeInForce::Gtc, now).unwrap(),
+            SIGNER_DEFAULT_EXPIRY
+        );
+        let midnight = Utc.with_ymd_and_hms(2024, 5, 2, 0, 0, 0).unwrap();
+        assert_eq!(
+            OrderExpiry::Default.resolve(TimeInForce::Day, now).unwrap(),
+            midnight.timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn rejects_expiry_outside_window() {
+        let now = now();
+        for expiry in [
+            OrderExpiry::After(Duration::minutes(1)),
+            OrderExpiry::After(Duration::days(31)),
+            OrderExpiry::At(now - Duration::hours(1)),
+        ] {
+            assert!(matches!(
+                expiry.resolve(TimeInForce::Gtc, now),
+                Err(LighterError::OrderValidation(_))
+            ));
+        }
+        assert!(OrderExpiry::After(Duration::hours(1))
+            .resolve(TimeInForce::Ioc, now)
+            .is_err());
+    }
+
+    #[test]
+    fn trigger_orders_rest_until_armed() {
+        assert_eq!(
+            OrderExpiry::Default.resolve_trigger(now()).unwrap(),
+            SIGNER_DEFAULT_EXPIRY
+        );
+        assert!(OrderExpiry::After(Duration::hours(1))
+            .resolve_trigger(now())
+            .is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+use super::{scale_usdc, LighterFfiTradingClient};
+use crate::api::transaction_api::TxResponse;
+use crate::error::{LighterError, Result};
+use crate::ffi_client::{fetch_account_snapshot, AccountPosition};
+use crate::metadata::MarketInfo;
+use crate::models::account::MarginType;
+use rust_decimal::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Initial margin fractions are signed in basis points.
+const MARGIN_FRACTION_SCALE: i64 = 10_000;
+
+impl LighterFfiTradingClient {
+    /// Set the leverage of `symbol` and switch it to cross or isolated
+    /// margin; Lighter changes both with one transaction. Leverage above
+    /// the market's maximum is rejected before signing.
+    pub async fn update_leverage(
+        &self,
+        symbol: &str,
+        leverage: Decimal,
+        margin_type: MarginType,
+    ) -> Result<TxResponse> {
+        let info = self.margin_market(symbol).await?;
+        let fraction = initial_margin_fraction(&info, leverage)?;
+        let nonce = self.nonce_manager.generate()? as i64;
+        self.transaction_api
+            .update_leverage(info.market_id, fraction, margin_type, nonce)
+            .await
+    }
+
+    /// Move `amount` USDC of collateral into the isolated position in
+    /// `symbol`.
+    pub async fn add_isolated_margin(&self, symbol: &str, amount: Decimal) -> Result<TxResponse> {
+        self.update_isolated_margin(symbol, amount, true).await
+    }
+
+    /// Release `amount` USDC from the isolated position in `symbol`. The
+    /// position must keep at least its initial margin.
+    pub async fn remove_isolated_margin(
+        &self,
+        symbol: &str,
+        amount: Decimal,
+    ) -> Result<TxResponse> {
+        self.update_isolated_margin(symbol, amount, false).await
+    }
+
+    async fn update_isolated_margin(
+        &self,
+        symbol: &str,
+        amount: Decimal,
+        add: bool,
+    ) -> Result<TxResponse> {
+        let scaled = scale_usdc(amount)?;
+        let info = self.market(symbol).await?;
+        let account = fetch_account_snapshot(&self.api_client, self.account_index).await?;
+        let position = account
+            .positions
+            .iter()
+            .find(|position| position.market_id == info.market_id)
+            .filter(|position| position.margin_type() == MarginType::Isolated)
+            .ok_or_else(|| {
+                LighterError::AccountState(format!("no isolated position in {}", info.symbol))
+            })?;
+        if !add {
+            let removable = removable_margin(position);
+            if amount > removable {
+                return Err(LighterError::OrderValidation(format!(
+                    "cannot remove {amount} USDC from {}: only {removable} exceeds its initial margin",
+                    info.symbol
+                )));
+            }
+        }
+
+        let nonce = self.nonce_manager.generate()? as i64;
+        self.transaction_api
+            .update_margin(info.market_id, scaled, add, nonce)
+            .await
+    }
+
+    /// Market metadata including margin fractions, which the plain market
+    /// listing omits. Fetched once and cached with the rest.
+    async fn margin_market(&self, symbol: &str) -> Result<MarketInfo> {
+        let info = self.market(symbol).await?;
+        if info.min_initial_margin_fraction.is_some() {
+            return Ok(info);
+        }
+
+        let details = self.metadata.fetch_market_details().await?;
+        let mut guard = self.markets.write().await;
+        for entry in details {
+            let key = entry.symbol.to_uppercase();
+            guard.insert(key, entry);
+        }
+        Ok(guard
+            .get(&info.symbol.to_uppercase())
+            .cloned()
+            .unwrap_or(info))
+    }
+}
+
+/// Initial margin fraction in basis points for `leverage`, rounded up so
+/// the leverage applied never exceeds the one asked for.
+fn initial_margin_fraction(info: &MarketInfo, leverage: Decimal) -> Result<i32> {
+    if leverage < Decimal::ONE {
+        return Err(LighterError::OrderValidation(format!(
+            "leverage {leverage} must be at least 1"
+        )));
+    }
+    let fraction = (Decimal::from(MARGIN_FRACTION_SCALE) / leverage)
+        .ceil()
+        .to_i32()
+        .unwrap_or(i32::MAX);
+    if let Some(min) = info.min_initial_margin_fraction {
+        if i64::from(fraction) < i64::from(min) {
+            return Err(LighterError::OrderValidation(format!(
+                "leverage {leverage} exceeds the {}x maximum of {}",
+                info.max_leverage().unwrap_or_default().normalize(),
+                info.symbol
+            )));
+        }
+    }
+    Ok(fraction)
+}
+
+/// Allocated margin above the initial margin the position's notional
+/// requires.
+fn removable_margin(position: &AccountPosition) -> Decimal {
+    let parse = |value: &str| Decimal::from_str(value).unwrap_or_default();
+    let allocated = parse(&position.allocated_margin);
+    let required = parse(&position.notional_value).abs() * parse(&position.initial_margin_fraction)
+        / Decimal::ONE_HUNDRED;
+    (allocated - required).max(Decimal::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn btc() -> MarketInfo {
+        MarketInfo {
+            market_id: 1,
+            symbol: "BTC".to_string(),
+            min_initial_margin_fraction: Some(200),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn converts_leverage_within_market_limits() {
+        assert_eq!(
+            initial_margin_fraction(&btc(), Decimal::from(10)).unwrap(),
+            1_000
+        );
+        assert_eq!(
+            initial_margin_fraction(&btc(), Decimal::from(50)).unwrap(),
+            200
+        );
+        // 10000 / 3 rounds up, so the applied leverage stays below 3x.
+        assert_eq!(
+            initial_margin_fraction(&btc(), Decimal::from(3)).unwrap(),
+            3_334
+        );
+        assert!(initial_margin_fraction(&btc(), Decimal::from(51)).is_err());
+        assert!(initial_margin_fraction(&btc(), Decimal::new(5, 1)).is_err());
+    }
+
+    #[test]
+    fn validates_margin_amounts() {
+        assert_eq!(scale_usdc(Decimal::new(125, 1)).unwrap(), 12_500_000);
+        assert!(scale_usdc(Decimal::ZERO).is_err());
+        assert!(scale_usdc(Decimal::new(1, 7)).is_err());
+
+        let position = AccountPosition {
+            market_id: 1,
+            symbol: "BTC".to_string(),
+            sign: -1,
+            size: "1".to_string(),
+            average_entry_price: "100".to_string(),
+            notional_value: "100".to_string(),
+            unrealized_pnl: String::new(),
+            realized_pnl: String::new(),
+            margin_mode: 1,
+            initial_margin_fraction: "20".to_string(),
+            allocated_margin: "35".to_string(),
+            open_order_count: 0,
+        };
+        assert_eq!(removable_margin(&position), Decimal::from(15));
+    }
+}
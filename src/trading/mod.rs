@@ -1,34 +1,131 @@
+use crate::auth::AuthTokenProvider;
 use crate::config::Config;
 use crate::error::{LighterError, Result};
+use crate::ffi_client::{
+    fetch_active_orders, fetch_inactive_orders, fetch_inactive_orders_page, AccountOrder,
+};
 use crate::metadata::{MarketInfo, MarketMetadata};
+use crate::models::common::OrderType;
+use crate::models::order::{CancelAllMode, TimeInForce};
 use crate::nonce::NonceManager;
-use crate::signers::FFISigner;
+use crate::signers::{FFISigner, SignedCreateOrder};
 use crate::{api::transaction_api::LighterTransactionApi, client::ApiClient};
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
+mod batch;
+mod bracket;
+mod close;
+mod expiry;
+mod leverage;
+mod portfolio;
+mod registry;
+mod risk;
+mod slippage;
+mod tracker;
+mod twap;
+mod validation;
+
+pub use batch::{BatchItemOutcome, BatchOrderRequest, BatchOutcome};
+pub use bracket::{
+    BracketChild, BracketGroup, BracketLeg, BracketMode, BracketRequest, BracketStatus,
+};
+pub use close::{CloseStyle, DEFAULT_CLOSE_SLIPPAGE_BPS};
+pub use expiry::{OrderExpiry, MAX_ORDER_EXPIRY, MIN_ORDER_EXPIRY};
+pub use portfolio::{
+    CostBasis, PnlSummary, PortfolioFill, PortfolioPosition, PortfolioTracker, PositionDiscrepancy,
+    DEFAULT_SEEN_TRADES_CAPACITY, UNATTRIBUTED,
+};
+pub use registry::{ClientOrderId, OrderRegistry, RegisteredOrder, DEFAULT_REGISTRY_CAPACITY};
+pub use risk::{RiskLimits, RiskViolation};
+pub use slippage::{estimate_market_fill, FillEstimate, SLIPPAGE_BOOK_DEPTH};
+pub use tracker::{OrderState, OrderTracker, OrderTransition, TrackedOrder};
+pub use twap::{TwapConfig, TwapHandle, TwapProgress, TwapSlice, TwapState};
+pub use validation::RoundingPolicy;
+
+use risk::{RiskGuard, RiskOrder};
+use validation::conform_order;
+
+/// Earliest a scheduled cancel-all may fire, relative to now.
+const MIN_SCHEDULED_CANCEL_ALL: Duration = Duration::minutes(5);
+/// Latest a scheduled cancel-all may fire, relative to now.
+const MAX_SCHEDULED_CANCEL_ALL: Duration = Duration::days(15);
+/// Decimals of USDC collateral amounts in signed transactions.
+const USDC_DECIMALS: u32 = 6;
+
 pub struct LighterFfiTradingClient {
     transaction_api: LighterTransactionApi,
+    api_client: ApiClient,
     metadata: MarketMetadata,
     markets: RwLock<HashMap<String, MarketInfo>>,
     nonce_manager: NonceManager,
+    account_index: i32,
+    registry: Mutex<OrderRegistry>,
+    rounding: RoundingPolicy,
+    risk: Option<RiskGuard>,
+}
+
+/// Optional order parameters beyond side, size and price.
+#[derive(Debug, Clone, Default)]
+pub struct OrderOptions {
+    pub expiry: OrderExpiry,
+    pub client_order_id: Option<ClientOrderId>,
+}
+
+impl OrderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_expiry(mut self, expiry: impl Into<OrderExpiry>) -> Self {
+        self.expiry = expiry.into();
+        self
+    }
+
+    pub fn with_client_order_id(mut self, client_order_id: impl Into<ClientOrderId>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+}
+
+/// Scaled order fields shared by every create-order path.
+#[derive(Debug, Clone, Copy)]
+struct OrderSpec {
+    amount: i64,
+    price: i32,
+    is_ask: bool,
+    order_type: OrderType,
+    time_in_force: TimeInForce,
+    reduce_only: bool,
+    trigger_price: i32,
+    order_expiry: i64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SubmittedOrder {
-    pub order: serde_json::Value,
+    pub order: SignedCreateOrder,
     pub response: crate::api::transaction_api::TxResponse,
 }
 
 impl SubmittedOrder {
-    pub fn order_id(&self) -> Option<&str> {
-        extract_str(&self.order, &["orderId", "order_id", "id"])
+    pub fn client_order_index(&self) -> i64 {
+        self.order.client_order_index
     }
 
-    pub fn client_order_id(&self) -> Option<&str> {
-        extract_str(&self.order, &["clientOrderId", "client_order_id"])
+    pub fn market_index(&self) -> i32 {
+        self.order.market_index
+    }
+
+    pub fn nonce(&self) -> i64 {
+        self.order.nonce
+    }
+
+    pub fn tx_hash(&self) -> Option<&str> {
+        self.response.tx_hash.as_deref()
     }
 }
 
@@ -53,17 +150,62 @@ impl LighterFfiTradingClient {
             .await?;
         let base_url = config.base_url.clone();
         let signing_url = build_signing_url(&base_url)?;
-        let signer = FFISigner::new(&signing_url, private_key, api_key_index, account_index)?;
-        let transaction_api = LighterTransactionApi::with_signer(api_client, signer);
+        let signer = Arc::new(FFISigner::new(
+            &signing_url,
+            private_key,
+            api_key_index,
+            account_index,
+        )?);
+        let auth = Arc::new(AuthTokenProvider::new(signer.clone()));
+        let api_client = api_client.with_auth_token_provider(auth);
+        let transaction_api = LighterTransactionApi::with_shared_signer(api_client.clone(), signer);
 
         Ok(Self {
             transaction_api,
+            api_client,
             metadata,
             markets: RwLock::new(markets),
             nonce_manager: NonceManager::with_seed(initial_nonce),
+            account_index,
+            registry: Mutex::new(OrderRegistry::default()),
+            rounding: RoundingPolicy::default(),
+            risk: None,
+        })
+    }
+
+    /// Client whose signer cannot sign and whose nonces start at
+    /// `next_nonce`, for tests of order preparation against a mock API.
+    #[cfg(all(test, unix))]
+    pub(crate) fn unsigned(config: Config, account_index: i32, next_nonce: u64) -> Result<Self> {
+        let api_client = ApiClient::new(config.clone())?;
+        let signer = Arc::new(FFISigner::unlinked(0, account_index));
+        Ok(Self {
+            transaction_api: LighterTransactionApi::with_shared_signer(api_client.clone(), signer),
+            api_client,
+            metadata: MarketMetadata::new(config)?,
+            markets: RwLock::new(HashMap::new()),
+            nonce_manager: NonceManager::with_seed(next_nonce),
+            account_index,
+            registry: Mutex::new(OrderRegistry::default()),
+            rounding: RoundingPolicy::default(),
+            risk: None,
         })
     }
 
+    /// Change how many client orders are remembered for lookups by client id.
+    pub fn with_registry_capacity(self, capacity: usize) -> Self {
+        Self {
+            registry: Mutex::new(OrderRegistry::new(capacity)),
+            ..self
+        }
+    }
+
+    /// Choose whether sizes and prices finer than the market's step and tick
+    /// are rejected (the default) or rounded before signing.
+    pub fn with_rounding_policy(self, rounding: RoundingPolicy) -> Self {
+        Self { rounding, ..self }
+    }
+
     pub async fn create_market_order(
         &self,
         symbol: &str,
@@ -72,113 +214,430 @@ impl LighterFfiTradingClient {
         limit_price: &Decimal,
         reduce_only: bool,
     ) -> Result<SubmittedOrder> {
+        self.create_market_order_with_options(
+            symbol,
+            is_buy,
+            base_amount,
+            limit_price,
+            reduce_only,
+            OrderOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn create_market_order_with_options(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+        options: OrderOptions,
+    ) -> Result<SubmittedOrder> {
+        let order_expiry = options.expiry.resolve(TimeInForce::Ioc, Utc::now())?;
         let info = self.market(symbol).await?;
-        let size_decimals = info.supported_size_decimals.unwrap_or(0);
-        let price_decimals = info.supported_price_decimals.unwrap_or(0);
-
-        let amount = scale_decimal(base_amount, size_decimals)
-            .ok_or_else(|| LighterError::Signing("unable to convert order size".to_string()))?;
-        if amount <= 0 {
-            return Err(LighterError::Signing(
-                "lighter order size must be positive".to_string(),
-            ));
-        }
+        let (amount, price) = conform_order(
+            &info,
+            base_amount,
+            limit_price,
+            Some(!is_buy),
+            self.rounding,
+        )?;
+        let spec = OrderSpec {
+            amount,
+            price,
+            is_ask: !is_buy,
+            order_type: OrderType::Market,
+            time_in_force: TimeInForce::Ioc,
+            reduce_only,
+            trigger_price: 0,
+            order_expiry,
+        };
+        self.submit_order(&info, spec, options.client_order_id)
+            .await
+    }
 
-        let price_scaled = scale_decimal(limit_price, price_decimals)
-            .ok_or_else(|| LighterError::Signing("unable to convert order price".to_string()))?;
-        if price_scaled <= 0 {
-            return Err(LighterError::Signing(
-                "lighter limit price must be positive".to_string(),
-            ));
-        }
-        let price: i32 = price_scaled.try_into().map_err(|_| {
-            LighterError::Signing("lighter limit price exceeds supported range".to_string())
-        })?;
+    pub async fn create_limit_order(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+        time_in_force: TimeInForce,
+    ) -> Result<SubmittedOrder> {
+        self.create_limit_order_with_expiry(
+            symbol,
+            is_buy,
+            base_amount,
+            limit_price,
+            reduce_only,
+            time_in_force,
+            OrderExpiry::Default,
+        )
+        .await
+    }
 
-        let is_ask = !is_buy;
-        let trigger_price = 0i32;
-        let order_expiry = 0i64;
-        let nonce = self.nonce_manager.generate()? as i64;
-        let client_order_index = normalise_client_order_index(nonce)?;
+    /// Limit order that the exchange removes at `expiry`, either an absolute
+    /// `DateTime<Utc>` or a `Duration` from now.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_limit_order_with_expiry(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+        time_in_force: TimeInForce,
+        expiry: impl Into<OrderExpiry>,
+    ) -> Result<SubmittedOrder> {
+        self.create_limit_order_with_options(
+            symbol,
+            is_buy,
+            base_amount,
+            limit_price,
+            reduce_only,
+            time_in_force,
+            OrderOptions::new().with_expiry(expiry),
+        )
+        .await
+    }
 
-        let (order, response) = self
-            .transaction_api
-            .create_order(
-                info.market_id,
-                client_order_index,
-                amount,
-                price,
-                is_ask,
-                crate::models::common::OrderType::Market,
-                crate::models::order::TimeInForce::Ioc,
-                reduce_only,
-                trigger_price,
-                order_expiry,
-                nonce,
-            )
-            .await?;
+    /// Limit order with an optional expiry and caller-supplied client id.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_limit_order_with_options(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+        time_in_force: TimeInForce,
+        options: OrderOptions,
+    ) -> Result<SubmittedOrder> {
+        let order_expiry = options.expiry.resolve(time_in_force, Utc::now())?;
+        let info = self.market(symbol).await?;
+        let (amount, price) = conform_order(
+            &info,
+            base_amount,
+            limit_price,
+            Some(!is_buy),
+            self.rounding,
+        )?;
+        let spec = OrderSpec {
+            amount,
+            price,
+            is_ask: !is_buy,
+            order_type: OrderType::Limit,
+            time_in_force,
+            reduce_only,
+            trigger_price: 0,
+            order_expiry,
+        };
+        self.submit_order(&info, spec, options.client_order_id)
+            .await
+    }
 
-        Ok(SubmittedOrder { order, response })
+    /// Stop-loss that executes as a market order once `trigger_price` is hit.
+    /// `limit_price` bounds the worst acceptable fill.
+    pub async fn create_stop_loss_order(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        trigger_price: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+    ) -> Result<SubmittedOrder> {
+        self.create_trigger_order(
+            symbol,
+            OrderType::StopLoss,
+            is_buy,
+            base_amount,
+            trigger_price,
+            limit_price,
+            reduce_only,
+            None,
+        )
+        .await
     }
 
-    pub async fn create_limit_order(
+    /// Stop-loss that rests as a limit order at `limit_price` once triggered.
+    pub async fn create_stop_loss_limit_order(
         &self,
         symbol: &str,
         is_buy: bool,
         base_amount: &Decimal,
+        trigger_price: &Decimal,
         limit_price: &Decimal,
         reduce_only: bool,
-        time_in_force: crate::models::order::TimeInForce,
     ) -> Result<SubmittedOrder> {
-        let info = self.market(symbol).await?;
-        let size_decimals = info.supported_size_decimals.unwrap_or(0);
-        let price_decimals = info.supported_price_decimals.unwrap_or(0);
-
-        let amount = scale_decimal(base_amount, size_decimals)
-            .ok_or_else(|| LighterError::Signing("unable to convert order size".to_string()))?;
-        if amount <= 0 {
-            return Err(LighterError::Signing(
-                "lighter order size must be positive".to_string(),
-            ));
-        }
+        self.create_trigger_order(
+            symbol,
+            OrderType::StopLossLimit,
+            is_buy,
+            base_amount,
+            trigger_price,
+            limit_price,
+            reduce_only,
+            None,
+        )
+        .await
+    }
 
-        let price_scaled = scale_decimal(limit_price, price_decimals)
-            .ok_or_else(|| LighterError::Signing("unable to convert order price".to_string()))?;
-        if price_scaled <= 0 {
-            return Err(LighterError::Signing(
-                "lighter limit price must be positive".to_string(),
-            ));
-        }
-        let price: i32 = price_scaled.try_into().map_err(|_| {
-            LighterError::Signing("lighter limit price exceeds supported range".to_string())
-        })?;
+    /// Take-profit that executes as a market order once `trigger_price` is hit.
+    /// `limit_price` bounds the worst acceptable fill.
+    pub async fn create_take_profit_order(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        trigger_price: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+    ) -> Result<SubmittedOrder> {
+        self.create_trigger_order(
+            symbol,
+            OrderType::TakeProfit,
+            is_buy,
+            base_amount,
+            trigger_price,
+            limit_price,
+            reduce_only,
+            None,
+        )
+        .await
+    }
+
+    /// Take-profit that rests as a limit order at `limit_price` once triggered.
+    pub async fn create_take_profit_limit_order(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        trigger_price: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+    ) -> Result<SubmittedOrder> {
+        self.create_trigger_order(
+            symbol,
+            OrderType::TakeProfitLimit,
+            is_buy,
+            base_amount,
+            trigger_price,
+            limit_price,
+            reduce_only,
+            None,
+        )
+        .await
+    }
 
-        let is_ask = !is_buy;
-        let trigger_price = 0i32;
-        let order_expiry = 0i64;
+    #[allow(clippy::too_many_arguments)]
+    async fn create_trigger_order(
+        &self,
+        symbol: &str,
+        order_type: OrderType,
+        is_buy: bool,
+        base_amount: &Decimal,
+        trigger_price: &Decimal,
+        limit_price: &Decimal,
+        reduce_only: bool,
+        client_id: Option<ClientOrderId>,
+    ) -> Result<SubmittedOrder> {
+        let info = self.market(symbol).await?;
+        let spec = trigger_order_spec(
+            &info,
+            order_type,
+            is_buy,
+            base_amount,
+            trigger_price,
+            limit_price,
+            reduce_only,
+            self.rounding,
+        )?;
+        self.submit_order(&info, spec, client_id).await
+    }
+
+    async fn submit_order(
+        &self,
+        info: &MarketInfo,
+        spec: OrderSpec,
+        client_id: Option<ClientOrderId>,
+    ) -> Result<SubmittedOrder> {
+        let requested_index = client_id
+            .as_ref()
+            .map(ClientOrderId::client_order_index)
+            .transpose()?;
+        let risk_order = RiskOrder::from_scaled(
+            info,
+            spec.amount,
+            spec.price,
+            spec.is_ask,
+            spec.reduce_only,
+            spec.trigger_price != 0,
+        );
+        self.enforce_risk(info, &[risk_order], true).await?;
         let nonce = self.nonce_manager.generate()? as i64;
-        let client_order_index = normalise_client_order_index(nonce)?;
+        let client_order_index = match requested_index {
+            Some(index) => index,
+            None => normalise_client_order_index(nonce)?,
+        };
 
         let (order, response) = self
             .transaction_api
             .create_order(
                 info.market_id,
                 client_order_index,
-                amount,
-                price,
-                is_ask,
-                crate::models::common::OrderType::Limit,
-                time_in_force,
-                reduce_only,
-                trigger_price,
-                order_expiry,
+                spec.amount,
+                spec.price,
+                spec.is_ask,
+                spec.order_type,
+                spec.time_in_force,
+                spec.reduce_only,
+                spec.trigger_price,
+                spec.order_expiry,
                 nonce,
             )
             .await?;
 
+        self.register_order(info, client_order_index, client_id);
         Ok(SubmittedOrder { order, response })
     }
 
+    fn register_order(
+        &self,
+        info: &MarketInfo,
+        client_order_index: i64,
+        client_id: Option<ClientOrderId>,
+    ) {
+        let tag = match client_id {
+            Some(ClientOrderId::Tag(tag)) => Some(tag),
+            _ => None,
+        };
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.insert(RegisteredOrder {
+                client_order_index,
+                tag,
+                symbol: info.symbol.clone(),
+                market_index: info.market_id,
+                order_index: None,
+            });
+        }
+    }
+
+    /// Look up an order previously submitted through this client.
+    pub fn lookup_client_order(
+        &self,
+        client_id: impl Into<ClientOrderId>,
+    ) -> Option<RegisteredOrder> {
+        let client_id = client_id.into();
+        self.registry.lock().ok()?.get(&client_id).cloned()
+    }
+
+    /// Cancel an order by the client index or tag it was submitted with. The
+    /// exchange order index is resolved from the account's active orders if
+    /// it has not been seen yet.
+    pub async fn cancel_order_by_client_id(
+        &self,
+        client_id: impl Into<ClientOrderId>,
+    ) -> Result<crate::api::transaction_api::TxResponse> {
+        let client_id = client_id.into();
+        let (entry, order_index) = self.resolve_client_order(&client_id).await?;
+        let response = self
+            .cancel_order(&entry.symbol, &order_index.to_string())
+            .await?;
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.remove(&client_id);
+        }
+        Ok(response)
+    }
+
+    /// Amend an order by the client index or tag it was submitted with.
+    pub async fn modify_order_by_client_id(
+        &self,
+        client_id: impl Into<ClientOrderId>,
+        new_size: &Decimal,
+        new_price: &Decimal,
+        new_trigger_price: Option<&Decimal>,
+    ) -> Result<crate::api::transaction_api::TxResponse> {
+        let client_id = client_id.into();
+        let (entry, order_index) = self.resolve_client_order(&client_id).await?;
+        self.modify_order_with_trigger(
+            &entry.symbol,
+            &order_index.to_string(),
+            new_size,
+            new_price,
+            new_trigger_price,
+        )
+        .await
+    }
+
+    async fn resolve_client_order(
+        &self,
+        client_id: &ClientOrderId,
+    ) -> Result<(RegisteredOrder, i64)> {
+        let entry = self.lookup_client_order(client_id.clone()).ok_or_else(|| {
+            LighterError::OrderValidation(format!("unknown client order {client_id:?}"))
+        })?;
+        if let Some(order_index) = entry.order_index {
+            return Ok((entry, order_index));
+        }
+
+        let order_index =
+            fetch_active_orders(&self.api_client, self.account_index, entry.market_index)
+                .await?
+                .into_iter()
+                .find(|order| order.client_order_index == entry.client_order_index)
+                .map(|order| order.order_index)
+                .ok_or_else(|| {
+                    LighterError::OrderValidation(format!(
+                        "client order {client_id:?} is not open on {}",
+                        entry.symbol
+                    ))
+                })?;
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.set_order_index(entry.client_order_index, order_index);
+        }
+        Ok((entry, order_index))
+    }
+
+    /// Orders of this account resting in `symbol`'s market.
+    pub async fn active_orders(&self, symbol: &str) -> Result<Vec<AccountOrder>> {
+        let info = self.market(symbol).await?;
+        fetch_active_orders(&self.api_client, self.account_index, info.market_id).await
+    }
+
+    /// Most recently closed orders of this account in `symbol`'s market.
+    pub async fn inactive_orders(&self, symbol: &str, limit: u32) -> Result<Vec<AccountOrder>> {
+        let info = self.market(symbol).await?;
+        fetch_inactive_orders(
+            &self.api_client,
+            self.account_index,
+            Some(info.market_id),
+            limit,
+        )
+        .await
+    }
+
+    /// One page of closed orders in `symbol`'s market, newest first. Pass
+    /// the returned cursor back in to continue further into the history.
+    pub async fn inactive_orders_page(
+        &self,
+        symbol: &str,
+        limit: u32,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<AccountOrder>, Option<String>)> {
+        let info = self.market(symbol).await?;
+        fetch_inactive_orders_page(
+            &self.api_client,
+            self.account_index,
+            Some(info.market_id),
+            limit,
+            cursor,
+        )
+        .await
+    }
+
     pub async fn cancel_order(
         &self,
         symbol: &str,
@@ -193,7 +652,124 @@ impl LighterFfiTradingClient {
             .await
     }
 
-    async fn market(&self, symbol: &str) -> Result<MarketInfo> {
+    /// Cancel open orders. Without a symbol a single immediate cancel-all tx
+    /// is sent; with a symbol the market's resting orders are cancelled one
+    /// by one, since Lighter's cancel-all is account wide. Each result is
+    /// keyed by the cancelled order index (`None` for the account-wide tx),
+    /// and a failed cancel does not stop the remaining ones.
+    pub async fn cancel_all_orders(
+        &self,
+        symbol: Option<&str>,
+    ) -> Result<Vec<(Option<i64>, Result<crate::api::transaction_api::TxResponse>)>> {
+        let symbol = match symbol {
+            Some(symbol) => symbol,
+            None => {
+                let response = self
+                    .cancel_all_orders_with_mode(CancelAllMode::Immediate)
+                    .await?;
+                return Ok(vec![(None, Ok(response))]);
+            }
+        };
+
+        let info = self.market(symbol).await?;
+        let orders =
+            fetch_active_orders(&self.api_client, self.account_index, info.market_id).await?;
+
+        let mut results = Vec::with_capacity(orders.len());
+        for order in orders {
+            let result = self
+                .cancel_order_index(info.market_id, order.order_index)
+                .await;
+            results.push((Some(order.order_index), result));
+        }
+        Ok(results)
+    }
+
+    async fn cancel_order_index(
+        &self,
+        market_id: i32,
+        order_index: i64,
+    ) -> Result<crate::api::transaction_api::TxResponse> {
+        let nonce = self.nonce_manager.generate()? as i64;
+        let client_cancel_index = normalise_client_order_index(nonce)?;
+        self.transaction_api
+            .cancel_order(
+                market_id,
+                client_cancel_index,
+                &order_index.to_string(),
+                nonce,
+            )
+            .await
+    }
+
+    /// Send an account-wide cancel-all using one of Lighter's time-in-force
+    /// modes. Scheduled cancels act as a dead man's switch and must fall
+    /// within the exchange's allowed window.
+    pub async fn cancel_all_orders_with_mode(
+        &self,
+        mode: CancelAllMode,
+    ) -> Result<crate::api::transaction_api::TxResponse> {
+        if let CancelAllMode::Scheduled(at) = mode {
+            validate_scheduled_cancel(at, Utc::now())?;
+        }
+        let nonce = self.nonce_manager.generate()? as i64;
+        self.transaction_api.cancel_all_orders(mode, nonce).await
+    }
+
+    /// Amend a resting order in place, keeping its queue position where the
+    /// exchange allows it.
+    pub async fn modify_order(
+        &self,
+        symbol: &str,
+        order_id: &str,
+        new_size: &Decimal,
+        new_price: &Decimal,
+    ) -> Result<crate::api::transaction_api::TxResponse> {
+        self.modify_order_with_trigger(symbol, order_id, new_size, new_price, None)
+            .await
+    }
+
+    /// Amend a resting order, optionally moving its trigger price.
+    pub async fn modify_order_with_trigger(
+        &self,
+        symbol: &str,
+        order_id: &str,
+        new_size: &Decimal,
+        new_price: &Decimal,
+        new_trigger_price: Option<&Decimal>,
+    ) -> Result<crate::api::transaction_api::TxResponse> {
+        let info = self.market(symbol).await?;
+        let order_index = parse_order_index(order_id)?;
+        let (amount, price) = conform_order(&info, new_size, new_price, None, self.rounding)?;
+        let trigger_price = match new_trigger_price {
+            Some(trigger) => scale_trigger_price(&info, trigger)?,
+            None => 0,
+        };
+        let risk_order = RiskOrder::from_scaled(
+            &info,
+            amount,
+            price,
+            false,
+            false,
+            new_trigger_price.is_some(),
+        );
+        self.enforce_risk(&info, &[risk_order], false).await?;
+        let nonce = self.nonce_manager.generate()? as i64;
+
+        self.transaction_api
+            .modify_order(
+                info.market_id,
+                order_index,
+                amount,
+                price as i64,
+                trigger_price as i64,
+                nonce,
+            )
+            .await
+    }
+
+    /// Market metadata for `symbol`, cached after the first lookup.
+    pub async fn market(&self, symbol: &str) -> Result<MarketInfo> {
         let key = symbol.to_uppercase();
         if let Some(info) = self.markets.read().await.get(&key) {
             return Ok(info.clone());
@@ -212,7 +788,7 @@ impl LighterFfiTradingClient {
     }
 }
 
-fn build_signing_url(url: &url::Url) -> Result<String> {
+pub(crate) fn build_signing_url(url: &url::Url) -> Result<String> {
     let host = url
         .host_str()
         .ok_or_else(|| LighterError::Config("Missing host in Lighter base URL".to_string()))?;
@@ -232,6 +808,118 @@ fn scale_decimal(value: &Decimal, decimals: u32) -> Option<i64> {
     (value * multiplier).to_i64()
 }
 
+/// Scale a USDC amount to the 1e6 units used by collateral transactions.
+pub(crate) fn scale_usdc(amount: Decimal) -> Result<i64> {
+    if amount <= Decimal::ZERO || amount.normalize().scale() > USDC_DECIMALS {
+        return Err(LighterError::OrderValidation(format!(
+            "USDC amount {amount} must be positive with at most {USDC_DECIMALS} decimals"
+        )));
+    }
+    scale_decimal(&amount, USDC_DECIMALS).ok_or_else(|| {
+        LighterError::OrderValidation(format!("USDC amount {amount} is out of range"))
+    })
+}
+
+fn scale_size(info: &MarketInfo, base_amount: &Decimal) -> Result<i64> {
+    let size_decimals = info.supported_size_decimals.unwrap_or(0);
+    let amount = scale_decimal(base_amount, size_decimals)
+        .ok_or_else(|| LighterError::Signing("unable to convert order size".to_string()))?;
+    if amount <= 0 {
+        return Err(LighterError::Signing(
+            "lighter order size must be positive".to_string(),
+        ));
+    }
+    Ok(amount)
+}
+
+fn scale_price(info: &MarketInfo, price: &Decimal) -> Result<i32> {
+    let price_decimals = info.supported_price_decimals.unwrap_or(0);
+    let price_scaled = scale_decimal(price, price_decimals)
+        .ok_or_else(|| LighterError::Signing("unable to convert order price".to_string()))?;
+    if price_scaled <= 0 {
+        return Err(LighterError::Signing(
+            "lighter limit price must be positive".to_string(),
+        ));
+    }
+    price_scaled.try_into().map_err(|_| {
+        LighterError::Signing("lighter limit price exceeds supported range".to_string())
+    })
+}
+
+/// Conform a stop-loss or take-profit order. Triggers rest until armed, so
+/// even market-style ones, which execute IOC, keep the signer's default
+/// 28-day expiry.
+#[allow(clippy::too_many_arguments)]
+fn trigger_order_spec(
+    info: &MarketInfo,
+    order_type: OrderType,
+    is_buy: bool,
+    base_amount: &Decimal,
+    trigger_price: &Decimal,
+    limit_price: &Decimal,
+    reduce_only: bool,
+    rounding: RoundingPolicy,
+) -> Result<OrderSpec> {
+    // Market-style triggers fill immediately once armed; limit-style
+    // triggers rest on the book until their expiry.
+    let time_in_force = match order_type {
+        OrderType::StopLoss | OrderType::TakeProfit => TimeInForce::Ioc,
+        _ => TimeInForce::Gtc,
+    };
+    let (amount, price) = conform_order(info, base_amount, limit_price, Some(!is_buy), rounding)?;
+    Ok(OrderSpec {
+        amount,
+        price,
+        is_ask: !is_buy,
+        order_type,
+        time_in_force,
+        reduce_only,
+        trigger_price: scale_trigger_price(info, trigger_price)?,
+        order_expiry: OrderExpiry::Default.resolve_trigger(Utc::now())?,
+    })
+}
+
+/// Trigger prices are compared against the mark price by the exchange, so
+/// extra precision is rejected rather than silently truncated.
+fn scale_trigger_price(info: &MarketInfo, trigger_price: &Decimal) -> Result<i32> {
+    let price_decimals = info.supported_price_decimals.unwrap_or(0);
+    if trigger_price.normalize().scale() > price_decimals {
+        return Err(LighterError::OrderValidation(format!(
+            "trigger price {trigger_price} exceeds {price_decimals} supported decimals for {}",
+            info.symbol
+        )));
+    }
+    scale_decimal(trigger_price, price_decimals)
+        .filter(|scaled| *scaled > 0)
+        .and_then(|scaled| i32::try_from(scaled).ok())
+        .ok_or_else(|| {
+            LighterError::OrderValidation(format!(
+                "trigger price {trigger_price} is out of range for {}",
+                info.symbol
+            ))
+        })
+}
+
+fn validate_scheduled_cancel(at: DateTime<Utc>, now: DateTime<Utc>) -> Result<()> {
+    if at < now + MIN_SCHEDULED_CANCEL_ALL || at > now + MAX_SCHEDULED_CANCEL_ALL {
+        return Err(LighterError::OrderValidation(format!(
+            "scheduled cancel-all at {at} must be between 5 minutes and 15 days from now"
+        )));
+    }
+    Ok(())
+}
+
+fn parse_order_index(order_id: &str) -> Result<i64> {
+    order_id
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|index| *index > 0)
+        .ok_or_else(|| {
+            LighterError::OrderValidation(format!("invalid Lighter order index: {order_id}"))
+        })
+}
+
 const LIGHTER_MAX_CLIENT_ORDER_INDEX: i64 = ((1u64 << 48) - 1) as i64;
 const LIGHTER_CLIENT_ORDER_SCALE: i64 = 100;
 
@@ -254,11 +942,6 @@ fn normalise_client_order_index(raw: i64) -> Result<i64> {
     Ok(clamped)
 }
 
-fn extract_str<'a>(value: &'a serde_json::Value, keys: &[&str]) -> Option<&'a str> {
-    keys.iter()
-        .find_map(|key| value.get(key).and_then(serde_json::Value::as_str))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +952,92 @@ mod tests {
         let scaled = scale_decimal(&value, 3).unwrap();
         assert_eq!(scaled, 1234);
     }
+
+    #[test]
+    fn parses_order_index() {
+        assert_eq!(
+            parse_order_index("281474976710657").unwrap(),
+            281474976710657
+        );
+        assert!(matches!(
+            parse_order_index("abc"),
+            Err(LighterError::OrderValidation(_))
+        ));
+        assert!(parse_order_index("0").is_err());
+    }
+
+    #[test]
+    fn validates_scheduled_cancel_window() {
+        let now = Utc::now();
+        assert!(validate_scheduled_cancel(now + Duration::minutes(10), now).is_ok());
+        assert!(validate_scheduled_cancel(now + Duration::minutes(1), now).is_err());
+        assert!(validate_scheduled_cancel(now + Duration::days(16), now).is_err());
+    }
+
+    #[test]
+    fn trigger_price_respects_market_decimals() {
+        let info = MarketInfo {
+            market_id: 1,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(4),
+            supported_price_decimals: Some(2),
+            supported_quote_decimals: Some(6),
+            ..Default::default()
+        };
+        assert_eq!(
+            scale_trigger_price(&info, &Decimal::new(312_450, 2)).unwrap(),
+            312_450
+        );
+        assert!(matches!(
+            scale_trigger_price(&info, &Decimal::new(3_124_505, 3)),
+            Err(LighterError::OrderValidation(_))
+        ));
+        assert!(matches!(
+            scale_trigger_price(&info, &Decimal::ZERO),
+            Err(LighterError::OrderValidation(_))
+        ));
+    }
+
+    #[test]
+    fn trigger_orders_keep_default_expiry() {
+        let info = MarketInfo {
+            market_id: 1,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(4),
+            supported_price_decimals: Some(2),
+            supported_quote_decimals: Some(6),
+            ..Default::default()
+        };
+        for order_type in [
+            OrderType::StopLoss,
+            OrderType::TakeProfit,
+            OrderType::StopLossLimit,
+            OrderType::TakeProfitLimit,
+        ] {
+            let spec = trigger_order_spec(
+                &info,
+                order_type,
+                false,
+                &Decimal::ONE,
+                &Decimal::from(3_000),
+                &Decimal::from(2_990),
+                true,
+                RoundingPolicy::default(),
+            )
+            .unwrap();
+            assert_eq!(spec.order_expiry, -1, "{order_type:?}");
+        }
+        let stop = trigger_order_spec(
+            &info,
+            OrderType::StopLoss,
+            false,
+            &Decimal::ONE,
+            &Decimal::from(3_000),
+            &Decimal::from(2_990),
+            true,
+            RoundingPolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(stop.time_in_force, TimeInForce::Ioc);
+    }
 }
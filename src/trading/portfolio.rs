@@ -0,0 +1,763 @@
+use crate::error::{LighterError, Result};
+use crate::ffi_client::AccountSnapshot;
+use crate::metadata::MarketInfo;
+use crate::models::common::{decimal_field, parse_timestamp, OrderBook, Side};
+use crate::models::order::Trade;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+/// Strategy that fills are booked under when none is given.
+pub const UNATTRIBUTED: &str = "unattributed";
+
+/// Trade ids remembered for deduplication by default.
+pub const DEFAULT_SEEN_TRADES_CAPACITY: usize = 10_000;
+
+/// How closing fills are matched against the lots they close.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CostBasis {
+    /// Close the oldest lots first.
+    Fifo,
+    /// Close against the running average entry price.
+    #[default]
+    AverageCost,
+}
+
+/// A fill of one of the account's orders, in quote units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioFill {
+    pub trade_id: Option<String>,
+    pub symbol: String,
+    pub is_buy: bool,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee: Decimal,
+    pub timestamp: DateTime<Utc>,
+    pub strategy: Option<String>,
+}
+
+impl PortfolioFill {
+    pub fn new(symbol: &str, is_buy: bool, price: Decimal, size: Decimal) -> Self {
+        Self {
+            trade_id: None,
+            symbol: symbol.to_uppercase(),
+            is_buy,
+            price,
+            size,
+            fee: Decimal::ZERO,
+            timestamp: Utc::now(),
+            strategy: None,
+        }
+    }
+
+    /// Convert a REST [`Trade`]. The fee is taken to be in quote units.
+    pub fn from_trade(trade: &Trade) -> Result<Self> {
+        let parse = |field: &str, value: &str| {
+            Decimal::from_str(value)
+                .map_err(|_| LighterError::Unknown(format!("unparseable trade {field} {value:?}")))
+        };
+        Ok(Self {
+            trade_id: Some(trade.id.clone()),
+            symbol: trade.symbol.to_uppercase(),
+            is_buy: trade.side == Side::Buy,
+            price: parse("price", &trade.price)?,
+            size: parse("quantity", &trade.quantity)?,
+            fee: if trade.fee.is_empty() {
+                Decimal::ZERO
+            } else {
+                parse("fee", &trade.fee)?
+            },
+            timestamp: trade.timestamp,
+            strategy: None,
+        })
+    }
+
+    pub fn with_fee(mut self, fee: Decimal) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn with_trade_id(mut self, trade_id: impl Into<String>) -> Self {
+        self.trade_id = Some(trade_id.into());
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: impl Into<String>) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    fn signed_size(&self) -> Decimal {
+        if self.is_buy {
+            self.size
+        } else {
+            -self.size
+        }
+    }
+}
+
+/// Locally computed position of one strategy, or of the whole account, in
+/// one market.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioPosition {
+    pub symbol: String,
+    /// Signed size, negative when short.
+    pub size: Decimal,
+    /// Average entry of the open size, if any.
+    pub average_entry: Option<Decimal>,
+    pub realized_pnl: Decimal,
+    /// Against the latest mark; `None` while flat or without a mark.
+    pub unrealized_pnl: Option<Decimal>,
+    pub fees: Decimal,
+    /// Traded notional, in quote units.
+    pub volume: Decimal,
+}
+
+/// PnL of a strategy or of the account, in quote units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PnlSummary {
+    pub realized: Decimal,
+    pub unrealized: Decimal,
+    pub fees: Decimal,
+}
+
+impl PnlSummary {
+    /// Realised plus unrealised PnL, after fees.
+    pub fn net(&self) -> Decimal {
+        self.realized + self.unrealized - self.fees
+    }
+
+    fn add(&mut self, other: PnlSummary) {
+        self.realized += other.realized;
+        self.unrealized += other.unrealized;
+        self.fees += other.fees;
+    }
+}
+
+/// A market where the local position disagrees with the exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionDiscrepancy {
+    pub symbol: String,
+    pub local_size: Decimal,
+    pub exchange_size: Decimal,
+    pub local_entry: Option<Decimal>,
+    pub exchange_entry: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Lot {
+    /// Signed size, the same sign for every lot of a ledger.
+    size: Decimal,
+    price: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Ledger {
+    lots: VecDeque<Lot>,
+    realized: Decimal,
+    fees: Decimal,
+    volume: Decimal,
+}
+
+impl Ledger {
+    fn size(&self) -> Decimal {
+        self.lots.iter().map(|lot| lot.size).sum()
+    }
+
+    fn cost(&self) -> Decimal {
+        self.lots.iter().map(|lot| lot.size * lot.price).sum()
+    }
+
+    fn unrealized(&self, mark: Decimal) -> Decimal {
+        self.size() * mark - self.cost()
+    }
+
+    /// Book a fill, closing opposite lots before opening a new one.
+    fn apply(&mut self, signed_size: Decimal, price: Decimal, fee: Decimal, basis: CostBasis) {
+        self.fees += fee;
+        self.volume += signed_size.abs() * price;
+
+        let mut remaining = signed_size;
+        while !remaining.is_zero() {
+            let Some(lot) = self.lots.front_mut() else {
+                break;
+            };
+            if lot.size.is_sign_positive() == remaining.is_sign_positive() {
+                break;
+            }
+            let closed = remaining.abs().min(lot.size.abs());
+            let direction = if lot.size.is_sign_positive() {
+                Decimal::ONE
+            } else {
+                -Decimal::ONE
+            };
+            self.realized += (price - lot.price) * closed * direction;
+            lot.size -= closed * direction;
+            remaining += closed * direction;
+            if lot.size.is_zero() {
+                self.lots.pop_front();
+            }
+        }
+
+        if remaining.is_zero() {
+            return;
+        }
+        self.lots.push_back(Lot {
+            size: remaining,
+            price,
+        });
+        if basis == CostBasis::AverageCost && self.lots.len() > 1 {
+            let size = self.size();
+            let price = self.cost() / size;
+            self.lots = VecDeque::from([Lot { size, price }]);
+        }
+    }
+}
+
+/// Bounded set of booked trade ids. The oldest ids are forgotten first once
+/// the capacity is reached.
+#[derive(Debug)]
+struct SeenTrades {
+    capacity: usize,
+    ids: HashSet<String>,
+    insertion_order: VecDeque<String>,
+}
+
+impl SeenTrades {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ids: HashSet::new(),
+            insertion_order: VecDeque::new(),
+        }
+    }
+
+    /// Returns `false` for an id already remembered.
+    fn insert(&mut self, trade_id: &str) -> bool {
+        if self.ids.contains(trade_id) {
+            return false;
+        }
+        while self.ids.len() >= self.capacity {
+            let Some(oldest) = self.insertion_order.pop_front() else {
+                break;
+            };
+            self.ids.remove(&oldest);
+        }
+        self.ids.insert(trade_id.to_string());
+        self.insertion_order.push_back(trade_id.to_string());
+        true
+    }
+}
+
+impl Default for SeenTrades {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEEN_TRADES_CAPACITY)
+    }
+}
+
+/// Books the account's fills per strategy and market and computes average
+/// entry, realised and unrealised PnL and fees locally, so PnL can be split
+/// by strategy where the exchange only reports it per account.
+///
+/// Fills are deduplicated by trade id, so the same trade may arrive from
+/// both REST and the WS stream. Only the most recent
+/// [`DEFAULT_SEEN_TRADES_CAPACITY`] ids are remembered unless changed with
+/// [`Self::with_seen_trades_capacity`].
+#[derive(Debug, Default)]
+pub struct PortfolioTracker {
+    basis: CostBasis,
+    ledgers: BTreeMap<(String, String), Ledger>,
+    marks: HashMap<String, Decimal>,
+    markets: HashMap<i32, MarketInfo>,
+    order_strategies: HashMap<i64, String>,
+    seen_trades: SeenTrades,
+    session_start: HashMap<String, Decimal>,
+}
+
+impl PortfolioTracker {
+    pub fn new(basis: CostBasis) -> Self {
+        Self {
+            basis,
+            ..Self::default()
+        }
+    }
+
+    /// Change how many trade ids are remembered for deduplication.
+    pub fn with_seen_trades_capacity(mut self, capacity: usize) -> Self {
+        self.seen_trades = SeenTrades::new(capacity);
+        self
+    }
+
+    /// Register a market so WS trades, which only carry a market id, can be
+    /// booked. Fees of WS trades are charged at the market's maker and taker
+    /// rates, which Lighter quotes in percent.
+    pub fn with_market(mut self, info: MarketInfo) -> Self {
+        self.add_market(info);
+        self
+    }
+
+    pub fn add_market(&mut self, info: MarketInfo) {
+        self.markets.insert(info.market_id, info);
+    }
+
+    /// Book future WS fills of exchange order `order_index` under `strategy`.
+    pub fn attribute_order(&mut self, order_index: i64, strategy: &str) {
+        self.order_strategies
+            .insert(order_index, strategy.to_string());
+    }
+
+    pub fn cost_basis(&self) -> CostBasis {
+        self.basis
+    }
+
+    /// Book a fill. Returns `false` for a trade id already booked.
+    pub fn apply_fill(&mut self, fill: &PortfolioFill) -> bool {
+        if let Some(trade_id) = &fill.trade_id {
+            if !self.seen_trades.insert(trade_id) {
+                return false;
+            }
+        }
+        let strategy = fill.strategy.as_deref().unwrap_or(UNATTRIBUTED);
+        self.ledgers
+            .entry((strategy.to_string(), fill.symbol.to_uppercase()))
+            .or_default()
+            .apply(fill.signed_size(), fill.price, fill.fee, self.basis);
+        true
+    }
+
+    /// Book a REST [`Trade`] under `strategy`.
+    pub fn apply_trade(&mut self, trade: &Trade, strategy: Option<&str>) -> Result<bool> {
+        let mut fill = PortfolioFill::from_trade(trade)?;
+        fill.strategy = strategy.map(str::to_string);
+        Ok(self.apply_fill(&fill))
+    }
+
+    /// Book the account's trades contained in an account WS message, as
+    /// sent on `account_all/{account_index}`. Lighter sends trades either
+    /// as a list or keyed by market index. Returns the fills booked.
+    pub fn apply_ws_message(&mut self, message: &Value, account_index: i64) -> Vec<PortfolioFill> {
+        let Some(trades) = message.get("trades") else {
+            return Vec::new();
+        };
+        let entries: Vec<&Value> = match trades {
+            Value::Array(list) => list.iter().collect(),
+            Value::Object(by_market) => by_market
+                .values()
+                .filter_map(Value::as_array)
+                .flatten()
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let fills: Vec<PortfolioFill> = entries
+            .into_iter()
+            .filter_map(|trade| self.ws_fill(trade, account_index))
+            .collect();
+        fills
+            .into_iter()
+            .filter(|fill| self.apply_fill(fill))
+            .collect()
+    }
+
+    fn ws_fill(&self, trade: &Value, account_index: i64) -> Option<PortfolioFill> {
+        let market_id = i32::try_from(trade.get("market_id")?.as_i64()?).ok()?;
+        let info = self.markets.get(&market_id)?;
+        let is_buy = if int_field(trade, "bid_account_id") == Some(account_index) {
+            true
+        } else if int_field(trade, "ask_account_id") == Some(account_index) {
+            false
+        } else {
+            return None;
+        };
+        let price = decimal_field(trade, "price")?;
+        let size = decimal_field(trade, "size")?;
+
+        let is_maker_ask = trade.get("is_maker_ask").and_then(Value::as_bool)?;
+        let is_maker = is_maker_ask != is_buy;
+        let rate = if is_maker {
+            info.maker_fee
+        } else {
+            info.taker_fee
+        };
+        let fee = rate.unwrap_or_default() * price * size / Decimal::ONE_HUNDRED;
+
+        let order_index = int_field(trade, if is_buy { "bid_id" } else { "ask_id" });
+        let mut fill = PortfolioFill::new(&info.symbol, is_buy, price, size).with_fee(fee);
+        fill.trade_id = trade
+            .get("trade_id")
+            .and_then(|id| id.as_i64().map(|id| id.to_string()));
+        if let Some(timestamp) = int_field(trade, "timestamp").and_then(parse_timestamp) {
+            fill.timestamp = timestamp;
+        }
+        fill.strategy = order_index.and_then(|index| self.order_strategies.get(&index).cloned());
+        Some(fill)
+    }
+
+    /// Set the price unrealised PnL of `symbol` is measured against.
+    pub fn set_mark(&mut self, symbol: &str, price: Decimal) {
+        self.marks.insert(symbol.to_uppercase(), price);
+    }
+
+    /// Mark `symbol` at the book mid. Returns the mid, if both sides have a
+    /// level.
+    pub fn mark_to_book(&mut self, symbol: &str, book: &OrderBook) -> Option<Decimal> {
+        let best = |levels: &[crate::models::common::PriceLevel]| {
+            levels
+                .first()
+                .and_then(|level| Decimal::from_str(&level.price).ok())
+        };
+        let mid = (best(&book.bids)? + best(&book.asks)?) / Decimal::TWO;
+        self.set_mark(symbol, mid);
+        Some(mid)
+    }
+
+    pub fn mark(&self, symbol: &str) -> Option<Decimal> {
+        self.marks.get(&symbol.to_uppercase()).copied()
+    }
+
+    /// Strategies with booked fills.
+    pub fn strategies(&self) -> Vec<&str> {
+        let mut strategies: Vec<&str> = self
+            .ledgers
+            .keys()
+            .map(|(strategy, _)| strategy.as_str())
+            .collect();
+        strategies.dedup();
+        strategies
+    }
+
+    /// Position of `strategy` in `symbol`.
+    pub fn strategy_position(&self, strategy: &str, symbol: &str) -> Option<PortfolioPosition> {
+        let symbol = symbol.to_uppercase();
+        let ledger = self.ledgers.get(&(strategy.to_string(), symbol.clone()))?;
+        Some(self.position_of(symbol, [ledger]))
+    }
+
+    /// Position of the whole account in `symbol`, across strategies.
+    pub fn position(&self, symbol: &str) -> Option<PortfolioPosition> {
+        let symbol = symbol.to_uppercase();
+        let ledgers: Vec<&Ledger> = self
+            .ledgers
+            .iter()
+            .filter(|((_, market), _)| *market == symbol)
+            .map(|(_, ledger)| ledger)
+            .collect();
+        (!ledgers.is_empty()).then(|| self.position_of(symbol, ledgers))
+    }
+
+    /// Account positions in every market with booked fills.
+    pub fn positions(&self) -> Vec<PortfolioPosition> {
+        let symbols: std::collections::BTreeSet<&String> =
+            self.ledgers.keys().map(|(_, symbol)| symbol).collect();
+        symbols
+            .into_iter()
+            .filter_map(|symbol| self.position(symbol))
+            .collect()
+    }
+
+    /// PnL of one strategy across markets. Markets without a mark add no
+    /// unrealised PnL.
+    pub fn strategy_pnl(&self, strategy: &str) -> PnlSummary {
+        let mut summary = PnlSummary::default();
+        let ledgers = self
+            .ledgers
+            .range((strategy.to_string(), String::new())..)
+            .take_while(|((owner, _), _)| owner == strategy);
+        for ((_, symbol), ledger) in ledgers {
+            summary.add(self.ledger_pnl(symbol, ledger));
+        }
+        summary
+    }
+
+    /// PnL of the account across strategies and markets.
+    pub fn total_pnl(&self) -> PnlSummary {
+        let mut summary = PnlSummary::default();
+        for ((_, symbol), ledger) in &self.ledgers {
+            summary.add(self.ledger_pnl(symbol, ledger));
+        }
+        summary
+    }
+
+    /// Start a new session, e.g. at the start of the trading day, from
+    /// which [`Self::session_pnl`] is measured. Positions carry over.
+    pub fn start_session(&mut self) {
+        self.session_start = self
+            .strategies()
+            .into_iter()
+            .map(|strategy| (strategy.to_string(), self.strategy_pnl(strategy).net()))
+            .collect();
+    }
+
+    /// Net PnL of `strategy` since the last [`Self::start_session`],
+    /// including the change in unrealised PnL of positions carried over.
+    pub fn session_pnl(&self, strategy: &str) -> Decimal {
+        let start = self
+            .session_start
+            .get(strategy)
+            .copied()
+            .unwrap_or_default();
+        self.strategy_pnl(strategy).net() - start
+    }
+
+    /// Compare local account positions with the exchange's, as returned by
+    /// `LighterFfiClient::get_account`. Sizes must match within
+    /// `size_tolerance` and entries within `price_tolerance`; markets open on
+    /// only one side are reported too.
+    pub fn reconcile(
+        &self,
+        account: &AccountSnapshot,
+        size_tolerance: Decimal,
+        price_tolerance: Decimal,
+    ) -> Vec<PositionDiscrepancy> {
+        let mut exchange: BTreeMap<String, (Decimal, Option<Decimal>)> = BTreeMap::new();
+        for position in &account.positions {
+            let size = position.signed_size().unwrap_or_default();
+            let entry = Decimal::from_str(&position.average_entry_price).ok();
+            exchange.insert(position.symbol.to_uppercase(), (size, entry));
+        }
+        let mut local: BTreeMap<String, (Decimal, Option<Decimal>)> = BTreeMap::new();
+        for position in self.positions() {
+            local.insert(position.symbol, (position.size, position.average_entry));
+        }
+
+        let symbols: std::collections::BTreeSet<String> =
+            exchange.keys().chain(local.keys()).cloned().collect();
+        symbols
+            .into_iter()
+            .filter_map(|symbol| {
+                let (local_size, local_entry) = local.get(&symbol).copied().unwrap_or_default();
+                let (exchange_size, exchange_entry) =
+                    exchange.get(&symbol).copied().unwrap_or_default();
+                let size_off = (local_size - exchange_size).abs() > size_tolerance;
+                let entry_off = !exchange_size.is_zero()
+                    && match (local_entry, exchange_entry) {
+                        (Some(local), Some(exchange)) => (local - exchange).abs() > price_tolerance,
+                        _ => false,
+                    };
+                (size_off || entry_off).then_some(PositionDiscrepancy {
+                    symbol,
+                    local_size,
+                    exchange_size,
+                    local_entry,
+                    exchange_entry,
+                })
+            })
+            .collect()
+    }
+
+    /// Adopt the exchange's open positions as unattributed lots at their
+    /// reported entry, e.g. when starting to track an account that already
+    /// holds positions. Replaces any unattributed lots already booked.
+    pub fn seed_from_account(&mut self, account: &AccountSnapshot) {
+        for position in &account.positions {
+            let Some(size) = position.signed_size().filter(|size| !size.is_zero()) else {
+                continue;
+            };
+            let Ok(price) = Decimal::from_str(&position.average_entry_price) else {
+                continue;
+            };
+            let ledger = self
+                .ledgers
+                .entry((UNATTRIBUTED.to_string(), position.symbol.to_uppercase()))
+                .or_default();
+            ledger.lots = VecDeque::from([Lot { size, price }]);
+        }
+    }
+
+    fn ledger_pnl(&self, symbol: &str, ledger: &Ledger) -> PnlSummary {
+        PnlSummary {
+            realized: ledger.realized,
+            unrealized: self
+                .mark(symbol)
+                .map(|mark| ledger.unrealized(mark))
+                .unwrap_or_default(),
+            fees: ledger.fees,
+        }
+    }
+
+    fn position_of<'a>(
+        &self,
+        symbol: String,
+        ledgers: impl IntoIterator<Item = &'a Ledger>,
+    ) -> PortfolioPosition {
+        let mut size = Decimal::ZERO;
+        let mut cost = Decimal::ZERO;
+        let mut realized_pnl = Decimal::ZERO;
+        let mut fees = Decimal::ZERO;
+        let mut volume = Decimal::ZERO;
+        for ledger in ledgers {
+            size += ledger.size();
+            cost += ledger.cost();
+            realized_pnl += ledger.realized;
+            fees += ledger.fees;
+            volume += ledger.volume;
+        }
+        let mark = self.mark(&symbol);
+        PortfolioPosition {
+            average_entry: (!size.is_zero()).then(|| cost / size),
+            unrealized_pnl: mark
+                .filter(|_| !size.is_zero())
+                .map(|mark| size * mark - cost),
+            symbol,
+            size,
+            realized_pnl,
+            fees,
+            volume,
+        }
+    }
+}
+
+fn int_field(value: &Value, key: &str) -> Option<i64> {
+    let field = value.get(key)?;
+    field
+        .as_i64()
+        .or_else(|| field.as_str().and_then(|text| text.parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi_client::AccountPosition;
+
+    fn fill(is_buy: bool, price: i64, size: i64) -> PortfolioFill {
+        PortfolioFill::new("eth", is_buy, Decimal::from(price), Decimal::from(size))
+    }
+
+    #[test]
+    fn fifo_and_average_cost_realise_differently() {
+        let mut fifo = PortfolioTracker::new(CostBasis::Fifo);
+        let mut average = PortfolioTracker::new(CostBasis::AverageCost);
+        for tracker in [&mut fifo, &mut average] {
+            tracker.apply_fill(&fill(true, 100, 1));
+            tracker.apply_fill(&fill(true, 110, 1));
+            tracker.apply_fill(&fill(false, 120, 1).with_fee(Decimal::ONE));
+            tracker.set_mark("ETH", Decimal::from(130));
+        }
+
+        let fifo_eth = fifo.position("ETH").unwrap();
+        assert_eq!(fifo_eth.realized_pnl, Decimal::from(20));
+        assert_eq!(fifo_eth.average_entry, Some(Decimal::from(110)));
+        assert_eq!(fifo_eth.unrealized_pnl, Some(Decimal::from(20)));
+
+        let average_eth = average.position("ETH").unwrap();
+        assert_eq!(average_eth.realized_pnl, Decimal::from(15));
+        assert_eq!(average_eth.average_entry, Some(Decimal::from(105)));
+        assert_eq!(average_eth.unrealized_pnl, Some(Decimal::from(25)));
+
+        assert_eq!(fifo.total_pnl().net(), Decimal::from(39));
+        assert_eq!(average.total_pnl().net(), Decimal::from(39));
+    }
+
+    #[test]
+    fn flips_through_zero_and_splits_by_strategy() {
+        let mut tracker = PortfolioTracker::new(CostBasis::Fifo);
+        tracker.apply_fill(&fill(true, 100, 2).with_strategy("grid"));
+        tracker.apply_fill(&fill(false, 90, 3).with_strategy("grid"));
+        tracker.apply_fill(&fill(true, 95, 1).with_strategy("mm"));
+        tracker.set_mark("ETH", Decimal::from(80));
+
+        let grid = tracker.strategy_position("grid", "ETH").unwrap();
+        assert_eq!(grid.size, Decimal::from(-1));
+        assert_eq!(grid.average_entry, Some(Decimal::from(90)));
+        assert_eq!(grid.realized_pnl, Decimal::from(-20));
+        assert_eq!(tracker.strategy_pnl("grid").net(), Decimal::from(-10));
+        assert_eq!(tracker.strategy_pnl("mm").net(), Decimal::from(-15));
+        assert_eq!(tracker.position("ETH").unwrap().size, Decimal::ZERO);
+
+        tracker.start_session();
+        tracker.set_mark("ETH", Decimal::from(85));
+        assert_eq!(tracker.session_pnl("grid"), Decimal::from(-5));
+        assert_eq!(tracker.session_pnl("mm"), Decimal::from(5));
+    }
+
+    #[test]
+    fn forgets_oldest_trade_ids_past_capacity() {
+        let mut tracker = PortfolioTracker::new(CostBasis::Fifo).with_seen_trades_capacity(2);
+        assert!(tracker.apply_fill(&fill(true, 100, 1).with_trade_id("1")));
+        assert!(tracker.apply_fill(&fill(true, 100, 1).with_trade_id("2")));
+        assert!(!tracker.apply_fill(&fill(true, 100, 1).with_trade_id("1")));
+
+        assert!(tracker.apply_fill(&fill(true, 100, 1).with_trade_id("3")));
+        assert_eq!(tracker.seen_trades.ids.len(), 2);
+        assert!(!tracker.apply_fill(&fill(true, 100, 1).with_trade_id("3")));
+        // The oldest id was evicted to make room.
+        assert!(tracker.apply_fill(&fill(true, 100, 1).with_trade_id("1")));
+        assert_eq!(tracker.position("ETH").unwrap().size, Decimal::from(4));
+    }
+
+    #[test]
+    fn books_ws_trades_once_and_reconciles() {
+        let mut tracker = PortfolioTracker::new(CostBasis::Fifo).with_market(MarketInfo {
+            market_id: 0,
+            symbol: "ETH".to_string(),
+            taker_fee: Some(Decimal::new(3, 2)),
+            ..Default::default()
+        });
+        tracker.attribute_order(900, "grid");
+        let message = serde_json::json!({
+            "type": "update/account_all",
+            "trades": {
+                "0": [{
+                    "trade_id": 1,
+                    "market_id": 0,
+                    "price": "2000.00",
+                    "size": "0.5",
+                    "bid_account_id": 7,
+                    "ask_account_id": 8,
+                    "bid_id": 900,
+                    "is_maker_ask": true,
+                    "timestamp": 1_700_000_000_000i64
+                }]
+            }
+        });
+        let fills = tracker.apply_ws_message(&message, 7);
+        assert_eq!(fills.len(), 1);
+        assert!(fills[0].is_buy);
+        assert_eq!(fills[0].fee, Decimal::new(3, 1));
+        assert!(tracker.apply_ws_message(&message, 7).is_empty());
+        assert_eq!(
+            tracker.strategy_position("grid", "ETH").unwrap().size,
+            Decimal::new(5, 1)
+        );
+
+        let mut account = AccountSnapshot {
+            account_index: 7,
+            l1_address: String::new(),
+            available_balance: String::new(),
+            collateral: String::new(),
+            cross_asset_value: String::new(),
+            total_asset_value: String::new(),
+            total_order_count: 0,
+            balances: Vec::new(),
+            positions: vec![AccountPosition {
+                market_id: 0,
+                symbol: "ETH".to_string(),
+                sign: 1,
+                size: "0.5".to_string(),
+                average_entry_price: "2000.00".to_string(),
+                notional_value: String::new(),
+                unrealized_pnl: String::new(),
+                realized_pnl: String::new(),
+                margin_mode: 0,
+                initial_margin_fraction: String::new(),
+                allocated_margin: String::new(),
+                open_order_count: 0,
+            }],
+        };
+        assert!(tracker
+            .reconcile(&account, Decimal::ZERO, Decimal::ZERO)
+            .is_empty());
+
+        account.positions[0].size = "1.5".to_string();
+        let discrepancies = tracker.reconcile(&account, Decimal::ZERO, Decimal::ZERO);
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].exchange_size, Decimal::new(15, 1));
+    }
+}
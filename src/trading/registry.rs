@@ -0,0 +1,241 @@
+use super::LIGHTER_MAX_CLIENT_ORDER_INDEX;
+use crate::error::{LighterError, Result};
+use sha3::{Digest, Keccak256};
+use std::collections::{HashMap, VecDeque};
+
+/// Default number of client orders remembered by the registry.
+pub const DEFAULT_REGISTRY_CAPACITY: usize = 10_000;
+
+/// Caller-chosen identifier for an order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientOrderId {
+    /// Raw Lighter client order index.
+    Index(i64),
+    /// Free-form tag, mapped deterministically onto a client order index so
+    /// the same tag resolves to the same order after a restart.
+    Tag(String),
+}
+
+impl From<i64> for ClientOrderId {
+    fn from(value: i64) -> Self {
+        Self::Index(value)
+    }
+}
+
+impl From<&str> for ClientOrderId {
+    fn from(value: &str) -> Self {
+        Self::Tag(value.to_string())
+    }
+}
+
+impl From<String> for ClientOrderId {
+    fn from(value: String) -> Self {
+        Self::Tag(value)
+    }
+}
+
+impl ClientOrderId {
+    /// Client order index signed into the order.
+    pub fn client_order_index(&self) -> Result<i64> {
+        match self {
+            Self::Index(index) => {
+                if (1..=LIGHTER_MAX_CLIENT_ORDER_INDEX).contains(index) {
+                    Ok(*index)
+                } else {
+                    Err(LighterError::OrderValidation(format!(
+                        "client order index {index} must be between 1 and {LIGHTER_MAX_CLIENT_ORDER_INDEX}"
+                    )))
+                }
+            }
+            Self::Tag(tag) => {
+                if tag.is_empty() {
+                    return Err(LighterError::OrderValidation(
+                        "client order tag must not be empty".to_string(),
+                    ));
+                }
+                let digest = Keccak256::digest(tag.as_bytes());
+                let mut bytes = [0u8; 8];
+                bytes[2..].copy_from_slice(&digest[..6]);
+                let index = i64::from_be_bytes(bytes) & LIGHTER_MAX_CLIENT_ORDER_INDEX;
+                Ok(index.max(1))
+            }
+        }
+    }
+
+    fn tag(&self) -> Option<&str> {
+        match self {
+            Self::Tag(tag) => Some(tag),
+            Self::Index(_) => None,
+        }
+    }
+}
+
+/// What the registry knows about one client order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredOrder {
+    pub client_order_index: i64,
+    pub tag: Option<String>,
+    pub symbol: String,
+    pub market_index: i32,
+    /// Exchange order index, once it has been observed.
+    pub order_index: Option<i64>,
+}
+
+/// Bounded map from client ids to exchange orders. The oldest entries are
+/// evicted first once the capacity is reached.
+#[derive(Debug)]
+pub struct OrderRegistry {
+    capacity: usize,
+    entries: HashMap<i64, RegisteredOrder>,
+    tags: HashMap<String, i64>,
+    insertion_order: VecDeque<i64>,
+}
+
+impl OrderRegistry {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            tags: HashMap::new(),
+            insertion_order: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn insert(&mut self, entry: RegisteredOrder) {
+        let index = entry.client_order_index;
+        if let Some(previous) = self.entries.remove(&index) {
+            self.forget_tag(&previous);
+            self.insertion_order.retain(|existing| *existing != index);
+        }
+
+        while self.entries.len() >= self.capacity {
+            let Some(oldest) = self.insertion_order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.forget_tag(&evicted);
+            }
+        }
+
+        if let Some(tag) = &entry.tag {
+            self.tags.insert(tag.clone(), index);
+        }
+        self.insertion_order.push_back(index);
+        self.entries.insert(index, entry);
+    }
+
+    pub fn get(&self, client_id: &ClientOrderId) -> Option<&RegisteredOrder> {
+        let index = match client_id.tag() {
+            Some(tag) => *self.tags.get(tag)?,
+            None => client_id.client_order_index().ok()?,
+        };
+        self.entries.get(&index)
+    }
+
+    /// Record the exchange order index assigned to a client order.
+    pub fn set_order_index(&mut self, client_order_index: i64, order_index: i64) -> bool {
+        match self.entries.get_mut(&client_order_index) {
+            Some(entry) => {
+                entry.order_index = Some(order_index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, client_id: &ClientOrderId) -> Option<RegisteredOrder> {
+        let index = self.get(client_id)?.client_order_index;
+        self.insertion_order.retain(|existing| *existing != index);
+        let entry = self.entries.remove(&index)?;
+        self.forget_tag(&entry);
+        Some(entry)
+    }
+
+    fn forget_tag(&mut self, entry: &RegisteredOrder) {
+        if let Some(tag) = &entry.tag {
+            if self.tags.get(tag) == Some(&entry.client_order_index) {
+                self.tags.remove(tag);
+            }
+        }
+    }
+}
+
+impl Default for OrderRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_REGISTRY_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(client_order_index: i64, tag: Option<&str>) -> RegisteredOrder {
+        RegisteredOrder {
+            client_order_index,
+            tag: tag.map(str::to_string),
+            symbol: "ETH".to_string(),
+            market_index: 0,
+            order_index: None,
+        }
+    }
+
+    #[test]
+    fn tags_map_to_stable_indices() {
+        let first = ClientOrderId::from("grid-eth-3")
+            .client_order_index()
+            .unwrap();
+        let second = ClientOrderId::from("grid-eth-3")
+            .client_order_index()
+            .unwrap();
+        let other = ClientOrderId::from("grid-eth-4")
+            .client_order_index()
+            .unwrap();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert!((1..=LIGHTER_MAX_CLIENT_ORDER_INDEX).contains(&first));
+
+        assert!(ClientOrderId::Index(0).client_order_index().is_err());
+        assert!(ClientOrderId::from("").client_order_index().is_err());
+    }
+
+    #[test]
+    fn looks_up_by_index_and_tag() {
+        let mut registry = OrderRegistry::new(4);
+        let tagged = ClientOrderId::from("hedge");
+        let index = tagged.client_order_index().unwrap();
+        registry.insert(entry(index, Some("hedge")));
+        registry.insert(entry(7, None));
+
+        assert!(registry.set_order_index(index, 281474976710657));
+        assert_eq!(
+            registry.get(&tagged).unwrap().order_index,
+            Some(281474976710657)
+        );
+        assert_eq!(registry.get(&ClientOrderId::Index(7)).unwrap().tag, None);
+
+        registry.remove(&tagged);
+        assert!(registry.get(&tagged).is_none());
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn evicts_oldest_entries() {
+        let mut registry = OrderRegistry::new(2);
+        registry.insert(entry(1, Some("a")));
+        registry.insert(entry(2, None));
+        registry.insert(entry(3, None));
+
+        assert_eq!(registry.len(), 2);
+        assert!(registry.get(&ClientOrderId::from("a")).is_none());
+        assert!(registry.get(&ClientOrderId::Index(3)).is_some());
+    }
+}
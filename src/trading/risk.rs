@@ -0,0 +1,549 @@
+use super::LighterFfiTradingClient;
+use crate::error::{LighterError, Result};
+use crate::ffi_client::{
+    fetch_account_snapshot, fetch_active_orders, fetch_order_book_orders, AccountOrder,
+};
+use crate::metadata::MarketInfo;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Pre-trade limits checked before an order is signed. Every limit is
+/// optional; an empty set of limits never blocks an order.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Largest notional (size × price) of a single order.
+    pub max_order_notional: Option<Decimal>,
+    /// Largest absolute position in any market if the order and every
+    /// resting order on the same side fill.
+    pub max_position: Option<Decimal>,
+    /// Per-symbol overrides of `max_position`.
+    pub market_max_position: HashMap<String, Decimal>,
+    /// Largest number of resting orders across the account.
+    pub max_open_orders: Option<usize>,
+    /// Largest number of orders submitted in any one second window.
+    pub max_orders_per_second: Option<u32>,
+    /// Largest distance of a limit price from the book mid, as a fraction of
+    /// the mid (`0.05` allows ±5%).
+    pub max_price_deviation: Option<Decimal>,
+}
+
+impl RiskLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_order_notional(mut self, notional: Decimal) -> Self {
+        self.max_order_notional = Some(notional);
+        self
+    }
+
+    pub fn with_max_position(mut self, size: Decimal) -> Self {
+        self.max_position = Some(size);
+        self
+    }
+
+    pub fn with_market_max_position(mut self, symbol: &str, size: Decimal) -> Self {
+        self.market_max_position.insert(symbol.to_uppercase(), size);
+        self
+    }
+
+    pub fn with_max_open_orders(mut self, count: usize) -> Self {
+        self.max_open_orders = Some(count);
+        self
+    }
+
+    pub fn with_max_orders_per_second(mut self, count: u32) -> Self {
+        self.max_orders_per_second = Some(count);
+        self
+    }
+
+    pub fn with_max_price_deviation(mut self, fraction: Decimal) -> Self {
+        self.max_price_deviation = Some(fraction);
+        self
+    }
+
+    fn position_limit(&self, symbol: &str) -> Option<Decimal> {
+        self.market_max_position
+            .get(&symbol.to_uppercase())
+            .copied()
+            .or(self.max_position)
+    }
+}
+
+/// The limit an order would have breached.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    OrderNotional {
+        symbol: String,
+        notional: Decimal,
+        limit: Decimal,
+    },
+    Position {
+        symbol: String,
+        projected: Decimal,
+        limit: Decimal,
+    },
+    OpenOrders {
+        open: usize,
+        limit: usize,
+    },
+    OrderRate {
+        limit: u32,
+    },
+    PriceBand {
+        symbol: String,
+        price: Decimal,
+        mid: Decimal,
+        max_deviation: Decimal,
+    },
+    /// The price band could not be checked because one side of the book is
+    /// empty.
+    NoReferencePrice {
+        symbol: String,
+    },
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OrderNotional {
+                symbol,
+                notional,
+                limit,
+            } => write!(f, "{symbol} order notional {notional} exceeds {limit}"),
+            Self::Position {
+                symbol,
+                projected,
+                limit,
+            } => write!(
+                f,
+                "{symbol} position would reach {projected}, limit is {limit}"
+            ),
+            Self::OpenOrders { open, limit } => {
+                write!(f, "{open} open orders already, limit is {limit}")
+            }
+            Self::OrderRate { limit } => write!(f, "more than {limit} orders per second"),
+            Self::PriceBand {
+                symbol,
+                price,
+                mid,
+                max_deviation,
+            } => write!(
+                f,
+                "{symbol} price {price} is more than {max_deviation} away from mid {mid}"
+            ),
+            Self::NoReferencePrice { symbol } => {
+                write!(f, "{symbol} has no mid price to check against")
+            }
+        }
+    }
+}
+
+impl From<RiskViolation> for LighterError {
+    fn from(value: RiskViolation) -> Self {
+        LighterError::RiskLimit(value)
+    }
+}
+
+/// An order as seen by the risk checks.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RiskOrder {
+    pub size: Decimal,
+    pub price: Decimal,
+    pub is_ask: bool,
+    pub reduce_only: bool,
+    /// Trigger orders rest away from the mid by design and skip the band.
+    pub is_trigger: bool,
+}
+
+impl RiskOrder {
+    pub fn from_scaled(
+        info: &MarketInfo,
+        amount: i64,
+        price: i32,
+        is_ask: bool,
+        reduce_only: bool,
+        is_trigger: bool,
+    ) -> Self {
+        Self {
+            size: Decimal::new(amount, info.supported_size_decimals.unwrap_or(0)),
+            price: Decimal::new(price as i64, info.supported_price_decimals.unwrap_or(0)),
+            is_ask,
+            reduce_only,
+            is_trigger,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct RiskGuard {
+    limits: RiskLimits,
+    recent: Mutex<VecDeque<Instant>>,
+}
+
+impl RiskGuard {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn check_notional(&self, symbol: &str, order: &RiskOrder) -> Result<()> {
+        let Some(limit) = self.limits.max_order_notional else {
+            return Ok(());
+        };
+        let notional = order.size * order.price;
+        if notional > limit {
+            return Err(RiskViolation::OrderNotional {
+                symbol: symbol.to_string(),
+                notional,
+                limit,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn check_price_band(&self, symbol: &str, price: Decimal, mid: Option<Decimal>) -> Result<()> {
+        let Some(max_deviation) = self.limits.max_price_deviation else {
+            return Ok(());
+        };
+        let Some(mid) = mid.filter(|mid| !mid.is_zero()) else {
+            return Err(RiskViolation::NoReferencePrice {
+                symbol: symbol.to_string(),
+            }
+            .into());
+        };
+        if ((price - mid) / mid).abs() > max_deviation {
+            return Err(RiskViolation::PriceBand {
+                symbol: symbol.to_string(),
+                price,
+                mid,
+                max_deviation,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Check the position reached if `order` and the `resting` orders on its
+    /// side all fill.
+    fn check_position(
+        &self,
+        symbol: &str,
+        current: Decimal,
+        resting: Decimal,
+        order: &RiskOrder,
+    ) -> Result<()> {
+        let Some(limit) = self.limits.position_limit(symbol) else {
+            return Ok(());
+        };
+        let projected = if order.is_ask {
+            current - resting - order.size
+        } else {
+            current + resting + order.size
+        };
+        // Orders that shrink the position are always allowed.
+        if projected.abs() > limit && projected.abs() > current.abs() {
+            return Err(RiskViolation::Position {
+                symbol: symbol.to_string(),
+                projected,
+                limit,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn check_open_orders(&self, open: usize, new_orders: usize) -> Result<()> {
+        let Some(limit) = self.limits.max_open_orders else {
+            return Ok(());
+        };
+        if open + new_orders > limit {
+            return Err(RiskViolation::OpenOrders { open, limit }.into());
+        }
+        Ok(())
+    }
+
+    /// Count `orders` against the rate window, failing without recording
+    /// anything if the window is full.
+    fn acquire_rate(&self, orders: usize, now: Instant) -> Result<()> {
+        let Some(limit) = self.limits.max_orders_per_second else {
+            return Ok(());
+        };
+        let mut recent = self
+            .recent
+            .lock()
+            .map_err(|_| LighterError::Unknown("risk rate window poisoned".to_string()))?;
+        while let Some(oldest) = recent.front() {
+            if now.duration_since(*oldest) >= Duration::from_secs(1) {
+                recent.pop_front();
+            } else {
+                break;
+            }
+        }
+        if recent.len() + orders > limit as usize {
+            return Err(RiskViolation::OrderRate { limit }.into());
+        }
+        recent.extend(std::iter::repeat_n(now, orders));
+        Ok(())
+    }
+
+    fn needs_account(&self) -> bool {
+        self.limits.max_open_orders.is_some()
+            || self.limits.max_position.is_some()
+            || !self.limits.market_max_position.is_empty()
+    }
+}
+
+impl LighterFfiTradingClient {
+    /// Replace the pre-trade risk limits applied to new and modified orders.
+    pub fn with_risk_limits(self, limits: RiskLimits) -> Self {
+        Self {
+            risk: Some(RiskGuard::new(limits)),
+            ..self
+        }
+    }
+
+    pub fn risk_limits(&self) -> Option<&RiskLimits> {
+        self.risk.as_ref().map(|guard| &guard.limits)
+    }
+
+    /// Check orders for one market against the configured limits, failing
+    /// on the first breach.
+    pub(super) async fn enforce_risk(
+        &self,
+        info: &MarketInfo,
+        orders: &[RiskOrder],
+        new_orders: bool,
+    ) -> Result<()> {
+        self.check_risk(info, orders, new_orders)
+            .await?
+            .into_iter()
+            .collect()
+    }
+
+    /// Check orders for one market against the configured limits, returning
+    /// an outcome per order. The book and account are fetched at most once,
+    /// and open-order and position limits count the orders cumulatively; an
+    /// order that fails is not counted against the ones after it. Local
+    /// limits are checked first so a breach costs no network round trip.
+    pub(super) async fn check_risk(
+        &self,
+        info: &MarketInfo,
+        orders: &[RiskOrder],
+        new_orders: bool,
+    ) -> Result<Vec<Result<()>>> {
+        let Some(guard) = &self.risk else {
+            return Ok(orders.iter().map(|_| Ok(())).collect());
+        };
+        let mut results: Vec<Result<()>> = orders
+            .iter()
+            .map(|order| guard.check_notional(&info.symbol, order))
+            .collect();
+
+        let priced = |results: &[Result<()>]| {
+            orders
+                .iter()
+                .zip(results)
+                .any(|(order, result)| result.is_ok() && !order.is_trigger)
+        };
+        if guard.limits.max_price_deviation.is_some() && priced(&results) {
+            let book = fetch_order_book_orders(&self.api_client, info.market_id, 1).await?;
+            for (order, result) in orders.iter().zip(results.iter_mut()) {
+                if result.is_ok() && !order.is_trigger {
+                    *result = guard.check_price_band(&info.symbol, order.price, book.mid_price());
+                }
+            }
+        }
+
+        if new_orders && guard.needs_account() && results.iter().any(Result::is_ok) {
+            let account = fetch_account_snapshot(&self.api_client, self.account_index).await?;
+            let mut open = usize::try_from(account.total_order_count).unwrap_or(0);
+            let position = account
+                .positions
+                .iter()
+                .find(|position| position.market_id == info.market_id)
+                .and_then(|position| position.signed_size())
+                .unwrap_or_default();
+            let (mut bids, mut asks) = if guard.limits.position_limit(&info.symbol).is_some() {
+                self.resting_exposure(info).await?
+            } else {
+                (Decimal::ZERO, Decimal::ZERO)
+            };
+
+            for (order, result) in orders.iter().zip(results.iter_mut()) {
+                if result.is_err() {
+                    continue;
+                }
+                if let Err(err) = guard.check_open_orders(open, 1) {
+                    *result = Err(err);
+                    continue;
+                }
+                if !order.reduce_only {
+                    let resting = if order.is_ask { &mut asks } else { &mut bids };
+                    if let Err(err) = guard.check_position(&info.symbol, position, *resting, order)
+                    {
+                        *result = Err(err);
+                        continue;
+                    }
+                    *resting += order.size;
+                }
+                open += 1;
+            }
+        }
+
+        let now = Instant::now();
+        for result in results.iter_mut().filter(|result| result.is_ok()) {
+            *result = guard.acquire_rate(1, now);
+        }
+        Ok(results)
+    }
+
+    /// Base size of this account's resting bids and asks in `info`'s
+    /// market. Reduce-only orders cannot grow the position and are skipped.
+    async fn resting_exposure(&self, info: &MarketInfo) -> Result<(Decimal, Decimal)> {
+        let orders =
+            fetch_active_orders(&self.api_client, self.account_index, info.market_id).await?;
+        Ok(resting_sizes(&orders))
+    }
+}
+
+fn resting_sizes(orders: &[AccountOrder]) -> (Decimal, Decimal) {
+    let mut bids = Decimal::ZERO;
+    let mut asks = Decimal::ZERO;
+    for order in orders.iter().filter(|order| !order.reduce_only) {
+        let remaining = Decimal::from_str(&order.remaining_base_amount).unwrap_or_default();
+        if order.is_ask {
+            asks += remaining;
+        } else {
+            bids += remaining;
+        }
+    }
+    (bids, asks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(size: i64, price: i64, is_ask: bool) -> RiskOrder {
+        RiskOrder {
+            size: Decimal::from(size),
+            price: Decimal::from(price),
+            is_ask,
+            reduce_only: false,
+            is_trigger: false,
+        }
+    }
+
+    #[test]
+    fn blocks_fat_finger_orders() {
+        let guard = RiskGuard::new(
+            RiskLimits::new()
+                .with_max_order_notional(Decimal::from(10_000))
+                .with_max_price_deviation(Decimal::new(5, 2)),
+        );
+        assert!(guard.check_notional("ETH", &order(3, 3_000, false)).is_ok());
+        assert!(matches!(
+            guard.check_notional("ETH", &order(300, 3_000, false)),
+            Err(LighterError::RiskLimit(RiskViolation::OrderNotional { .. }))
+        ));
+
+        let mid = Some(Decimal::from(3_000));
+        assert!(guard
+            .check_price_band("ETH", Decimal::from(3_100), mid)
+            .is_ok());
+        assert!(matches!(
+            guard.check_price_band("ETH", Decimal::from(300), mid),
+            Err(LighterError::RiskLimit(RiskViolation::PriceBand { .. }))
+        ));
+        assert!(matches!(
+            guard.check_price_band("ETH", Decimal::from(3_000), None),
+            Err(LighterError::RiskLimit(
+                RiskViolation::NoReferencePrice { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn limits_position_growth_but_not_reduction() {
+        let guard = RiskGuard::new(
+            RiskLimits::new()
+                .with_max_position(Decimal::from(10))
+                .with_market_max_position("btc", Decimal::ONE),
+        );
+        let none = Decimal::ZERO;
+        assert!(guard
+            .check_position("ETH", Decimal::from(8), none, &order(2, 1, false))
+            .is_ok());
+        assert!(guard
+            .check_position("ETH", Decimal::from(8), none, &order(3, 1, false))
+            .is_err());
+        assert!(guard
+            .check_position("ETH", Decimal::from(12), none, &order(1, 1, true))
+            .is_ok());
+        assert!(guard
+            .check_position("BTC", Decimal::ZERO, none, &order(2, 1, true))
+            .is_err());
+    }
+
+    #[test]
+    fn counts_resting_orders_towards_position() {
+        let guard = RiskGuard::new(RiskLimits::new().with_max_position(Decimal::from(10)));
+        let resting: Vec<AccountOrder> = serde_json::from_value(serde_json::json!([
+            {"order_index": 1, "remaining_base_amount": "4", "is_ask": false},
+            {"order_index": 2, "remaining_base_amount": "5", "is_ask": false},
+            {"order_index": 3, "remaining_base_amount": "6", "is_ask": true},
+            {"order_index": 4, "remaining_base_amount": "9", "is_ask": false, "reduce_only": true}
+        ]))
+        .unwrap();
+        let (bids, asks) = resting_sizes(&resting);
+        assert_eq!((bids, asks), (Decimal::from(9), Decimal::from(6)));
+
+        // Each bid alone is under the limit, but not on top of those resting.
+        assert!(guard
+            .check_position("ETH", Decimal::ZERO, bids, &order(2, 1, false))
+            .is_err());
+        assert!(guard
+            .check_position("ETH", Decimal::ZERO, bids, &order(1, 1, false))
+            .is_ok());
+        assert!(guard
+            .check_position("ETH", Decimal::ZERO, asks, &order(4, 1, true))
+            .is_ok());
+    }
+
+    #[test]
+    fn rate_window_slides() {
+        let guard = RiskGuard::new(RiskLimits::new().with_max_orders_per_second(2));
+        let start = Instant::now();
+        assert!(guard.acquire_rate(2, start).is_ok());
+        assert!(matches!(
+            guard.acquire_rate(1, start + Duration::from_millis(500)),
+            Err(LighterError::RiskLimit(RiskViolation::OrderRate {
+                limit: 2
+            }))
+        ));
+        assert!(guard
+            .acquire_rate(1, start + Duration::from_millis(1_000))
+            .is_ok());
+    }
+
+    #[test]
+    fn counts_new_orders_against_open_limit() {
+        let guard = RiskGuard::new(RiskLimits::new().with_max_open_orders(3));
+        assert!(guard.check_open_orders(2, 1).is_ok());
+        assert!(matches!(
+            guard.check_open_orders(3, 1),
+            Err(LighterError::RiskLimit(RiskViolation::OpenOrders {
+                open: 3,
+                limit: 3
+            }))
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+use super::{LighterFfiTradingClient, OrderOptions, SubmittedOrder};
+use crate::error::{LighterError, Result};
+use crate::ffi_client::{fetch_order_book_orders, BookOrder, OrderBookOrders};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Number of resting orders per side fetched to price a market order.
+pub const SLIPPAGE_BOOK_DEPTH: u32 = 100;
+
+const BPS_DENOMINATOR: i64 = 10_000;
+
+/// Expected execution of a market order against a book snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillEstimate {
+    /// Volume weighted price over the levels needed to fill the order.
+    pub average_price: Decimal,
+    /// Price of the last level touched.
+    pub worst_price: Decimal,
+    /// Worst acceptable price: the average moved against the taker by the
+    /// allowed slippage.
+    pub limit_price: Decimal,
+}
+
+/// Walk the side of `book` a taker would hit and price `base_amount` against
+/// it. Fails if the book cannot fill the whole amount at or inside the
+/// slippage-adjusted limit.
+pub fn estimate_market_fill(
+    book: &OrderBookOrders,
+    is_buy: bool,
+    base_amount: &Decimal,
+    max_slippage_bps: u32,
+) -> Result<FillEstimate> {
+    if *base_amount <= Decimal::ZERO {
+        return Err(LighterError::OrderValidation(format!(
+            "market order size {base_amount} must be positive"
+        )));
+    }
+    let levels = if is_buy { &book.asks } else { &book.bids };
+
+    let (filled, cost, worst_price) = walk(levels, base_amount, None)?;
+    if filled < *base_amount {
+        return Err(LighterError::OrderValidation(format!(
+            "order book too thin: only {filled} of {base_amount} available"
+        )));
+    }
+    let average_price = cost / filled;
+
+    let slippage = Decimal::from(max_slippage_bps) / Decimal::from(BPS_DENOMINATOR);
+    let limit_price = if is_buy {
+        average_price * (Decimal::ONE + slippage)
+    } else {
+        average_price * (Decimal::ONE - slippage)
+    };
+
+    if (is_buy && worst_price > limit_price) || (!is_buy && worst_price < limit_price) {
+        let (within_limit, _, _) = walk(levels, base_amount, Some((is_buy, limit_price)))?;
+        return Err(LighterError::OrderValidation(format!(
+            "order book too thin: only {within_limit} of {base_amount} available within {max_slippage_bps} bps of {average_price}"
+        )));
+    }
+
+    Ok(FillEstimate {
+        average_price,
+        worst_price,
+        limit_price,
+    })
+}
+
+/// Accumulate size and cost over `levels` until `base_amount` is filled,
+/// optionally stopping at a price bound.
+fn walk(
+    levels: &[BookOrder],
+    base_amount: &Decimal,
+    bound: Option<(bool, Decimal)>,
+) -> Result<(Decimal, Decimal, Decimal)> {
+    let mut filled = Decimal::ZERO;
+    let mut cost = Decimal::ZERO;
+    let mut worst_price = Decimal::ZERO;
+    for level in levels {
+        let (Some(price), Some(size)) = (level.price(), level.remaining_base_amount()) else {
+            return Err(LighterError::Unknown(format!(
+                "unparseable order book level: {} @ {}",
+                level.remaining_base_amount, level.price
+            )));
+        };
+        if let Some((is_buy, limit)) = bound {
+            if (is_buy && price > limit) || (!is_buy && price < limit) {
+                break;
+            }
+        }
+        let take = size.min(*base_amount - filled);
+        filled += take;
+        cost += take * price;
+        worst_price = price;
+        if filled >= *base_amount {
+            break;
+        }
+    }
+    Ok((filled, cost, worst_price))
+}
+
+impl LighterFfiTradingClient {
+    /// Submit a market order whose worst acceptable price is derived from
+    /// the current book instead of supplied by the caller.
+    pub async fn create_market_order_with_slippage(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        max_slippage_bps: u32,
+        reduce_only: bool,
+    ) -> Result<SubmittedOrder> {
+        self.create_market_order_with_slippage_and_options(
+            symbol,
+            is_buy,
+            base_amount,
+            max_slippage_bps,
+            reduce_only,
+            OrderOptions::default(),
+        )
+        .await
+    }
+
+    /// Like [`Self::create_market_order_with_slippage`] with a
+    /// caller-supplied client id.
+    pub async fn create_market_order_with_slippage_and_options(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        max_slippage_bps: u32,
+        reduce_only: bool,
+        options: OrderOptions,
+    ) -> Result<SubmittedOrder> {
+        let info = self.market(symbol).await?;
+        let book =
+            fetch_order_book_orders(&self.api_client, info.market_id, SLIPPAGE_BOOK_DEPTH).await?;
+        self.market_order_from_book(
+            symbol,
+            is_buy,
+            base_amount,
+            &book,
+            max_slippage_bps,
+            reduce_only,
+            options,
+        )
+        .await
+    }
+
+    /// Like [`Self::create_market_order_with_slippage`], pricing against a
+    /// book the caller already maintains. A streamed [`OrderBook`] converts
+    /// with `OrderBookOrders::from(&update.book)`.
+    ///
+    /// [`OrderBook`]: crate::models::common::OrderBook
+    pub async fn create_market_order_from_book(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        book: &OrderBookOrders,
+        max_slippage_bps: u32,
+        reduce_only: bool,
+    ) -> Result<SubmittedOrder> {
+        self.market_order_from_book(
+            symbol,
+            is_buy,
+            base_amount,
+            book,
+            max_slippage_bps,
+            reduce_only,
+            OrderOptions::default(),
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn market_order_from_book(
+        &self,
+        symbol: &str,
+        is_buy: bool,
+        base_amount: &Decimal,
+        book: &OrderBookOrders,
+        max_slippage_bps: u32,
+        reduce_only: bool,
+        options: OrderOptions,
+    ) -> Result<SubmittedOrder> {
+        let info = self.market(symbol).await?;
+        let estimate = estimate_market_fill(book, is_buy, base_amount, max_slippage_bps)?;
+        // Round the limit towards the book so it stays inside the slippage
+        // bound once snapped to the tick.
+        let strategy = if is_buy {
+            RoundingStrategy::ToZero
+        } else {
+            RoundingStrategy::AwayFromZero
+        };
+        let limit_price = estimate
+            .limit_price
+            .round_dp_with_strategy(info.supported_price_decimals.unwrap_or(0), strategy);
+        self.create_market_order_with_options(
+            symbol,
+            is_buy,
+            base_amount,
+            &limit_price,
+            reduce_only,
+            options,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::{OrderBook, PriceLevel};
+
+    fn level(price: &str, size: &str) -> BookOrder {
+        BookOrder {
+            order_index: 1,
+            price: price.to_string(),
+            remaining_base_amount: size.to_string(),
+        }
+    }
+
+    fn book() -> OrderBookOrders {
+        OrderBookOrders {
+            asks: vec![level("100.00", "1.0"), level("101.00", "1.0")],
+            bids: vec![level("99.00", "0.5"), level("98.00", "2.0")],
+        }
+    }
+
+    #[test]
+    fn walks_levels_for_the_average_price() {
+        let estimate = estimate_market_fill(&book(), true, &Decimal::new(15, 1), 100).unwrap();
+        // 1.0 @ 100 + 0.5 @ 101 over 1.5
+        assert_eq!(
+            estimate.average_price.round_dp(4),
+            Decimal::new(1_003_333, 4)
+        );
+        assert_eq!(estimate.worst_price, Decimal::from(101));
+        assert!(estimate.limit_price > estimate.average_price);
+
+        let sell = estimate_market_fill(&book(), false, &Decimal::ONE, 200).unwrap();
+        assert_eq!(sell.average_price, Decimal::new(985, 1));
+        assert!(sell.limit_price < sell.average_price);
+    }
+
+    #[test]
+    fn prices_streamed_books() {
+        let streamed = OrderBook {
+            bids: vec![PriceLevel {
+                price: "99.00".to_string(),
+                quantity: "0.5".to_string(),
+            }],
+            asks: vec![
+                PriceLevel {
+                    price: "100.00".to_string(),
+                    quantity: "1.0".to_string(),
+                },
+                PriceLevel {
+                    price: "101.00".to_string(),
+                    quantity: "1.0".to_string(),
+                },
+            ],
+            timestamp: chrono::Utc::now(),
+        };
+        let converted = OrderBookOrders::from(&streamed);
+        assert_eq!(converted.best_bid(), Some(Decimal::from(99)));
+
+        let size = Decimal::new(15, 1);
+        assert_eq!(
+            estimate_market_fill(&converted, true, &size, 100).unwrap(),
+            estimate_market_fill(&book(), true, &size, 100).unwrap()
+        );
+    }
+
+    #[test]
+    fn refuses_thin_books() {
+        let too_large = estimate_market_fill(&book(), true, &Decimal::from(3), 500);
+        assert!(matches!(too_large, Err(LighterError::OrderValidation(_))));
+
+        // Filling 2.0 needs the 101 level, more than 10 bps above the average.
+        let too_tight = estimate_market_fill(&book(), true, &Decimal::from(2), 10);
+        assert!(matches!(too_tight, Err(LighterError::OrderValidation(_))));
+    }
+}
@@ -0,0 +1,502 @@
+use super::SubmittedOrder;
+use crate::ffi_client::AccountOrder;
+use crate::models::common::OrderStatus;
+use crate::models::order::Order;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Lifecycle state of a locally tracked order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OrderState {
+    Submitted,
+    Acknowledged,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+    Expired,
+}
+
+impl OrderState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Filled | Self::Cancelled | Self::Rejected | Self::Expired
+        )
+    }
+
+    /// Map a Lighter order status string such as `open` or
+    /// `canceled-expired`.
+    pub fn from_lighter_status(status: &str) -> Option<Self> {
+        let state = match status {
+            "in-progress" => Self::Submitted,
+            "pending" | "open" => Self::Acknowledged,
+            "filled" => Self::Filled,
+            "canceled-expired" => Self::Expired,
+            "canceled-post-only"
+            | "canceled-reduce-only"
+            | "canceled-position-not-allowed"
+            | "canceled-margin-not-allowed"
+            | "canceled-invalid-balance" => Self::Rejected,
+            other if other.starts_with("cancel") => Self::Cancelled,
+            _ => return None,
+        };
+        Some(state)
+    }
+}
+
+impl From<OrderStatus> for OrderState {
+    fn from(value: OrderStatus) -> Self {
+        match value {
+            OrderStatus::Pending => Self::Submitted,
+            OrderStatus::Open => Self::Acknowledged,
+            OrderStatus::PartiallyFilled => Self::PartiallyFilled,
+            OrderStatus::Filled => Self::Filled,
+            OrderStatus::Cancelled => Self::Cancelled,
+            OrderStatus::Rejected => Self::Rejected,
+            OrderStatus::Expired => Self::Expired,
+        }
+    }
+}
+
+/// Snapshot of a tracked order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedOrder {
+    pub client_order_index: i64,
+    pub order_index: Option<i64>,
+    pub market_index: i32,
+    pub state: OrderState,
+    pub is_ask: bool,
+    pub filled_base: Decimal,
+    pub filled_quote: Decimal,
+    pub tx_hash: Option<String>,
+}
+
+impl TrackedOrder {
+    /// Volume weighted fill price, if anything has filled.
+    pub fn average_price(&self) -> Option<Decimal> {
+        if self.filled_base.is_zero() {
+            None
+        } else {
+            Some(self.filled_quote / self.filled_base)
+        }
+    }
+}
+
+/// A state change reported to transition callbacks. Further fills on a
+/// partially filled order are reported with `from == to`.
+#[derive(Debug, Clone)]
+pub struct OrderTransition {
+    pub from: OrderState,
+    pub to: OrderState,
+    pub order: TrackedOrder,
+}
+
+type TransitionCallback = Box<dyn Fn(&OrderTransition) + Send + Sync>;
+
+/// Follows orders through their lifecycle from submission to a terminal
+/// state. Updates may arrive out of order from different feeds; states never
+/// move backwards and fill quantities never shrink.
+///
+/// Only orders started with [`Self::track_submission`] or reported by a
+/// venue through [`Self::apply_tracked`] are followed. Account and REST
+/// updates for any other order, e.g. one placed by another process on the
+/// same account, are ignored.
+#[derive(Default)]
+pub struct OrderTracker {
+    orders: HashMap<i64, TrackedOrder>,
+    by_order_index: HashMap<i64, i64>,
+    callbacks: Vec<TransitionCallback>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a callback invoked on every state transition.
+    pub fn on_transition<F>(&mut self, callback: F)
+    where
+        F: Fn(&OrderTransition) + Send + Sync + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    pub fn get(&self, client_order_index: i64) -> Option<&TrackedOrder> {
+        self.orders.get(&client_order_index)
+    }
+
+    pub fn get_by_order_index(&self, order_index: i64) -> Option<&TrackedOrder> {
+        self.by_order_index
+            .get(&order_index)
+            .and_then(|client_index| self.orders.get(client_index))
+    }
+
+    /// Orders that have not reached a terminal state.
+    pub fn open_orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders
+            .values()
+            .filter(|order| !order.state.is_terminal())
+    }
+
+    /// Drop orders in a terminal state and return how many were removed.
+    pub fn prune_terminal(&mut self) -> usize {
+        let before = self.orders.len();
+        self.orders.retain(|_, order| !order.state.is_terminal());
+        let orders = &self.orders;
+        self.by_order_index
+            .retain(|_, client_index| orders.contains_key(client_index));
+        before - self.orders.len()
+    }
+
+    /// Start tracking an order accepted by `sendTx`.
+    pub fn track_submission(&mut self, submitted: &SubmittedOrder) {
+        let order = &submitted.order;
+        self.orders
+            .entry(order.client_order_index)
+            .or_insert_with(|| TrackedOrder {
+                client_order_index: order.client_order_index,
+                order_index: None,
+                market_index: order.market_index,
+                state: OrderState::Submitted,
+                is_ask: order.is_ask,
+                filled_base: Decimal::ZERO,
+                filled_quote: Decimal::ZERO,
+                tx_hash: submitted.response.tx_hash.clone(),
+            });
+    }
+
+    /// Mark an order as rejected, e.g. after `sendTx` returned an error.
+    pub fn mark_rejected(&mut self, client_order_index: i64) -> Option<OrderTransition> {
+        self.transition(client_order_index, OrderState::Rejected, None, None)
+    }
+
+    /// Apply an order from the account order endpoints or the account WS
+    /// stream. Orders not tracked yet are ignored.
+    pub fn apply_account_order(&mut self, update: &AccountOrder) -> Option<OrderTransition> {
+        let state = OrderState::from_lighter_status(&update.status)?;
+        let client_order_index = self.resolve_client_index(
+            Some(update.client_order_index).filter(|index| *index > 0),
+            Some(update.order_index),
+        )?;
+        if !self.orders.contains_key(&client_order_index) {
+            return None;
+        }
+
+        let fills = parse_decimal(&update.filled_base_amount)
+            .zip(parse_decimal(&update.filled_quote_amount));
+        self.transition(client_order_index, state, Some(update.order_index), fills)
+    }
+
+    /// Apply every order contained in an account WS message. Lighter sends
+    /// orders either as a list or keyed by market index.
+    pub fn apply_ws_message(&mut self, message: &serde_json::Value) -> Vec<OrderTransition> {
+        let Some(orders) = message.get("orders") else {
+            return Vec::new();
+        };
+        let entries: Vec<&serde_json::Value> = match orders {
+            serde_json::Value::Array(list) => list.iter().collect(),
+            serde_json::Value::Object(by_market) => by_market
+                .values()
+                .filter_map(serde_json::Value::as_array)
+                .flatten()
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        entries
+            .into_iter()
+            .filter_map(|entry| serde_json::from_value::<AccountOrder>(entry.clone()).ok())
+            .filter_map(|order| self.apply_account_order(&order))
+            .collect()
+    }
+
+    /// Apply an order snapshot from a venue that reports orders directly,
+    /// such as [`crate::paper::PaperExchange`].
+    pub fn apply_tracked(&mut self, update: &TrackedOrder) -> Option<OrderTransition> {
+        self.orders
+            .entry(update.client_order_index)
+            .or_insert_with(|| TrackedOrder {
+                state: OrderState::Submitted,
+                filled_base: Decimal::ZERO,
+                filled_quote: Decimal::ZERO,
+                ..update.clone()
+            });
+        self.transition(
+            update.client_order_index,
+            update.state,
+            update.order_index,
+            Some((update.filled_base, update.filled_quote)),
+        )
+    }
+
+    /// Apply an order returned by REST polling of `OrderApi::get_orders`.
+    /// Orders not tracked yet are ignored.
+    pub fn apply_order(&mut self, order: &Order) -> Option<OrderTransition> {
+        let client_index = order
+            .client_order_id
+            .as_deref()
+            .and_then(|id| id.parse::<i64>().ok());
+        let order_index = order.id.parse::<i64>().ok();
+        let client_order_index = self.resolve_client_index(client_index, order_index)?;
+        if !self.orders.contains_key(&client_order_index) {
+            return None;
+        }
+
+        let filled_base = parse_decimal(&order.filled_quantity);
+        let fills = filled_base.map(|base| {
+            let price = order
+                .average_fill_price
+                .as_deref()
+                .and_then(parse_decimal)
+                .unwrap_or_default();
+            (base, base * price)
+        });
+        self.transition(client_order_index, order.status.into(), order_index, fills)
+    }
+
+    fn resolve_client_index(
+        &self,
+        client_order_index: Option<i64>,
+        order_index: Option<i64>,
+    ) -> Option<i64> {
+        client_order_index
+            .or_else(|| order_index.and_then(|index| self.by_order_index.get(&index).copied()))
+    }
+
+    fn transition(
+        &mut self,
+        client_order_index: i64,
+        reported: OrderState,
+        order_index: Option<i64>,
+        fills: Option<(Decimal, Decimal)>,
+    ) -> Option<OrderTransition> {
+        let order = self.orders.get_mut(&client_order_index)?;
+        if let Some(order_index) = order_index {
+            order.order_index = Some(order_index);
+            self.by_order_index.insert(order_index, client_order_index);
+        }
+
+        let mut filled_more = false;
+        if let Some((base, quote)) = fills {
+            if base > order.filled_base {
+                order.filled_base = base;
+                order.filled_quote = quote;
+                filled_more = true;
+            }
+        }
+
+        let mut next = reported;
+        if next == OrderState::Acknowledged && !order.filled_base.is_zero() {
+            next = OrderState::PartiallyFilled;
+        }
+
+        let from = order.state;
+        let advances = !from.is_terminal() && next > from;
+        let refilled = filled_more && from == OrderState::PartiallyFilled;
+        if !advances && !refilled {
+            return None;
+        }
+        if advances {
+            order.state = next;
+        }
+
+        let transition = OrderTransition {
+            from,
+            to: order.state,
+            order: order.clone(),
+        };
+        for callback in &self.callbacks {
+            callback(&transition);
+        }
+        Some(transition)
+    }
+}
+
+impl std::fmt::Debug for OrderTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OrderTracker")
+            .field("orders", &self.orders)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+fn parse_decimal(value: &str) -> Option<Decimal> {
+    if value.is_empty() {
+        return None;
+    }
+    Decimal::from_str(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::transaction_api::TxResponse;
+    use crate::signers::SignedCreateOrder;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn submitted(client_order_index: i64) -> SubmittedOrder {
+        SubmittedOrder {
+            order: SignedCreateOrder {
+                account_index: 1,
+                api_key_index: 0,
+                market_index: 0,
+                client_order_index,
+                base_amount: 1000,
+                price: 300000,
+                is_ask: false,
+                order_type: 0,
+                time_in_force: 1,
+                reduce_only: false,
+                trigger_price: 0,
+                order_expiry: -1,
+                expired_at: 0,
+                nonce: 5,
+                signature: None,
+            },
+            response: TxResponse {
+                code: 200,
+                tx_hash: Some("0xabc".to_string()),
+                message: None,
+                predicted_execution_time_ms: None,
+            },
+        }
+    }
+
+    fn account_order(status: &str, filled_base: &str, filled_quote: &str) -> AccountOrder {
+        serde_json::from_value(serde_json::json!({
+            "order_index": 900,
+            "client_order_index": 11,
+            "market_index": 0,
+            "filled_base_amount": filled_base,
+            "filled_quote_amount": filled_quote,
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn follows_fills_to_completion() {
+        let mut tracker = OrderTracker::new();
+        let transitions = Arc::new(AtomicUsize::new(0));
+        let counter = transitions.clone();
+        tracker.on_transition(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        tracker.track_submission(&submitted(11));
+        assert_eq!(tracker.get(11).unwrap().state, OrderState::Submitted);
+
+        let ack = tracker
+            .apply_account_order(&account_order("open", "0", "0"))
+            .unwrap();
+        assert_eq!(ack.to, OrderState::Acknowledged);
+
+        let partial = tracker
+            .apply_account_order(&account_order("open", "0.5", "1500"))
+            .unwrap();
+        assert_eq!(partial.to, OrderState::PartiallyFilled);
+        assert_eq!(
+            partial.order.average_price(),
+            Some(Decimal::from_str("3000").unwrap())
+        );
+
+        let filled = tracker
+            .apply_account_order(&account_order("filled", "1", "3100"))
+            .unwrap();
+        assert_eq!(filled.to, OrderState::Filled);
+        assert_eq!(
+            tracker.get_by_order_index(900).unwrap().average_price(),
+            Some(Decimal::from_str("3100").unwrap())
+        );
+
+        // Stale updates after a terminal state are ignored.
+        assert!(tracker
+            .apply_account_order(&account_order("open", "0.5", "1500"))
+            .is_none());
+        assert_eq!(transitions.load(Ordering::SeqCst), 3);
+        assert_eq!(tracker.prune_terminal(), 1);
+    }
+
+    #[test]
+    fn maps_lighter_statuses() {
+        assert_eq!(
+            OrderState::from_lighter_status("canceled-expired"),
+            Some(OrderState::Expired)
+        );
+        assert_eq!(
+            OrderState::from_lighter_status("canceled-post-only"),
+            Some(OrderState::Rejected)
+        );
+        assert_eq!(
+            OrderState::from_lighter_status("canceled"),
+            Some(OrderState::Cancelled)
+        );
+        assert_eq!(OrderState::from_lighter_status("unknown"), None);
+    }
+
+    #[test]
+    fn applies_ws_orders_keyed_by_market() {
+        let mut tracker = OrderTracker::new();
+        tracker.track_submission(&submitted(11));
+        let message = serde_json::json!({
+            "type": "update/account_orders",
+            "orders": {
+                "0": [{
+                    "order_index": 900,
+                    "client_order_index": 11,
+                    "market_index": 0,
+                    "status": "canceled"
+                }]
+            }
+        });
+        let transitions = tracker.apply_ws_message(&message);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].to, OrderState::Cancelled);
+    }
+
+    #[test]
+    fn ignores_untracked_orders_on_every_feed() {
+        let mut tracker = OrderTracker::new();
+        assert!(tracker
+            .apply_account_order(&account_order("open", "0", "0"))
+            .is_none());
+
+        let rest = Order {
+            id: "900".to_string(),
+            client_order_id: Some("11".to_string()),
+            symbol: "ETH".to_string(),
+            side: crate::models::common::Side::Buy,
+            order_type: crate::models::common::OrderType::Limit,
+            status: OrderStatus::Open,
+            quantity: "1".to_string(),
+            price: Some("3000".to_string()),
+            stop_price: None,
+            filled_quantity: "0".to_string(),
+            remaining_quantity: "1".to_string(),
+            average_fill_price: None,
+            fee: None,
+            time_in_force: crate::models::order::TimeInForce::Gtc,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            expires_at: None,
+        };
+        assert!(tracker.apply_order(&rest).is_none());
+        assert!(tracker.get(11).is_none());
+        assert_eq!(tracker.open_orders().count(), 0);
+
+        // Both feeds follow the order once it has been submitted.
+        tracker.track_submission(&submitted(11));
+        assert_eq!(
+            tracker.apply_order(&rest).unwrap().to,
+            OrderState::Acknowledged
+        );
+        let filled = tracker
+            .apply_account_order(&account_order("filled", "1", "3000"))
+            .unwrap();
+        assert_eq!(filled.to, OrderState::Filled);
+    }
+}
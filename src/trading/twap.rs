@@ -0,0 +1,572 @@
+use super::{LighterFfiTradingClient, OrderExpiry, OrderSpec, SubmittedOrder};
+use crate::error::{LighterError, Result};
+use crate::models::common::OrderType;
+use crate::models::order::TimeInForce;
+use chrono::{Duration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::*;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Times the history is checked for a slice's fill before giving up.
+const SETTLE_ATTEMPTS: u32 = 5;
+const SETTLE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+/// Closed orders searched for a slice; slices are sent one at a time.
+const SETTLE_HISTORY_LIMIT: u32 = 20;
+
+/// Parameters of a TWAP execution.
+#[derive(Debug, Clone)]
+pub struct TwapConfig {
+    pub symbol: String,
+    pub is_buy: bool,
+    pub total_amount: Decimal,
+    /// Worst price any slice may fill at.
+    pub limit_price: Decimal,
+    pub duration: Duration,
+    pub slices: u32,
+    pub reduce_only: bool,
+    /// Random spread applied to each interval, as a fraction of the nominal
+    /// interval (`0.2` gives ±20%).
+    pub interval_jitter: f64,
+    /// Random spread applied to each slice size, as a fraction of the
+    /// nominal slice.
+    pub size_jitter: f64,
+    /// Seed for the jitter, for reproducible schedules.
+    pub seed: Option<u64>,
+}
+
+impl TwapConfig {
+    pub fn new(
+        symbol: impl Into<String>,
+        is_buy: bool,
+        total_amount: Decimal,
+        limit_price: Decimal,
+        duration: Duration,
+        slices: u32,
+    ) -> Self {
+        Self {
+            symbol: symbol.into(),
+            is_buy,
+            total_amount,
+            limit_price,
+            duration,
+            slices,
+            reduce_only: false,
+            interval_jitter: 0.0,
+            size_jitter: 0.0,
+            seed: None,
+        }
+    }
+
+    pub fn with_reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    pub fn with_interval_jitter(mut self, jitter: f64) -> Self {
+        self.interval_jitter = jitter;
+        self
+    }
+
+    pub fn with_size_jitter(mut self, jitter: f64) -> Self {
+        self.size_jitter = jitter;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.total_amount <= Decimal::ZERO {
+            return Err(LighterError::OrderValidation(format!(
+                "TWAP amount {} must be positive",
+                self.total_amount
+            )));
+        }
+        if self.slices == 0 || self.duration <= Duration::zero() {
+            return Err(LighterError::OrderValidation(
+                "TWAP needs at least one slice and a positive duration".to_string(),
+            ));
+        }
+        for (name, jitter) in [
+            ("interval", self.interval_jitter),
+            ("size", self.size_jitter),
+        ] {
+            if !(0.0..1.0).contains(&jitter) {
+                return Err(LighterError::OrderValidation(format!(
+                    "TWAP {name} jitter {jitter} must be in [0, 1)"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One planned child order: wait `delay` after the previous slice, then
+/// send `amount`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TwapSlice {
+    pub delay: std::time::Duration,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwapState {
+    Running,
+    Paused,
+    /// The whole amount filled.
+    Completed,
+    /// The schedule ran out before the whole amount filled.
+    Incomplete,
+    Cancelled,
+}
+
+/// Snapshot of a running TWAP.
+#[derive(Debug, Clone)]
+pub struct TwapProgress {
+    pub state: TwapState,
+    pub slices_total: usize,
+    pub slices_sent: usize,
+    pub total_amount: Decimal,
+    /// Amount sent in accepted child orders.
+    pub submitted_amount: Decimal,
+    /// Amount the child orders filled. Children are immediate-or-cancel, so
+    /// this trails `submitted_amount`; the unfilled part of a slice is
+    /// carried into the next one.
+    pub filled_amount: Decimal,
+    pub orders: Vec<SubmittedOrder>,
+    /// Slices that failed, with the reason.
+    pub errors: Vec<(usize, String)>,
+}
+
+impl TwapProgress {
+    /// Amount still to be filled.
+    pub fn remaining_amount(&self) -> Decimal {
+        (self.total_amount - self.filled_amount).max(Decimal::ZERO)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TwapControl {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// Controls a TWAP started with [`LighterFfiTradingClient::start_twap`].
+/// Dropping the handle lets a running TWAP finish; a paused one is cancelled.
+#[derive(Debug)]
+pub struct TwapHandle {
+    control: watch::Sender<TwapControl>,
+    progress: Arc<Mutex<TwapProgress>>,
+    task: JoinHandle<()>,
+}
+
+impl TwapHandle {
+    /// Stop sending slices until resumed. Time spent paused extends the
+    /// schedule.
+    pub fn pause(&self) {
+        self.set(TwapControl::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.set(TwapControl::Run);
+    }
+
+    /// Stop sending slices. Children already sent are not cancelled.
+    pub fn cancel(&self) {
+        self.set(TwapControl::Cancel);
+    }
+
+    pub fn progress(&self) -> TwapProgress {
+        lock(&self.progress).clone()
+    }
+
+    /// Wait for the TWAP to complete or be cancelled.
+    pub async fn wait(self) -> Result<TwapProgress> {
+        self.task
+            .await
+            .map_err(|err| LighterError::Unknown(format!("TWAP task failed: {err}")))?;
+        Ok(lock(&self.progress).clone())
+    }
+
+    fn set(&self, control: TwapControl) {
+        let changed = self.control.send_if_modified(|current| {
+            if *current == TwapControl::Cancel || *current == control {
+                return false;
+            }
+            *current = control;
+            true
+        });
+        if !changed {
+            return;
+        }
+        let mut progress = lock(&self.progress);
+        if matches!(progress.state, TwapState::Running | TwapState::Paused) {
+            progress.state = match control {
+                TwapControl::Run => TwapState::Running,
+                TwapControl::Pause => TwapState::Paused,
+                TwapControl::Cancel => TwapState::Cancelled,
+            };
+        }
+    }
+}
+
+impl LighterFfiTradingClient {
+    /// Work `config.total_amount` as immediate-or-cancel limit orders spread
+    /// over `config.duration`, in the background.
+    pub async fn start_twap(self: &Arc<Self>, config: TwapConfig) -> Result<TwapHandle> {
+        config.validate()?;
+        let info = self.market(&config.symbol).await?;
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let plan = plan_slices(
+            &config,
+            info.supported_size_decimals.unwrap_or(0),
+            &mut StdRng::seed_from_u64(seed),
+        );
+
+        let progress = Arc::new(Mutex::new(TwapProgress {
+            state: TwapState::Running,
+            slices_total: plan.len(),
+            slices_sent: 0,
+            total_amount: config.total_amount,
+            submitted_amount: Decimal::ZERO,
+            filled_amount: Decimal::ZERO,
+            orders: Vec::new(),
+            errors: Vec::new(),
+        }));
+        let (control, receiver) = watch::channel(TwapControl::Run);
+        let task = tokio::spawn(run_twap(
+            Arc::clone(self),
+            config,
+            plan,
+            receiver,
+            Arc::clone(&progress),
+        ));
+
+        Ok(TwapHandle {
+            control,
+            progress,
+            task,
+        })
+    }
+
+    /// Submit a single order using Lighter's native TWAP order type, which
+    /// the exchange slices until `config.duration` elapses. Slicing and
+    /// jitter settings are ignored.
+    pub async fn create_native_twap_order(&self, config: &TwapConfig) -> Result<SubmittedOrder> {
+        config.validate()?;
+        let info = self.market(&config.symbol).await?;
+        let order_expiry =
+            OrderExpiry::After(config.duration).resolve(TimeInForce::Gtc, Utc::now())?;
+        let (amount, price) = super::conform_order(
+            &info,
+            &config.total_amount,
+            &config.limit_price,
+            Some(!config.is_buy),
+            self.rounding,
+        )?;
+        let spec = OrderSpec {
+            amount,
+            price,
+            is_ask: !config.is_buy,
+            order_type: OrderType::Twap,
+            time_in_force: TimeInForce::Gtc,
+            reduce_only: config.reduce_only,
+            trigger_price: 0,
+            order_expiry,
+        };
+        self.submit_order(&info, spec, None).await
+    }
+}
+
+async fn run_twap(
+    client: Arc<LighterFfiTradingClient>,
+    config: TwapConfig,
+    plan: Vec<TwapSlice>,
+    mut control: watch::Receiver<TwapControl>,
+    progress: Arc<Mutex<TwapProgress>>,
+) {
+    // The previous slice, settled once the next one is due so its fill is
+    // known without holding up the schedule.
+    let mut unsettled: Option<(usize, i64, Decimal)> = None;
+    let mut carry = Decimal::ZERO;
+    for (index, slice) in plan.into_iter().enumerate() {
+        if !wait_for_slot(&mut control, slice.delay).await {
+            if let Some(previous) = unsettled.take() {
+                settle_slice(&client, &config, &progress, previous).await;
+            }
+            lock(&progress).state = TwapState::Cancelled;
+            return;
+        }
+        if let Some(previous) = unsettled.take() {
+            carry += settle_slice(&client, &config, &progress, previous).await;
+        }
+
+        let amount = slice.amount + carry;
+        let result = client
+            .create_limit_order(
+                &config.symbol,
+                config.is_buy,
+                &amount,
+                &config.limit_price,
+                config.reduce_only,
+                TimeInForce::Ioc,
+            )
+            .await;
+
+        let mut progress = lock(&progress);
+        progress.slices_sent += 1;
+        match result {
+            Ok(order) => {
+                carry = Decimal::ZERO;
+                progress.submitted_amount += amount;
+                unsettled = Some((index, order.client_order_index(), amount));
+                progress.orders.push(order);
+            }
+            Err(err) => {
+                // Nothing was sent, so the whole slice moves on.
+                carry = amount;
+                progress.errors.push((index, err.to_string()));
+            }
+        }
+    }
+    if let Some(previous) = unsettled.take() {
+        settle_slice(&client, &config, &progress, previous).await;
+    }
+
+    let mut progress = lock(&progress);
+    progress.state = if progress.remaining_amount().is_zero() {
+        TwapState::Completed
+    } else {
+        TwapState::Incomplete
+    };
+}
+
+/// Record how much of a sent slice filled and return the unfilled part.
+/// A slice whose fill cannot be found is reported as an error and not
+/// carried, so the parent is never overfilled.
+async fn settle_slice(
+    client: &LighterFfiTradingClient,
+    config: &TwapConfig,
+    progress: &Mutex<TwapProgress>,
+    (index, client_order_index, amount): (usize, i64, Decimal),
+) -> Decimal {
+    let mut outcome = Err(LighterError::Unknown(
+        "order not found in the account's history".to_string(),
+    ));
+    for attempt in 0..SETTLE_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(SETTLE_RETRY_DELAY).await;
+        }
+        outcome = client
+            .inactive_orders(&config.symbol, SETTLE_HISTORY_LIMIT)
+            .await
+            .map(|orders| {
+                orders
+                    .into_iter()
+                    .find(|order| order.client_order_index == client_order_index)
+            });
+        if matches!(outcome, Ok(Some(_))) {
+            break;
+        }
+    }
+
+    let mut progress = lock(progress);
+    match outcome {
+        Ok(Some(order)) => {
+            let filled = Decimal::from_str(&order.filled_base_amount)
+                .unwrap_or_default()
+                .min(amount);
+            progress.filled_amount += filled;
+            amount - filled
+        }
+        Ok(None) => {
+            progress
+                .errors
+                .push((index, "fill of slice not found".to_string()));
+            Decimal::ZERO
+        }
+        Err(err) => {
+            progress
+                .errors
+                .push((index, format!("fill of slice unknown: {err}")));
+            Decimal::ZERO
+        }
+    }
+}
+
+/// Sleep for `delay`, holding while paused. Returns `false` if cancelled.
+async fn wait_for_slot(
+    control: &mut watch::Receiver<TwapControl>,
+    delay: std::time::Duration,
+) -> bool {
+    let mut deadline = Instant::now() + delay;
+    loop {
+        let current = *control.borrow_and_update();
+        match current {
+            TwapControl::Cancel => return false,
+            TwapControl::Pause => {
+                let paused_at = Instant::now();
+                if control.changed().await.is_err() {
+                    return false;
+                }
+                deadline += paused_at.elapsed();
+            }
+            TwapControl::Run => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => return true,
+                    changed = control.changed() => {
+                        if changed.is_err() {
+                            // Handle dropped: nobody can pause or cancel any more.
+                            tokio::time::sleep_until(deadline).await;
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Split the parent into slices, jittering sizes and intervals. The first
+/// slice goes out immediately and the last one when `duration` has elapsed.
+/// Jittered sizes are rounded down to `size_decimals` along a cumulative
+/// schedule, so the rounding remainder is spread over the slices rather than
+/// piling up in the last one.
+fn plan_slices(config: &TwapConfig, size_decimals: u32, rng: &mut impl Rng) -> Vec<TwapSlice> {
+    let slices = config.slices as usize;
+    let size_weights = jittered_weights(slices, config.size_jitter, rng);
+    let interval_weights = jittered_weights(slices.saturating_sub(1), config.interval_jitter, rng);
+    let duration = config.duration.to_std().unwrap_or_default();
+
+    let mut plan = Vec::with_capacity(slices);
+    let mut sent = Decimal::ZERO;
+    let mut cumulative_weight = 0.0;
+    for (index, weight) in size_weights.into_iter().enumerate() {
+        cumulative_weight += weight;
+        let target = if index + 1 == slices {
+            config.total_amount
+        } else {
+            (config.total_amount * Decimal::from_f64(cumulative_weight).unwrap_or(Decimal::ONE))
+                .round_dp_with_strategy(size_decimals, RoundingStrategy::ToZero)
+                .min(config.total_amount)
+        };
+        let amount = target - sent;
+        let delay = match index {
+            0 => std::time::Duration::ZERO,
+            _ => duration.mul_f64(interval_weights[index - 1]),
+        };
+        if amount <= Decimal::ZERO {
+            continue;
+        }
+        sent = target;
+        plan.push(TwapSlice { delay, amount });
+    }
+    plan
+}
+
+/// `count` weights of `1 ± jitter`, normalised to sum to one.
+fn jittered_weights(count: usize, jitter: f64, rng: &mut impl Rng) -> Vec<f64> {
+    let weights: Vec<f64> = (0..count)
+        .map(|_| 1.0 + jitter * rng.gen_range(-1.0..=1.0))
+        .collect();
+    let total: f64 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+fn lock(progress: &Mutex<TwapProgress>) -> std::sync::MutexGuard<'_, TwapProgress> {
+    progress
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TwapConfig {
+        TwapConfig::new(
+            "ETH",
+            true,
+            Decimal::from(10),
+            Decimal::from(3_100),
+            Duration::minutes(10),
+            4,
+        )
+    }
+
+    #[test]
+    fn plans_even_slices_without_jitter() {
+        let plan = plan_slices(&config(), 2, &mut StdRng::seed_from_u64(1));
+        assert_eq!(plan.len(), 4);
+        assert_eq!(plan[0].delay, std::time::Duration::ZERO);
+        assert_eq!(plan[1].delay, std::time::Duration::from_secs(200));
+        let elapsed: std::time::Duration = plan.iter().map(|slice| slice.delay).sum();
+        assert_eq!(elapsed, std::time::Duration::from_secs(600));
+        assert!(plan.iter().all(|slice| slice.amount == Decimal::new(25, 1)));
+    }
+
+    #[test]
+    fn jittered_slices_still_sum_to_total() {
+        let config = config().with_size_jitter(0.5).with_interval_jitter(0.3);
+        let plan = plan_slices(&config, 3, &mut StdRng::seed_from_u64(7));
+        let total: Decimal = plan.iter().map(|slice| slice.amount).sum();
+        assert_eq!(total, Decimal::from(10));
+        assert!(plan.iter().all(|slice| slice.amount.scale() <= 3));
+        // Every slice, the last included, is within a size step of its
+        // jittered share.
+        let weights = jittered_weights(4, 0.5, &mut StdRng::seed_from_u64(7));
+        for (slice, weight) in plan.iter().zip(weights) {
+            let share = Decimal::from(10) * Decimal::from_f64(weight).unwrap();
+            assert!((slice.amount - share).abs() <= Decimal::new(1, 3));
+        }
+
+        let elapsed: f64 = plan.iter().map(|slice| slice.delay.as_secs_f64()).sum();
+        assert!((elapsed - 600.0).abs() < 1e-6);
+        for slice in &plan[1..] {
+            assert!(slice.delay >= std::time::Duration::from_secs(100));
+            assert!(slice.delay <= std::time::Duration::from_secs(300));
+        }
+    }
+
+    #[test]
+    fn spreads_rounding_remainder() {
+        let mut config = config();
+        config.total_amount = Decimal::new(1_003, 2);
+        config.slices = 7;
+        let plan = plan_slices(&config, 2, &mut StdRng::seed_from_u64(3));
+        let total: Decimal = plan.iter().map(|slice| slice.amount).sum();
+        assert_eq!(total, config.total_amount);
+        let largest = plan.iter().map(|slice| slice.amount).max().unwrap();
+        let smallest = plan.iter().map(|slice| slice.amount).min().unwrap();
+        assert!(largest - smallest <= Decimal::new(1, 2));
+    }
+
+    #[test]
+    fn rejects_bad_config() {
+        assert!(config().with_size_jitter(1.5).validate().is_err());
+        let mut empty = config();
+        empty.slices = 0;
+        assert!(empty.validate().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pause_holds_and_cancel_stops_the_wait() {
+        let (sender, mut receiver) = watch::channel(TwapControl::Pause);
+        let waiter = tokio::spawn(async move {
+            wait_for_slot(&mut receiver, std::time::Duration::from_millis(10)).await
+        });
+        tokio::time::advance(std::time::Duration::from_millis(100)).await;
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        sender.send(TwapControl::Cancel).unwrap();
+        assert!(!waiter.await.unwrap());
+    }
+}
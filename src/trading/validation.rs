@@ -0,0 +1,186 @@
+use super::{scale_price, scale_size};
+use crate::error::{LighterError, Result};
+use crate::metadata::MarketInfo;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// How sizes and prices finer than the market's step and tick are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingPolicy {
+    /// Reject the order with [`LighterError::OrderValidation`].
+    #[default]
+    Reject,
+    /// Round the size down to the size step and the price to the passive
+    /// side of the tick (down for bids, up for asks).
+    Round,
+}
+
+/// Check an order against the market's precision and minimums and return
+/// its scaled size and price. Runs before a nonce is taken, so rejected
+/// orders never reach the signer.
+pub(super) fn conform_order(
+    info: &MarketInfo,
+    base_amount: &Decimal,
+    price: &Decimal,
+    is_ask: Option<bool>,
+    policy: RoundingPolicy,
+) -> Result<(i64, i32)> {
+    let size_decimals = info.supported_size_decimals.unwrap_or(0);
+    let price_decimals = info.supported_price_decimals.unwrap_or(0);
+
+    let price_strategy = match is_ask {
+        Some(false) => RoundingStrategy::ToZero,
+        Some(true) => RoundingStrategy::AwayFromZero,
+        None => RoundingStrategy::MidpointNearestEven,
+    };
+    let base_amount = conform_precision(
+        info,
+        "size",
+        base_amount,
+        size_decimals,
+        policy,
+        RoundingStrategy::ToZero,
+    )?;
+    let price = conform_precision(info, "price", price, price_decimals, policy, price_strategy)?;
+
+    if base_amount <= Decimal::ZERO {
+        return Err(LighterError::OrderValidation(format!(
+            "order size {base_amount} must be positive for {}",
+            info.symbol
+        )));
+    }
+    if price <= Decimal::ZERO {
+        return Err(LighterError::OrderValidation(format!(
+            "order price {price} must be positive for {}",
+            info.symbol
+        )));
+    }
+    if let Some(min_base) = info.min_base_amount {
+        if base_amount < min_base {
+            return Err(LighterError::OrderValidation(format!(
+                "order size {base_amount} is below the minimum {min_base} for {}",
+                info.symbol
+            )));
+        }
+    }
+    if let Some(min_quote) = info.min_quote_amount {
+        let notional = base_amount * price;
+        if notional < min_quote {
+            return Err(LighterError::OrderValidation(format!(
+                "order notional {notional} is below the minimum {min_quote} for {}",
+                info.symbol
+            )));
+        }
+    }
+
+    Ok((scale_size(info, &base_amount)?, scale_price(info, &price)?))
+}
+
+fn conform_precision(
+    info: &MarketInfo,
+    field: &str,
+    value: &Decimal,
+    decimals: u32,
+    policy: RoundingPolicy,
+    strategy: RoundingStrategy,
+) -> Result<Decimal> {
+    if value.normalize().scale() <= decimals {
+        return Ok(*value);
+    }
+    match policy {
+        RoundingPolicy::Reject => Err(LighterError::OrderValidation(format!(
+            "order {field} {value} exceeds {decimals} supported decimals for {}",
+            info.symbol
+        ))),
+        RoundingPolicy::Round => Ok(value.round_dp_with_strategy(decimals, strategy)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth() -> MarketInfo {
+        MarketInfo {
+            market_id: 0,
+            symbol: "ETH".to_string(),
+            supported_size_decimals: Some(4),
+            supported_price_decimals: Some(2),
+            supported_quote_decimals: Some(6),
+            min_base_amount: Some(Decimal::new(50, 4)),
+            min_quote_amount: Some(Decimal::from(10)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rejects_extra_precision_by_default() {
+        let info = eth();
+        let err = conform_order(
+            &info,
+            &Decimal::new(12_345, 5),
+            &Decimal::new(3_000, 0),
+            Some(false),
+            RoundingPolicy::Reject,
+        )
+        .unwrap_err();
+        assert!(matches!(err, LighterError::OrderValidation(_)));
+
+        assert_eq!(
+            conform_order(
+                &info,
+                &Decimal::new(1_000, 2),
+                &Decimal::new(300_000, 2),
+                Some(false),
+                RoundingPolicy::Reject,
+            )
+            .unwrap(),
+            (100_000, 300_000)
+        );
+    }
+
+    #[test]
+    fn rounds_to_the_passive_side() {
+        let info = eth();
+        let size = Decimal::new(123_456, 5); // 1.23456
+        let price = Decimal::new(3_000_125, 3); // 3000.125
+
+        let bid = conform_order(&info, &size, &price, Some(false), RoundingPolicy::Round);
+        assert_eq!(bid.unwrap(), (12_345, 300_012));
+        let ask = conform_order(&info, &size, &price, Some(true), RoundingPolicy::Round);
+        assert_eq!(ask.unwrap(), (12_345, 300_013));
+    }
+
+    #[test]
+    fn enforces_market_minimums() {
+        let info = eth();
+        let below_base = conform_order(
+            &info,
+            &Decimal::new(10, 4),
+            &Decimal::from(3_000),
+            Some(false),
+            RoundingPolicy::Reject,
+        );
+        assert!(matches!(below_base, Err(LighterError::OrderValidation(_))));
+
+        let below_quote = conform_order(
+            &info,
+            &Decimal::new(50, 4),
+            &Decimal::from(1_000),
+            Some(false),
+            RoundingPolicy::Reject,
+        );
+        assert!(matches!(below_quote, Err(LighterError::OrderValidation(_))));
+
+        let rounded_to_zero = conform_order(
+            &info,
+            &Decimal::new(1, 5),
+            &Decimal::from(3_000),
+            Some(false),
+            RoundingPolicy::Round,
+        );
+        assert!(matches!(
+            rounded_to_zero,
+            Err(LighterError::OrderValidation(_))
+        ));
+    }
+}
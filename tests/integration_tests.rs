@@ -37,7 +37,7 @@ async fn test_get_account_integration() {
 
     let config = Config::new()
         .with_api_key("test_key")
-        .with_base_url(&server.url())
+        .with_base_url(server.url())
         .unwrap();
 
     let client = LighterClient::new(
@@ -87,7 +87,7 @@ async fn test_create_order_integration() {
 
     let config = Config::new()
         .with_api_key("test_key")
-        .with_base_url(&server.url())
+        .with_base_url(server.url())
         .unwrap();
 
     let client = LighterClient::new(
@@ -149,7 +149,7 @@ async fn test_get_market_data_integration() {
         .create_async()
         .await;
 
-    let config = Config::new().with_base_url(&server.url()).unwrap();
+    let config = Config::new().with_base_url(server.url()).unwrap();
 
     let client = LighterClient::new_read_only(config).unwrap();
 
@@ -183,7 +183,7 @@ async fn test_error_handling_integration() {
 
     let config = Config::new()
         .with_api_key("test_key")
-        .with_base_url(&server.url())
+        .with_base_url(server.url())
         .unwrap();
 
     let client = LighterClient::new(
@@ -238,7 +238,7 @@ async fn test_rate_limit_handling() {
 
     let config = Config::new()
         .with_api_key("test_key")
-        .with_base_url(&server.url())
+        .with_base_url(server.url())
         .unwrap()
         .with_max_retries(0);
 
@@ -299,7 +299,7 @@ async fn test_pagination_integration() {
 
     let config = Config::new()
         .with_api_key("test_key")
-        .with_base_url(&server.url())
+        .with_base_url(server.url())
         .unwrap();
 
     let client = LighterClient::new(
@@ -328,3 +328,210 @@ async fn test_pagination_integration() {
     assert_eq!(p.total, 100);
     assert!(p.has_next);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_auth_token_attached_to_private_endpoints() {
+    use lighter_rust::{ApiClient, AuthTokenProvider};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let mut server = mockito::Server::new_async().await;
+    let private = server
+        .mock("GET", "/api/v1/accountActiveOrders")
+        .match_query(mockito::Matcher::Any)
+        .match_header("authorization", "cached-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"code": 200, "orders": []}).to_string())
+        .expect(2)
+        .create_async()
+        .await;
+    let public = server
+        .mock("GET", "/api/v1/orderBooks")
+        .match_header("authorization", "Bearer test_key")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({"order_books": []}).to_string())
+        .create_async()
+        .await;
+
+    let config = Config::new()
+        .with_api_key("test_key")
+        .with_base_url(server.url())
+        .unwrap();
+    let mints = Arc::new(AtomicUsize::new(0));
+    let minted = mints.clone();
+    let provider = Arc::new(AuthTokenProvider::with_minter(move |_| {
+        minted.fetch_add(1, Ordering::SeqCst);
+        Ok("cached-token".to_string())
+    }));
+    let client = ApiClient::new(config)
+        .unwrap()
+        .with_auth_token_provider(provider);
+
+    for _ in 0..2 {
+        let _: serde_json::Value = client
+            .get("/accountActiveOrders?account_index=1&market_id=0")
+            .await
+            .unwrap();
+    }
+    let _: serde_json::Value = client.get("/orderBooks").await.unwrap();
+
+    private.assert_async().await;
+    public.assert_async().await;
+    assert_eq!(mints.load(Ordering::SeqCst), 1);
+}
+
+async fn mock_trading_venue(server: &mut mockito::ServerGuard, nonce: u64) -> Vec<mockito::Mock> {
+    let markets = json!({
+        "code": 200,
+        "order_books": [{
+            "market_id": 0,
+            "symbol": "ETH",
+            "supported_size_decimals": 4,
+            "supported_price_decimals": 2,
+            "supported_quote_decimals": 6,
+            "min_base_amount": "0.0050"
+        }]
+    });
+    vec![
+        server
+            .mock("GET", "/api/v1/orderBooks")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(markets.to_string())
+            .create_async()
+            .await,
+        server
+            .mock("GET", "/api/v1/nextNonce")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "code": 200, "nonce": nonce }).to_string())
+            .create_async()
+            .await,
+    ]
+}
+
+// Needs the native signer library; run with --ignored where it is installed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[ignore]
+async fn test_submit_batch_signs_consecutive_nonces() {
+    use lighter_rust::trading::{BatchItemOutcome, BatchOrderRequest};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    let mut server = mockito::Server::new_async().await;
+    let _venue = mock_trading_venue(&mut server, 41).await;
+    let captured = Arc::new(Mutex::new(None::<serde_json::Value>));
+    let sink = captured.clone();
+    let batch_mock = server
+        .mock("POST", "/api/v1/sendTxBatch")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body_from_request(move |request| {
+            *sink.lock().unwrap() = serde_json::from_slice(request.body().unwrap()).ok();
+            json!({ "code": 200, "tx_hash": ["0xa", "0xb"] })
+                .to_string()
+                .into_bytes()
+        })
+        .expect(1)
+        .create_async()
+        .await;
+
+    let config = Config::new().with_base_url(server.url()).unwrap();
+    let client = lighter_rust::LighterFfiTradingClient::new(config, "0x01", 7, 3)
+        .await
+        .unwrap();
+
+    let create = |size: &str| BatchOrderRequest::Create {
+        symbol: "ETH".to_string(),
+        is_buy: true,
+        base_amount: Decimal::from_str(size).unwrap(),
+        limit_price: Decimal::from_str("2500.00").unwrap(),
+        reduce_only: false,
+        time_in_force: lighter_rust::TimeInForce::Gtc,
+        client_order_id: None,
+    };
+    let outcome = client
+        .submit_batch(vec![
+            create("0.0100"),
+            // Finer than the market's size step, so it is never signed.
+            create("0.01005"),
+            BatchOrderRequest::Cancel {
+                symbol: "ETH".to_string(),
+                order_id: "12345".to_string(),
+            },
+        ])
+        .await
+        .unwrap();
+
+    batch_mock.assert_async().await;
+    assert!(!outcome.is_complete());
+    assert!(matches!(
+        &outcome.items[0],
+        BatchItemOutcome::Submitted { tx_hash: Some(hash) } if hash == "0xa"
+    ));
+    assert!(matches!(&outcome.items[1], BatchItemOutcome::Rejected(_)));
+    assert!(matches!(
+        &outcome.items[2],
+        BatchItemOutcome::Submitted { tx_hash: Some(hash) } if hash == "0xb"
+    ));
+    assert_eq!(
+        outcome
+            .failures()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+        vec![1]
+    );
+
+    // Both fields are JSON documents encoded as strings, and the rejected
+    // item did not consume a nonce.
+    let body = captured.lock().unwrap().take().unwrap();
+    let tx_types: Vec<i32> = serde_json::from_str(body["tx_types"].as_str().unwrap()).unwrap();
+    assert_eq!(tx_types, vec![14, 15]);
+    let tx_infos: Vec<String> = serde_json::from_str(body["tx_infos"].as_str().unwrap()).unwrap();
+    let nonces: Vec<i64> = tx_infos
+        .iter()
+        .map(|info| {
+            let info: serde_json::Value = serde_json::from_str(info).unwrap();
+            info["Nonce"].as_i64().unwrap()
+        })
+        .collect();
+    assert_eq!(nonces, vec![41, 42]);
+}
+
+// Needs the native signer library; run with --ignored where it is installed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[ignore]
+async fn test_submit_batch_surfaces_exchange_rejection() {
+    use lighter_rust::trading::BatchOrderRequest;
+
+    let mut server = mockito::Server::new_async().await;
+    let _venue = mock_trading_venue(&mut server, 41).await;
+    let _batch = server
+        .mock("POST", "/api/v1/sendTxBatch")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({ "code": 21120, "message": "invalid nonce", "tx_hash": [] }).to_string())
+        .create_async()
+        .await;
+
+    let config = Config::new().with_base_url(server.url()).unwrap();
+    let client = lighter_rust::LighterFfiTradingClient::new(config, "0x01", 7, 3)
+        .await
+        .unwrap();
+
+    let err = client
+        .submit_batch(vec![BatchOrderRequest::Cancel {
+            symbol: "ETH".to_string(),
+            order_id: "12345".to_string(),
+        }])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        lighter_rust::LighterError::Api { status: 21120, ref message } if message == "invalid nonce"
+    ));
+}